@@ -1,7 +1,7 @@
 use std::env;
 
 use fallible_iterator::FallibleIterator;
-use sqlite3_parser::lexer::sql::Parser;
+use sqlite3_parser::lexer::sql::{Diagnostic, Parser};
 
 /// Parse args.
 // RUST_LOG=sqlite3Parser=debug
@@ -14,7 +14,7 @@ fn main() {
             match parser.next() {
                 Ok(None) => break,
                 Err(err) => {
-                    eprintln!("Err: {err} in {arg}");
+                    eprint!("{}", Diagnostic::new(&err, arg.as_bytes()));
                     break;
                 }
                 Ok(Some(cmd)) => {
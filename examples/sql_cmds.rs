@@ -3,11 +3,9 @@ use std::env;
 use std::fs::read;
 use std::panic;
 
-#[cfg(not(feature = "YYNOERRORRECOVERY"))]
-use sqlite3_parser::lexer::sql::Error;
 use sqlite3_parser::lexer::sql::Parser;
 
-/// Parse specified files and print all commands.
+/// Parse specified files and print all commands and errors.
 fn main() {
     env_logger::init();
     let args = env::args();
@@ -20,14 +18,8 @@ fn main() {
                 match parser.next() {
                     Ok(None) => break,
                     Err(err) => {
+                        // the parser resumes at the next statement
                         eprintln!("Err: {err} in {arg}");
-                        #[cfg(feature = "YYNOERRORRECOVERY")]
-                        break;
-                        #[cfg(not(feature = "YYNOERRORRECOVERY"))]
-                        if let Error::ParserError(..) = err {
-                        } else {
-                            break;
-                        }
                     }
                     Ok(Some(cmd)) => {
                         println!("{cmd}");
@@ -0,0 +1,30 @@
+//! Parse arbitrary input: no panic, and each command parsed must be
+//! formatted to SQL which parses back to the same command.
+//!
+//! `cargo +nightly fuzz run parse`
+#![no_main]
+
+use fallible_iterator::FallibleIterator;
+use libfuzzer_sys::fuzz_target;
+use sqlite3_parser::lexer::sql::Parser;
+
+fuzz_target!(|data: &[u8]| {
+    let mut parser = Parser::new(data);
+    loop {
+        match parser.next() {
+            Ok(Some(cmd)) => {
+                let sql = cmd.to_string();
+                match Parser::new(sql.as_bytes()).next() {
+                    Ok(Some(reparsed)) => assert_eq!(cmd, reparsed, "{sql}"),
+                    Ok(None) => panic!("{sql}: no command"),
+                    Err(err) => panic!("{sql}: {err}"),
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                // the parser resumes after the invalid statement
+                let _ = err.to_string();
+            }
+        }
+    }
+});
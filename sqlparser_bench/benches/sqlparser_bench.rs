@@ -42,6 +42,12 @@ fn basic_queries(c: &mut Criterion) {
             assert!(parser.next().unwrap().unwrap().readonly())
         });
     });
+    group.bench_with_input("sqlite3_parser::with_select_info", &with_query, |b, &s| {
+        b.iter(|| {
+            let mut parser = Parser::new(s);
+            assert!(parser.next_info().unwrap().unwrap().readonly)
+        });
+    });
 }
 
 criterion_group!(benches, basic_queries);
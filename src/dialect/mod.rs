@@ -1,5 +1,6 @@
 //! SQLite dialect
 
+use std::borrow::Cow;
 use std::fmt::Formatter;
 use std::str;
 use uncased::UncasedStr;
@@ -37,8 +38,10 @@ impl TokenType {
     }
 }
 
+/// Invalid UTF-8 sequences are replaced by `U+FFFD`
+/// (the tokenizer rejects them unless in lossy mode)
 pub(crate) fn from_bytes(bytes: &[u8]) -> String {
-    unsafe { str::from_utf8_unchecked(bytes).to_owned() }
+    String::from_utf8_lossy(bytes).into_owned()
 }
 
 include!(concat!(env!("OUT_DIR"), "/keywords.rs"));
@@ -46,9 +49,8 @@ pub(crate) const MAX_KEYWORD_LEN: usize = 17;
 
 /// Check if `word` is a keyword
 pub fn keyword_token(word: &[u8]) -> Option<TokenType> {
-    KEYWORDS
-        .get(UncasedStr::new(unsafe { str::from_utf8_unchecked(word) }))
-        .copied()
+    let word = str::from_utf8(word).ok()?;
+    KEYWORDS.get(UncasedStr::new(word)).copied()
 }
 
 pub(crate) fn is_identifier(name: &str) -> bool {
@@ -75,8 +77,9 @@ pub(crate) fn is_identifier_continue(b: u8) -> bool {
 
 // keyword may become an identifier
 // see %fallback in parse.y
-pub(crate) fn from_token(_ty: u16, value: Token) -> String {
-    from_bytes(value.1)
+pub(crate) fn from_token(_ty: u16, value: Token<'_>) -> Cow<'_, str> {
+    // borrowed unless invalid UTF-8 sequences are replaced (lossy mode)
+    String::from_utf8_lossy(value.1)
 }
 
 impl TokenType {
@@ -243,3 +246,69 @@ impl TokenType {
         }
     }
 }
+
+/// Lexical category of a token, for syntax highlighting
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenCategory {
+    /// Keyword
+    Keyword,
+    /// Identifier, quoted or not
+    Identifier,
+    /// String literal
+    String,
+    /// Integer or float literal
+    Number,
+    /// BLOB literal
+    Blob,
+    /// Parameter
+    Variable,
+    /// Operator
+    Operator,
+    /// `(`, `)`, `,`, `.` or `;`
+    Punctuation,
+    /// Comment
+    Comment,
+    /// White spaces (and end of input)
+    Whitespace,
+}
+
+impl TokenCategory {
+    /// Lowercase name, like `keyword`
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Keyword => "keyword",
+            Self::Identifier => "identifier",
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Blob => "blob",
+            Self::Variable => "variable",
+            Self::Operator => "operator",
+            Self::Punctuation => "punctuation",
+            Self::Comment => "comment",
+            Self::Whitespace => "whitespace",
+        }
+    }
+}
+
+impl TokenType {
+    /// Category of the token, regardless of its context: keywords which are
+    /// used as identifiers are not detected here
+    /// (see [`token_categories`](crate::lexer::sql::token_categories)).
+    pub const fn category(self) -> TokenCategory {
+        use TokenType::*;
+        match self {
+            TK_ID => TokenCategory::Identifier,
+            TK_STRING => TokenCategory::String,
+            TK_INTEGER | TK_FLOAT => TokenCategory::Number,
+            TK_BLOB => TokenCategory::Blob,
+            TK_VARIABLE => TokenCategory::Variable,
+            TK_BITAND | TK_BITNOT | TK_BITOR | TK_CONCAT | TK_EQ | TK_GE | TK_GT | TK_LE
+            | TK_LSHIFT | TK_LT | TK_MINUS | TK_NE | TK_PLUS | TK_PTR | TK_REM | TK_RSHIFT
+            | TK_SLASH | TK_STAR => TokenCategory::Operator,
+            TK_COMMA | TK_DOT | TK_LP | TK_RP | TK_SEMI => TokenCategory::Punctuation,
+            TK_COMMENT => TokenCategory::Comment,
+            TK_SPACE | TK_EOF => TokenCategory::Whitespace,
+            _ => TokenCategory::Keyword,
+        }
+    }
+}
@@ -1,14 +1,36 @@
 //! All terminal symbols.
 
-/// Token classes
-// Generated by lemon (parse.h).
-// Renamed manually.
-// To be keep in sync.
-#[non_exhaustive]
-#[allow(non_camel_case_types, missing_docs)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd)]
-#[repr(u16)]
-pub enum TokenType {
+/// Define `TokenType` and its conversion from the code used by the parser,
+/// so that both are generated from the same list
+macro_rules! token_types {
+    ($($name:ident = $code:literal,)*) => {
+        /// Token classes
+        // Generated by lemon (parse.h).
+        // Renamed manually.
+        // To be keep in sync.
+        #[non_exhaustive]
+        #[allow(non_camel_case_types, missing_docs)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd)]
+        #[repr(u16)]
+        pub enum TokenType {
+            $($name = $code,)*
+        }
+
+        /// Token type from the code used by the parser
+        impl TryFrom<u16> for TokenType {
+            type Error = u16;
+
+            fn try_from(code: u16) -> Result<Self, u16> {
+                match code {
+                    $($code => Ok(Self::$name),)*
+                    _ => Err(code),
+                }
+            }
+        }
+    };
+}
+
+token_types! {
     TK_EOF = 0,
     TK_SEMI = 1,
     TK_EXPLAIN = 2,
@@ -177,4 +199,13 @@ pub enum TokenType {
     TK_WINDOW = 165,
     TK_OVER = 166,
     TK_FILTER = 167,
+    // Not used by the grammar, only by the tokenizer
+    TK_SPACE = 168,
+    TK_COMMENT = 169,
+    // Not produced by the tokenizer, only fed by the parser before a fragment
+    TK_FRAGMENT_EXPR = 170,
+    TK_FRAGMENT_TYPE = 171,
+    TK_FRAGMENT_COLUMN_DEF = 172,
+    TK_FRAGMENT_SORTED_COLUMNS = 173,
+    TK_FRAGMENT_QUALIFIED_NAME = 174,
 }
@@ -0,0 +1,217 @@
+//! Conversion of byte offsets into line and column numbers
+
+use memchr::memchr_iter;
+
+/// Unit of column numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// UTF-8 bytes, like [`Scanner::column`](super::Scanner::column)
+    Byte,
+    /// Unicode scalar values
+    Char,
+    /// UTF-16 code units, like LSP clients by default
+    Utf16,
+}
+
+/// Non-ASCII character, or invalid UTF-8 sequence (which counts as one
+/// `U+FFFD` character)
+#[derive(Clone, Copy, Debug)]
+struct WideChar {
+    /// byte offset of the first byte
+    start: usize,
+    /// length in bytes
+    len: u8,
+    /// length in UTF-16 code units
+    utf16: u8,
+    /// bytes minus chars, up to the end of this character
+    extra_chars: usize,
+    /// bytes minus UTF-16 code units, up to the end of this character
+    extra_utf16: usize,
+}
+
+/// Start of each line of an input, to convert byte offsets into line and
+/// column numbers (both 1-based) in bytes, chars or UTF-16 code units,
+/// and back.
+///
+/// Conversions are done by binary search, without scanning the input
+/// again. Byte offsets are expected at character boundaries.
+///
+/// ```rust
+/// use sqlite3_parser::lexer::{ColumnUnit, LineIndex};
+///
+/// let input = "SELECT 1;\nSELECT 'é😀', x;".as_bytes();
+/// let index = LineIndex::new(input);
+/// let x = input.len() - 2;
+/// assert_eq!(index.line_col(x, ColumnUnit::Byte), (2, 18));
+/// assert_eq!(index.line_col(x, ColumnUnit::Char), (2, 14));
+/// assert_eq!(index.line_col(x, ColumnUnit::Utf16), (2, 15));
+/// assert_eq!(index.offset(2, 15, ColumnUnit::Utf16), Some(x));
+/// ```
+#[derive(Clone, Debug)]
+pub struct LineIndex {
+    /// byte offset of the start of each line
+    line_starts: Vec<usize>,
+    /// non-ASCII characters, in input order
+    wide_chars: Vec<WideChar>,
+    /// input length in bytes
+    len: usize,
+}
+
+impl LineIndex {
+    /// Index `input`
+    pub fn new(input: &[u8]) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(memchr_iter(b'\n', input).map(|i| i + 1));
+        let mut wide_chars = Vec::new();
+        let (mut extra_chars, mut extra_utf16) = (0, 0);
+        let mut push = |start: usize, len: usize, utf16: usize| {
+            extra_chars += len - 1;
+            extra_utf16 += len - utf16;
+            wide_chars.push(WideChar {
+                start,
+                len: len as u8,
+                utf16: utf16 as u8,
+                extra_chars,
+                extra_utf16,
+            });
+        };
+        let mut offset = 0;
+        for chunk in input.utf8_chunks() {
+            let valid = chunk.valid();
+            if !valid.is_ascii() {
+                for (i, c) in valid.char_indices().filter(|(_, c)| !c.is_ascii()) {
+                    push(offset + i, c.len_utf8(), c.len_utf16());
+                }
+            }
+            offset += valid.len();
+            let invalid = chunk.invalid();
+            if !invalid.is_empty() {
+                push(offset, invalid.len(), 1);
+                offset += invalid.len();
+            }
+        }
+        Self {
+            line_starts,
+            wide_chars,
+            len: input.len(),
+        }
+    }
+    /// Number of lines
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+    /// Byte offset where `line` (1-based) starts
+    pub fn line_start(&self, line: u64) -> Option<usize> {
+        let line = usize::try_from(line).ok()?.checked_sub(1)?;
+        self.line_starts.get(line).copied()
+    }
+    /// Line and column (both 1-based) of byte `offset`
+    pub fn line_col(&self, offset: usize, unit: ColumnUnit) -> (u64, usize) {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let start = self.line_starts[line - 1];
+        let column = offset - start - (self.extra(offset, unit) - self.extra(start, unit));
+        (line as u64, column + 1)
+    }
+    /// Byte offset of `line` and `column` (both 1-based), or `None` when out
+    /// of the line (the column just past the last character is valid) or
+    /// inside a character
+    pub fn offset(&self, line: u64, column: usize, unit: ColumnUnit) -> Option<usize> {
+        let start = self.line_start(line)?;
+        // the new line is not part of the line
+        let end = match self.line_starts.get(line as usize) {
+            Some(next) => next - 1,
+            None => self.len,
+        };
+        let mut remaining = column.checked_sub(1)?;
+        let mut offset = start;
+        let first = self.wide_chars.partition_point(|wc| wc.start < start);
+        for wc in self.wide_chars[first..]
+            .iter()
+            .take_while(|wc| wc.start < end)
+        {
+            let gap = wc.start - offset;
+            if remaining <= gap {
+                return Some(offset + remaining);
+            }
+            remaining -= gap;
+            let width = match unit {
+                ColumnUnit::Byte => wc.len,
+                ColumnUnit::Char => 1,
+                ColumnUnit::Utf16 => wc.utf16,
+            } as usize;
+            if remaining < width {
+                return None;
+            }
+            remaining -= width;
+            offset = wc.start + wc.len as usize;
+        }
+        let offset = offset + remaining;
+        (offset <= end).then_some(offset)
+    }
+    /// Bytes minus `unit`s before byte `offset`
+    fn extra(&self, offset: usize, unit: ColumnUnit) -> usize {
+        let i = self
+            .wide_chars
+            .partition_point(|wc| wc.start + wc.len as usize <= offset);
+        match (unit, i.checked_sub(1).map(|i| &self.wide_chars[i])) {
+            (ColumnUnit::Byte, _) | (_, None) => 0,
+            (ColumnUnit::Char, Some(wc)) => wc.extra_chars,
+            (ColumnUnit::Utf16, Some(wc)) => wc.extra_utf16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColumnUnit, LineIndex};
+
+    #[test]
+    fn line_col() {
+        let input = "é\n\nab😀c\r\nx".as_bytes();
+        let index = LineIndex::new(input);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_start(3), Some(4));
+        assert_eq!(index.line_start(0), None);
+        assert_eq!(index.line_start(5), None);
+        for (offset, byte, char, utf16) in [
+            (0, (1, 1), (1, 1), (1, 1)),
+            (2, (1, 3), (1, 2), (1, 2)),
+            (3, (2, 1), (2, 1), (2, 1)),
+            (6, (3, 3), (3, 3), (3, 3)),
+            (10, (3, 7), (3, 4), (3, 5)),
+            (13, (4, 1), (4, 1), (4, 1)),
+            (14, (4, 2), (4, 2), (4, 2)),
+            (99, (4, 2), (4, 2), (4, 2)),
+        ] {
+            assert_eq!(index.line_col(offset, ColumnUnit::Byte), byte, "{offset}");
+            assert_eq!(index.line_col(offset, ColumnUnit::Char), char, "{offset}");
+            assert_eq!(index.line_col(offset, ColumnUnit::Utf16), utf16, "{offset}");
+            let offset = offset.min(input.len());
+            assert_eq!(index.offset(byte.0, byte.1, ColumnUnit::Byte), Some(offset));
+            assert_eq!(index.offset(char.0, char.1, ColumnUnit::Char), Some(offset));
+            assert_eq!(
+                index.offset(utf16.0, utf16.1, ColumnUnit::Utf16),
+                Some(offset)
+            );
+        }
+        // inside `😀`
+        assert_eq!(index.offset(3, 4, ColumnUnit::Utf16), None);
+        assert_eq!(index.offset(3, 4, ColumnUnit::Byte), None);
+        // past the end of the line
+        assert_eq!(index.offset(2, 2, ColumnUnit::Char), None);
+        assert_eq!(index.offset(3, 7, ColumnUnit::Char), None);
+        assert_eq!(index.offset(3, 0, ColumnUnit::Char), None);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        // like `String::from_utf8_lossy`: one `U+FFFD` per invalid sequence
+        let input = b"a\xF0\x9F\xFFb";
+        let index = LineIndex::new(input);
+        assert_eq!(String::from_utf8_lossy(input), "a\u{FFFD}\u{FFFD}b");
+        assert_eq!(index.line_col(4, ColumnUnit::Char), (1, 4));
+        assert_eq!(index.line_col(4, ColumnUnit::Utf16), (1, 4));
+        assert_eq!(index.offset(1, 4, ColumnUnit::Char), Some(4));
+    }
+}
@@ -1,6 +1,8 @@
 //! Streaming SQLite tokenizer
 
+mod line_index;
 mod scan;
 pub mod sql;
 
+pub use line_index::{ColumnUnit, LineIndex};
 pub use scan::{ScanError, Scanner, Splitter};
@@ -6,10 +6,13 @@ use std::error::Error;
 use std::fmt;
 use std::io;
 
+use super::{ColumnUnit, LineIndex};
+
 /// Error with position
 pub trait ScanError: Error + From<io::Error> + Sized {
-    /// Update the position where the error occurs
-    fn position(&mut self, line: u64, column: usize);
+    /// Update the position where the error occurs:
+    /// byte `offset` in the input, `line` and `column`
+    fn position(&mut self, offset: usize, line: u64, column: usize);
 }
 
 /// The `(&[u8], TokenType)` is the token.
@@ -56,6 +59,8 @@ pub struct Scanner<S: Splitter> {
     line: u64,
     /// current column number (byte offset, not char offset)
     column: usize,
+    /// when set, `line` and `column` are not tracked but computed
+    line_index: Option<LineIndex>,
 }
 
 impl<S: Splitter> Scanner<S> {
@@ -67,22 +72,64 @@ impl<S: Splitter> Scanner<S> {
             splitter,
             line: 1,
             column: 1,
+            line_index: None,
         }
     }
+    /// Compute line and column numbers with `index` of the input, instead of
+    /// counting new lines while scanning.
+    ///
+    /// ```rust
+    /// use sqlite3_parser::lexer::sql::Tokenizer;
+    /// use sqlite3_parser::lexer::{LineIndex, Scanner};
+    ///
+    /// let input = b"SELECT\n  1";
+    /// let mut s = Scanner::new(Tokenizer::new()).with_line_index(LineIndex::new(input));
+    /// s.scan(input).unwrap();
+    /// s.scan(input).unwrap();
+    /// assert_eq!((s.line(), s.column()), (2, 4));
+    /// ```
+    pub fn with_line_index(mut self, index: LineIndex) -> Self {
+        self.line_index = Some(index);
+        self
+    }
+    /// Index of the input, if any
+    pub fn line_index(&self) -> Option<&LineIndex> {
+        self.line_index.as_ref()
+    }
+    /// Remove the index of the input, and count new lines while scanning
+    /// from the current position
+    pub fn take_line_index(&mut self) -> Option<LineIndex> {
+        (self.line, self.column) = (self.line(), self.column());
+        self.line_index.take()
+    }
 
+    /// Current byte offset in input
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
     /// Current line number
     pub fn line(&self) -> u64 {
-        self.line
+        match self.line_index {
+            Some(ref index) => index.line_col(self.offset, ColumnUnit::Byte).0,
+            None => self.line,
+        }
     }
 
     /// Current column number (byte offset, not char offset)
     pub fn column(&self) -> usize {
-        self.column
+        match self.line_index {
+            Some(ref index) => index.line_col(self.offset, ColumnUnit::Byte).1,
+            None => self.column,
+        }
     }
     /// Associated splitter
     pub fn splitter(&self) -> &S {
         &self.splitter
     }
+    /// Associated splitter, mutably
+    pub fn splitter_mut(&mut self) -> &mut S {
+        &mut self.splitter
+    }
     /// Mark current position
     pub fn mark(&mut self) {
         self.mark = (self.offset, self.line, self.column);
@@ -92,6 +139,15 @@ impl<S: Splitter> Scanner<S> {
         (self.offset, self.line, self.column) = self.mark;
     }
 
+    /// Skip `input` up to byte `offset`, if not already past it.
+    pub fn skip_to(&mut self, input: &[u8], offset: usize) {
+        let offset = offset.min(input.len());
+        if offset > self.offset {
+            let data = &input[self.offset..];
+            self.consume(data, offset - self.offset);
+        }
+    }
+
     /// Reset the scanner such that it behaves as if it had never been used.
     pub fn reset(&mut self) {
         self.offset = 0;
@@ -120,7 +176,7 @@ impl<S: Splitter> Scanner<S> {
                 let data = &input[self.offset..];
                 match self.splitter.split(data) {
                     Err(mut e) => {
-                        e.position(self.line, self.column);
+                        e.position(self.offset, self.line(), self.column());
                         return Err(e);
                     }
                     Ok((None, 0)) => {
@@ -148,6 +204,10 @@ impl<S: Splitter> Scanner<S> {
     fn consume(&mut self, data: &[u8], amt: usize) {
         debug!(target: "scanner", "consume({})", amt);
         debug_assert!(amt <= data.len());
+        if self.line_index.is_some() {
+            self.offset += amt;
+            return;
+        }
         for byte in &data[..amt] {
             if *byte == b'\n' {
                 self.line += 1;
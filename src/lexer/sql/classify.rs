@@ -0,0 +1,213 @@
+//! Classification of statements, without building the AST
+use super::{Error, Parser, TokenType};
+use crate::custom_err;
+use crate::dialect::{sentinel, Token, TokenType::*};
+use crate::parser::ast::check::pragma_readonly;
+use crate::parser::ast::fmt::TokenStream;
+use crate::parser::ast::{Name, ParameterInfo, StmtKind};
+use crate::parser::parse::{yySimulator, YYCODETYPE};
+
+/// Statement summary returned by [`Parser::next_info`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StmtInfo {
+    /// Kind of statement
+    pub kind: StmtKind,
+    /// Like `sqlite3_stmt_isexplain`
+    pub is_explain: bool,
+    /// Like `sqlite3_stmt_readonly`, as [`Stmt::readonly`](crate::ast::Stmt::readonly)
+    /// decides it
+    pub readonly: bool,
+    /// Like `sqlite3_bind_parameter_count`
+    pub param_count: u32,
+}
+
+impl Parser<'_> {
+    /// Check the syntax of the next statement and classify it, without
+    /// building its AST.
+    ///
+    /// Only the grammar is checked: errors reported by the grammar actions
+    /// or by [`Cmd::check`](crate::ast::Cmd::check) (like duplicate column
+    /// names) are not.
+    /// Like [`FallibleIterator::next`](fallible_iterator::FallibleIterator::next),
+    /// after an error, the rest of the broken statement is skipped.
+    ///
+    /// Return `None` at the end of the input.
+    ///
+    /// ```rust
+    /// use sqlite3_parser::ast::StmtKind;
+    /// use sqlite3_parser::lexer::sql::Parser;
+    ///
+    /// let mut parser = Parser::new(b"EXPLAIN WITH x AS (SELECT ?) DELETE FROM t WHERE a = :a; BEGIN");
+    /// let info = parser.next_info().unwrap().unwrap();
+    /// assert_eq!(info.kind, StmtKind::Delete);
+    /// assert!(info.is_explain);
+    /// assert!(!info.readonly);
+    /// assert_eq!(info.param_count, 2);
+    /// assert_eq!(parser.next_info().unwrap().unwrap().kind, StmtKind::Begin);
+    /// assert!(parser.next_info().unwrap().is_none());
+    /// ```
+    pub fn next_info(&mut self) -> Result<Option<StmtInfo>, Error> {
+        let start = self.scanner.offset();
+        self.classify().inspect_err(|err| {
+            let error_end = err
+                .position()
+                .map_or(0, |pos| pos.end.saturating_sub(self.offset));
+            self.skip_statement(start, error_end.max(self.scanner.offset()));
+        })
+    }
+
+    fn classify(&mut self) -> Result<Option<StmtInfo>, Error> {
+        let mut simulator = yySimulator::new();
+        let mut classifier = Classifier::default();
+        let mut params = ParameterInfo::default();
+        let mut last_token_parsed = TK_EOF;
+        loop {
+            let offset = self.offset;
+            let next = self.next_token(last_token_parsed).map_err(|mut err| {
+                err.adjust_offset(offset);
+                err
+            })?;
+            let Some((token_type, token)) = next else {
+                break;
+            };
+            self.simulate(&mut simulator, token_type, token)?;
+            last_token_parsed = token_type;
+            if token_type == TK_VARIABLE {
+                // like `ExprKind::Variable` formatting: `?` is not in the token
+                let variable = String::from_utf8_lossy(token.1);
+                let variable = match token.1.first() {
+                    Some(b'$' | b'@' | b'#' | b':') => variable,
+                    _ => format!("?{variable}").into(),
+                };
+                if let Err(err) = params.append(TK_VARIABLE, Some(&variable)) {
+                    let pos = self.error_position(token.0, token.2);
+                    return Err(Error::ParserError(custom_err!("{}", err), Some(pos)));
+                }
+            }
+            if token_type != TK_SEMI {
+                classifier.next(token_type, token.1);
+            } else if classifier.kind.is_some() && simulator.yy_accepts_all(&[TK_EOF as YYCODETYPE])
+            {
+                return Ok(classifier.info(params.count));
+            }
+        }
+        if last_token_parsed == TK_EOF {
+            return Ok(None); // empty input
+        }
+        /* Upon reaching the end of input, simulate the parse of TK_SEMI and 0 */
+        let token = sentinel(self.offset + self.input.len());
+        if last_token_parsed != TK_SEMI {
+            self.simulate(&mut simulator, TK_SEMI, token)?;
+        }
+        self.simulate(&mut simulator, TK_EOF, token)?;
+        Ok(classifier.info(params.count))
+    }
+
+    fn simulate(
+        &self,
+        simulator: &mut yySimulator,
+        token_type: TokenType,
+        token: Token<'_>,
+    ) -> Result<(), Error> {
+        self.check_token(token_type, &token)?;
+        if simulator.yy_parse(token_type as YYCODETYPE) {
+            return Ok(());
+        }
+        let at_end = token.1.is_empty();
+        Err(self.syntax_error(token_type, token, at_end, simulator.yy_expected_tokens()))
+    }
+}
+
+/// Find the kind of statement from its first tokens
+#[derive(Default)]
+struct Classifier {
+    explain: bool,
+    /// first keyword, when not enough to find the kind
+    prefix: Option<TokenType>,
+    /// parentheses depth after `WITH`
+    depth: usize,
+    kind: Option<StmtKind>,
+    /// `PRAGMA` name, without its schema name
+    pragma_name: String,
+    /// whether the `PRAGMA` has a value
+    pragma_value: bool,
+}
+
+impl Classifier {
+    fn next(&mut self, token_type: TokenType, value: &[u8]) {
+        if self.kind == Some(StmtKind::Pragma) {
+            match token_type {
+                TK_EQ | TK_LP => self.pragma_value = true,
+                TK_DOT => {}
+                _ if !self.pragma_value => {
+                    let name = Name::new(String::from_utf8_lossy(value));
+                    self.pragma_name = name.unquoted().into_owned();
+                }
+                _ => {}
+            }
+            return;
+        }
+        if self.kind.is_some() {
+            return;
+        }
+        self.kind = match (self.prefix, token_type) {
+            (None, TK_EXPLAIN) if !self.explain => {
+                self.explain = true;
+                None
+            }
+            (None, TK_QUERY | TK_PLAN) if self.explain => None,
+            (None, TK_ALTER) => Some(StmtKind::AlterTable),
+            (None, TK_ANALYZE) => Some(StmtKind::Analyze),
+            (None, TK_ATTACH) => Some(StmtKind::Attach),
+            (None, TK_BEGIN) => Some(StmtKind::Begin),
+            (None, TK_COMMIT | TK_END) => Some(StmtKind::Commit),
+            (None, TK_DETACH) => Some(StmtKind::Detach),
+            (None, TK_PRAGMA) => Some(StmtKind::Pragma),
+            (None, TK_REINDEX) => Some(StmtKind::Reindex),
+            (None, TK_RELEASE) => Some(StmtKind::Release),
+            (None, TK_ROLLBACK) => Some(StmtKind::Rollback),
+            (None, TK_SAVEPOINT) => Some(StmtKind::Savepoint),
+            (None, TK_VACUUM) => Some(StmtKind::Vacuum),
+            (None, TK_CREATE | TK_DROP | TK_WITH) => {
+                self.prefix = Some(token_type);
+                None
+            }
+            (Some(TK_CREATE), TK_TEMP | TK_UNIQUE) => None,
+            (Some(TK_CREATE), TK_INDEX) => Some(StmtKind::CreateIndex),
+            (Some(TK_CREATE), TK_TABLE) => Some(StmtKind::CreateTable),
+            (Some(TK_CREATE), TK_TRIGGER) => Some(StmtKind::CreateTrigger),
+            (Some(TK_CREATE), TK_VIEW) => Some(StmtKind::CreateView),
+            (Some(TK_CREATE), TK_VIRTUAL) => Some(StmtKind::CreateVirtualTable),
+            (Some(TK_DROP), TK_INDEX) => Some(StmtKind::DropIndex),
+            (Some(TK_DROP), TK_TABLE) => Some(StmtKind::DropTable),
+            (Some(TK_DROP), TK_TRIGGER) => Some(StmtKind::DropTrigger),
+            (Some(TK_DROP), TK_VIEW) => Some(StmtKind::DropView),
+            (Some(TK_WITH), TK_LP) => {
+                self.depth += 1;
+                None
+            }
+            (Some(TK_WITH), TK_RP) => {
+                self.depth = self.depth.saturating_sub(1);
+                None
+            }
+            (Some(TK_WITH), _) if self.depth > 0 => None,
+            (None | Some(TK_WITH), TK_DELETE) => Some(StmtKind::Delete),
+            (None | Some(TK_WITH), TK_INSERT | TK_REPLACE) => Some(StmtKind::Insert),
+            (None | Some(TK_WITH), TK_SELECT | TK_VALUES) => Some(StmtKind::Select),
+            (None | Some(TK_WITH), TK_UPDATE) => Some(StmtKind::Update),
+            _ => None,
+        };
+    }
+
+    fn info(&self, param_count: u32) -> Option<StmtInfo> {
+        self.kind.map(|kind| StmtInfo {
+            kind,
+            is_explain: self.explain,
+            readonly: match kind {
+                StmtKind::Pragma => pragma_readonly(&self.pragma_name, self.pragma_value),
+                _ => kind.readonly(),
+            },
+            param_count,
+        })
+    }
+}
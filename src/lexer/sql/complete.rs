@@ -0,0 +1,319 @@
+//! Detection of statement boundaries, like `sqlite3_complete`
+use memchr::memchr;
+
+use super::{Error, Tokenizer};
+use crate::dialect::TokenType::{self, *};
+use crate::lexer::scan::Splitter;
+
+/// Like [`sqlite3_complete`](https://sqlite.org/c3ref/complete.html):
+/// whether `sql` ends with a complete SQL statement, that is, with a `;`
+/// which is not inside a string literal, a comment or the body of a
+/// `CREATE TRIGGER`.
+///
+/// ```rust
+/// use sqlite3_parser::lexer::sql::is_complete;
+///
+/// assert!(is_complete(b"SELECT 1; -- done"));
+/// assert!(!is_complete(b"SELECT ';"));
+/// assert!(!is_complete(b"CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1;"));
+/// ```
+pub fn is_complete(sql: &[u8]) -> bool {
+    let mut checker = CompleteChecker::new();
+    checker.feed(sql);
+    checker.is_complete()
+}
+
+/// Incremental version of [`is_complete`], fed line by line.
+///
+/// Only the last, possibly unfinished, token is kept between two calls,
+/// and a literal or a comment spanning several lines is resumed where it
+/// stopped, so input is scanned only once.
+///
+/// ```rust
+/// use sqlite3_parser::lexer::sql::CompleteChecker;
+///
+/// let mut checker = CompleteChecker::new();
+/// assert!(!checker.feed(b"SELECT 'a;\n"));
+/// assert!(!checker.feed(b"b'\n"));
+/// assert!(checker.feed(b";\n"));
+/// ```
+#[derive(Debug)]
+pub struct CompleteChecker {
+    state: State,
+    /// data fed but not scanned yet: a token which may continue
+    pending: Vec<u8>,
+    /// unfinished literal or comment, instead of `pending`
+    in_token: Option<InToken>,
+}
+
+impl Default for CompleteChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompleteChecker {
+    /// Constructor
+    pub fn new() -> Self {
+        Self {
+            state: State::Invalid,
+            pending: Vec::new(),
+            in_token: None,
+        }
+    }
+    /// Scan `data`, which follows the data previously fed.
+    /// Return whether all data fed so far ends with a complete statement.
+    pub fn feed(&mut self, mut data: &[u8]) -> bool {
+        if let Some(mut in_token) = self.in_token.take() {
+            let Some(amt) = in_token.resume(data) else {
+                self.in_token = Some(in_token);
+                return self.is_complete();
+            };
+            self.state = self.state.next(in_token.token());
+            data = &data[amt..];
+        }
+        self.pending.extend_from_slice(data);
+        let mut offset = 0;
+        loop {
+            let (amt, end) = self.state.scan(&self.pending[offset..], false);
+            offset += amt;
+            if !end {
+                break;
+            }
+        }
+        self.pending.drain(..offset);
+        if let Some((mut in_token, start)) = InToken::start(&self.pending) {
+            if in_token.resume(&self.pending[start..]).is_none() {
+                self.in_token = Some(in_token);
+                self.pending.clear();
+            }
+        }
+        self.is_complete()
+    }
+    /// Whether all data fed so far ends with a complete statement
+    pub fn is_complete(&self) -> bool {
+        if let Some(in_token) = self.in_token {
+            return self.state.next(in_token.unterminated()) == State::Start;
+        }
+        let mut state = self.state;
+        let mut offset = 0;
+        while offset < self.pending.len() {
+            let (amt, _) = state.scan(&self.pending[offset..], true);
+            offset += amt;
+        }
+        state == State::Start
+    }
+    /// Forget all data fed so far
+    pub fn reset(&mut self) {
+        self.state = State::Invalid;
+        self.pending.clear();
+        self.in_token = None;
+    }
+}
+
+/// Position inside a literal or a comment which may continue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum InToken {
+    /// inside a literal or an identifier quoted by the byte
+    Quote(u8),
+    /// just after a quote which ends the literal, unless it is doubled
+    QuoteEnd(u8),
+    /// inside a `[...]` identifier
+    Bracket,
+    /// inside a `/* ... */` comment, just after a `*` or not
+    BlockComment(bool),
+    /// inside a `--` comment
+    LineComment,
+}
+
+impl InToken {
+    /// Literal or comment starting `data`, and offset of its content
+    fn start(data: &[u8]) -> Option<(Self, usize)> {
+        match data {
+            [quote @ (b'\'' | b'"' | b'`'), ..] => Some((Self::Quote(*quote), 1)),
+            [b'[', ..] => Some((Self::Bracket, 1)),
+            [b'/', b'*', ..] => Some((Self::BlockComment(false), 2)),
+            [b'-', b'-', ..] => Some((Self::LineComment, 2)),
+            _ => None,
+        }
+    }
+
+    /// Scan `data`, which follows the data previously scanned.
+    /// Return the offset just past the end of the token, if it ends in `data`.
+    fn resume(&mut self, data: &[u8]) -> Option<usize> {
+        let mut offset = 0;
+        while offset < data.len() {
+            let rest = &data[offset..];
+            match *self {
+                Self::Quote(quote) => {
+                    let i = memchr(quote, rest)?;
+                    *self = Self::QuoteEnd(quote);
+                    offset += i + 1;
+                }
+                Self::QuoteEnd(quote) => {
+                    if rest[0] != quote {
+                        return Some(offset);
+                    }
+                    // escaped quote
+                    *self = Self::Quote(quote);
+                    offset += 1;
+                }
+                Self::Bracket => return memchr(b']', rest).map(|i| offset + i + 1),
+                Self::BlockComment(star) => {
+                    if star && rest[0] == b'/' {
+                        return Some(offset + 1);
+                    }
+                    let i = memchr(b'*', rest);
+                    match i.map(|i| (i, rest.get(i + 1))) {
+                        Some((i, Some(b'/'))) => return Some(offset + i + 2),
+                        Some((i, Some(_))) => {
+                            *self = Self::BlockComment(false);
+                            offset += i + 1;
+                        }
+                        Some((_, None)) => {
+                            *self = Self::BlockComment(true);
+                            return None;
+                        }
+                        None => {
+                            *self = Self::BlockComment(false);
+                            return None;
+                        }
+                    }
+                }
+                Self::LineComment => return memchr(b'\n', rest).map(|i| offset + i),
+            }
+        }
+        None
+    }
+
+    /// Token, once it ends
+    fn token(self) -> Token {
+        match self {
+            Self::BlockComment(_) | Self::LineComment => Token::Ws,
+            _ => Token::Other,
+        }
+    }
+    /// Token, if it does not end before the end of input
+    fn unterminated(self) -> Token {
+        match self {
+            Self::LineComment => Token::Ws,
+            _ => Token::Other,
+        }
+    }
+}
+
+/// Token classes relevant to find the end of a statement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Semi,
+    /// White space and comments
+    Ws,
+    Other,
+    Explain,
+    Create,
+    Temp,
+    Trigger,
+    End,
+}
+
+impl From<Option<TokenType>> for Token {
+    fn from(token_type: Option<TokenType>) -> Self {
+        match token_type {
+            None | Some(TK_SPACE | TK_COMMENT) => Self::Ws,
+            Some(TK_SEMI) => Self::Semi,
+            Some(TK_EXPLAIN) => Self::Explain,
+            Some(TK_CREATE) => Self::Create,
+            Some(TK_TEMP) => Self::Temp,
+            Some(TK_TRIGGER) => Self::Trigger,
+            Some(TK_END) => Self::End,
+            Some(_) => Self::Other,
+        }
+    }
+}
+
+/// States of the `sqlite3_complete` automaton
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum State {
+    /// No non-whitespace input seen so far
+    Invalid,
+    /// At the beginning of a statement
+    Start,
+    /// In the middle of a statement
+    Normal,
+    /// `EXPLAIN` seen at the beginning of a statement
+    Explain,
+    /// `CREATE` seen at the beginning of a statement
+    Create,
+    /// In the middle of a trigger definition
+    Trigger,
+    /// Just after a `;` in a trigger definition
+    Semi,
+    /// Just after `END` in a trigger definition
+    End,
+}
+
+impl State {
+    // https://sqlite.org/src/file?name=src/complete.c
+    fn next(self, token: Token) -> Self {
+        use State::*;
+        match (self, token) {
+            (Trigger | Semi, Token::Semi) => Semi,
+            (_, Token::Semi) => Start,
+            (_, Token::Ws) => self,
+            (Invalid | Start, Token::Explain) => Explain,
+            (Invalid | Start | Explain, Token::Create) => Create,
+            (Invalid | Start, _) => Normal,
+            (Explain, Token::Other) => Explain,
+            (Explain, _) => Normal,
+            (Create, Token::Temp) => Create,
+            (Create, Token::Trigger) => Trigger,
+            (Normal | Create, _) => Normal,
+            (Semi, Token::End) => End,
+            (Trigger | Semi | End, _) => Trigger,
+        }
+    }
+
+    /// Scan `data` up to the end of the first statement.
+    ///
+    /// Return the amount of bytes scanned and whether a statement ends there.
+    /// Unless `eof`, the last token is not scanned when it may continue in
+    /// the data which follows.
+    pub(crate) fn scan(&mut self, data: &[u8], eof: bool) -> (usize, bool) {
+        // statement boundaries do not depend on the encoding
+        let mut tokenizer = Tokenizer::new().with_lossy_utf8(true);
+        let mut offset = 0;
+        while offset < data.len() {
+            let data = &data[offset..];
+            let (token, amt) = match tokenizer.split(data) {
+                Ok((token, amt)) => {
+                    if amt == data.len() && !eof && token.is_none_or(|(_, tt)| tt != TK_SEMI) {
+                        // the token may continue in the data not scanned yet
+                        break;
+                    }
+                    (Token::from(token.map(|(_, tt)| tt)), amt)
+                }
+                Err(
+                    Error::UnterminatedLiteral(_)
+                    | Error::UnterminatedBracket(_)
+                    | Error::UnterminatedBlockComment(_),
+                ) => {
+                    if !eof {
+                        break;
+                    }
+                    (Token::Other, data.len())
+                }
+                // Let the parser report the error
+                Err(_) => (Token::Other, 1),
+            };
+            if amt == 0 {
+                break;
+            }
+            offset += amt;
+            *self = self.next(token);
+            if token == Token::Semi && *self == State::Start {
+                return (offset, true);
+            }
+        }
+        (offset, false)
+    }
+}
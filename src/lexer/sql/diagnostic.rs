@@ -0,0 +1,161 @@
+//! Multi-line error reports, with the offending source code
+use std::fmt::{self, Display, Formatter};
+
+use memchr::{memchr, memchr_iter, memrchr};
+
+use super::{Error, ParserError};
+use crate::parser::ast::Span;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Error report, showing the source line(s) with the offending span
+/// underlined, like:
+/// ```text
+/// error: duplicate column name: x
+///  --> 1:24
+///   |
+/// 1 | CREATE TABLE t(x TEXT, x TEXT)
+///   |                        ^
+/// note: previous definition here
+///   |
+/// 1 | CREATE TABLE t(x TEXT, x TEXT)
+///   |                -
+/// ```
+///
+/// ```rust
+/// use fallible_iterator::FallibleIterator;
+/// use sqlite3_parser::lexer::sql::{Diagnostic, Parser};
+///
+/// let sql = b"SELECT 1 FROM t ORDER x";
+/// let err = Parser::new(sql).next().unwrap_err();
+/// let report = Diagnostic::new(&err, sql).to_string();
+/// assert!(report.starts_with("error: near \"x\": syntax error, expected BY\n --> 1:23\n"));
+/// ```
+///
+/// `source` must be the whole input given to the parser, because error
+/// positions are byte offsets in it.
+pub struct Diagnostic<'a> {
+    error: &'a Error,
+    source: &'a [u8],
+    ansi: bool,
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Constructor
+    pub fn new(error: &'a Error, source: &'a [u8]) -> Self {
+        Self {
+            error,
+            source,
+            ansi: false,
+        }
+    }
+    /// Use ANSI escape sequences to colour the report
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+    /// Related locations, with their label
+    pub fn notes(&self) -> Vec<(Span, &'static str)> {
+        match self.error {
+            Error::ParserError(ParserError::Duplicate { previous, .. }, _) => {
+                vec![(*previous, "previous definition here")]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn paint(&self, f: &mut Formatter<'_>, style: &str, text: impl Display) -> fmt::Result {
+        if self.ansi {
+            write!(f, "{style}{text}{RESET}")
+        } else {
+            write!(f, "{text}")
+        }
+    }
+
+    /// Source lines covered by `span`, with `marker` under the span
+    fn snippet(
+        &self,
+        f: &mut Formatter<'_>,
+        span: Span,
+        width: usize,
+        marker: char,
+        style: &str,
+    ) -> fmt::Result {
+        let src = self.source;
+        let end = span.end.min(src.len());
+        let start = span.start.min(end);
+        let gutter = " ".repeat(width);
+        self.paint(f, BLUE, format_args!("{gutter} |"))?;
+        writeln!(f)?;
+        let mut line_start = memrchr(b'\n', &src[..start]).map_or(0, |i| i + 1);
+        let mut line_no = line_number(src, line_start);
+        loop {
+            let line_end = memchr(b'\n', &src[line_start..]).map_or(src.len(), |i| line_start + i);
+            let line = &src[line_start..line_end];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            self.paint(f, BLUE, format_args!("{line_no:>width$} |"))?;
+            if !line.is_empty() {
+                write!(f, " {}", String::from_utf8_lossy(line))?;
+            }
+            writeln!(f)?;
+            // underline
+            let from = start.max(line_start) - line_start;
+            let to = (end.min(line_end) - line_start).clamp(from, line.len().max(from));
+            let indent: String = String::from_utf8_lossy(&line[..from.min(line.len())])
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let len = String::from_utf8_lossy(&line[from.min(line.len())..to.min(line.len())])
+                .chars()
+                .count()
+                .max(1);
+            self.paint(f, BLUE, format_args!("{gutter} |"))?;
+            write!(f, " {indent}")?;
+            self.paint(f, style, marker.to_string().repeat(len))?;
+            writeln!(f)?;
+            if end <= line_end || line_end == src.len() {
+                return Ok(());
+            }
+            line_start = line_end + 1;
+            line_no += 1;
+        }
+    }
+}
+
+/// 1-based line number of `offset` in `src`
+fn line_number(src: &[u8], offset: usize) -> u64 {
+    memchr_iter(b'\n', &src[..offset]).count() as u64 + 1
+}
+
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.paint(f, RED, "error")?;
+        self.paint(f, BOLD, format_args!(": {}", self.error.message()))?;
+        writeln!(f)?;
+        let Some(pos) = self.error.position() else {
+            return Ok(());
+        };
+        let primary = Span::new(pos.start, pos.end);
+        let notes = self.notes();
+        let last_line = std::iter::once(primary)
+            .chain(notes.iter().map(|(span, _)| *span))
+            .map(|span| span.end_position(self.source).0)
+            .max()
+            .unwrap_or(1);
+        let width = last_line.to_string().len();
+        let (line, column) = primary.start_position(self.source);
+        self.paint(f, BLUE, format_args!("{:width$}-->", ""))?;
+        writeln!(f, " {line}:{column}")?;
+        self.snippet(f, primary, width, '^', RED)?;
+        for (span, label) in notes {
+            self.paint(f, CYAN, "note")?;
+            writeln!(f, ": {label}")?;
+            self.snippet(f, span, width, '-', CYAN)?;
+        }
+        Ok(())
+    }
+}
@@ -2,9 +2,58 @@ use std::error;
 use std::fmt;
 use std::io;
 
+use memchr::{memchr_iter, memrchr};
+
 use crate::lexer::scan::ScanError;
 use crate::parser::ParserError;
 
+use super::TokenType;
+
+/// Location of the offending token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    /// byte offset where the offending token starts
+    pub start: usize,
+    /// byte offset just past the offending token
+    pub end: usize,
+    /// line number of `start` (1-based)
+    pub line: u64,
+    /// column number of `start` (1-based, byte offset, not char offset)
+    pub column: usize,
+}
+
+impl Position {
+    /// Position relative to the data being split, before the scanner
+    /// makes it absolute in [`ScanError::position`]
+    pub(crate) fn relative(len: usize) -> Option<Self> {
+        Some(Self {
+            start: 0,
+            end: len,
+            line: 0,
+            column: 0,
+        })
+    }
+    /// Position of `data[start..end]`, relative to the data being split,
+    /// when the offending text is not at the start of `data`
+    pub(crate) fn relative_range(data: &[u8], start: usize, end: usize) -> Option<Self> {
+        let before = &data[..start];
+        Some(Self {
+            start,
+            end,
+            // number of new lines before `start`
+            line: memchr_iter(b'\n', before).count() as u64,
+            // column of `start`, if after a new line
+            column: memrchr(b'\n', before).map_or(0, |i| start - i),
+        })
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.line, self.column)
+    }
+}
+
 /// SQL lexer and parser errors
 #[non_exhaustive]
 #[derive(Debug)]
@@ -12,58 +61,200 @@ pub enum Error {
     /// I/O Error
     Io(io::Error),
     /// Lexer error
-    UnrecognizedToken(Option<(u64, usize)>),
+    UnrecognizedToken(Option<Position>),
     /// Missing quote or double-quote or backtick
-    UnterminatedLiteral(Option<(u64, usize)>),
+    UnterminatedLiteral(Option<Position>),
     /// Missing `]`
-    UnterminatedBracket(Option<(u64, usize)>),
+    UnterminatedBracket(Option<Position>),
     /// Missing `*/`
-    UnterminatedBlockComment(Option<(u64, usize)>),
+    UnterminatedBlockComment(Option<Position>),
     /// Invalid parameter name
-    BadVariableName(Option<(u64, usize)>),
+    BadVariableName(Option<Position>),
     /// Invalid number format
-    BadNumber(Option<(u64, usize)>),
+    BadNumber(Option<Position>),
     /// Invalid or missing sign after `!`
-    ExpectedEqualsSign(Option<(u64, usize)>),
+    ExpectedEqualsSign(Option<Position>),
     /// BLOB literals are string literals containing hexadecimal data and preceded by a single "x" or "X" character.
-    MalformedBlobLiteral(Option<(u64, usize)>),
+    MalformedBlobLiteral(Option<Position>),
     /// Hexadecimal integer literals follow the C-language notation of "0x" or "0X" followed by hexadecimal digits.
-    MalformedHexInteger(Option<(u64, usize)>),
+    MalformedHexInteger(Option<Position>),
+    /// Invalid UTF-8 sequence
+    InvalidUtf8(Option<Position>),
     /// Grammar error
-    ParserError(ParserError, Option<(u64, usize)>),
+    ParserError(ParserError, Option<Position>),
+}
+
+/// Kind of [`Error`], without any payload
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// I/O Error
+    Io,
+    /// Lexer error
+    UnrecognizedToken,
+    /// Missing quote or double-quote or backtick
+    UnterminatedLiteral,
+    /// Missing `]`
+    UnterminatedBracket,
+    /// Missing `*/`
+    UnterminatedBlockComment,
+    /// Invalid parameter name
+    BadVariableName,
+    /// Invalid number format
+    BadNumber,
+    /// Invalid or missing sign after `!`
+    ExpectedEqualsSign,
+    /// Malformed BLOB literal
+    MalformedBlobLiteral,
+    /// Malformed hexadecimal integer literal
+    MalformedHexInteger,
+    /// Invalid UTF-8 sequence
+    InvalidUtf8,
+    /// Syntax error
+    SyntaxError,
+    /// Unexpected end of input
+    UnexpectedEof,
+    /// Additional syntax rule violated
+    Custom,
+    /// Syntax not supported by the target SQLite version
+    Unsupported,
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
-            Self::Io(ref err) => err.fmt(f),
-            Self::UnrecognizedToken(pos) => write!(f, "unrecognized token at {:?}", pos.unwrap()),
-            Self::UnterminatedLiteral(pos) => {
-                write!(f, "non-terminated literal at {:?}", pos.unwrap())
-            }
-            Self::UnterminatedBracket(pos) => {
-                write!(f, "non-terminated bracket at {:?}", pos.unwrap())
-            }
-            Self::UnterminatedBlockComment(pos) => {
-                write!(f, "non-terminated block comment at {:?}", pos.unwrap())
-            }
-            Self::BadVariableName(pos) => write!(f, "bad variable name at {:?}", pos.unwrap()),
-            Self::BadNumber(pos) => write!(f, "bad number at {:?}", pos.unwrap()),
-            Self::ExpectedEqualsSign(pos) => write!(f, "expected = sign at {:?}", pos.unwrap()),
-            Self::MalformedBlobLiteral(pos) => {
-                write!(f, "malformed blob literal at {:?}", pos.unwrap())
-            }
-            Self::MalformedHexInteger(pos) => {
-                write!(f, "malformed hex integer at {:?}", pos.unwrap())
-            }
-            Self::ParserError(ref msg, Some(pos)) => write!(f, "{msg} at {pos:?}"),
-            Self::ParserError(ref msg, _) => write!(f, "{msg}"),
+        if let Self::Io(ref err) = *self {
+            return err.fmt(f);
+        }
+        self.message().fmt(f)?;
+        if let Some(pos) = self.position() {
+            write!(f, " at {pos}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error message, without position
+pub(crate) struct Message<'a>(&'a Error);
+
+impl fmt::Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self.0 {
+            Error::Io(ref err) => err.fmt(f),
+            Error::UnrecognizedToken(_) => f.write_str("unrecognized token"),
+            Error::UnterminatedLiteral(_) => f.write_str("non-terminated literal"),
+            Error::UnterminatedBracket(_) => f.write_str("non-terminated bracket"),
+            Error::UnterminatedBlockComment(_) => f.write_str("non-terminated block comment"),
+            Error::BadVariableName(_) => f.write_str("bad variable name"),
+            Error::BadNumber(_) => f.write_str("bad number"),
+            Error::ExpectedEqualsSign(_) => f.write_str("expected = sign"),
+            Error::MalformedBlobLiteral(_) => f.write_str("malformed blob literal"),
+            Error::MalformedHexInteger(_) => f.write_str("malformed hex integer"),
+            Error::InvalidUtf8(_) => f.write_str("invalid UTF-8 sequence"),
+            Error::ParserError(ref err, _) => err.fmt(f),
         }
     }
 }
 
 impl error::Error for Error {}
 
+impl Error {
+    /// Error message, without position
+    pub(crate) fn message(&self) -> Message<'_> {
+        Message(self)
+    }
+    /// Kind of error
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Self::Io(_) => ErrorKind::Io,
+            Self::UnrecognizedToken(_) => ErrorKind::UnrecognizedToken,
+            Self::UnterminatedLiteral(_) => ErrorKind::UnterminatedLiteral,
+            Self::UnterminatedBracket(_) => ErrorKind::UnterminatedBracket,
+            Self::UnterminatedBlockComment(_) => ErrorKind::UnterminatedBlockComment,
+            Self::BadVariableName(_) => ErrorKind::BadVariableName,
+            Self::BadNumber(_) => ErrorKind::BadNumber,
+            Self::ExpectedEqualsSign(_) => ErrorKind::ExpectedEqualsSign,
+            Self::MalformedBlobLiteral(_) => ErrorKind::MalformedBlobLiteral,
+            Self::MalformedHexInteger(_) => ErrorKind::MalformedHexInteger,
+            Self::InvalidUtf8(_) => ErrorKind::InvalidUtf8,
+            Self::ParserError(ParserError::SyntaxError { .. }, _) => ErrorKind::SyntaxError,
+            Self::ParserError(ParserError::UnexpectedEof, _) => ErrorKind::UnexpectedEof,
+            Self::ParserError(
+                ParserError::Custom(_)
+                | ParserError::Duplicate { .. }
+                | ParserError::NoSuchColumn { .. },
+                _,
+            ) => ErrorKind::Custom,
+            Self::ParserError(ParserError::Unsupported { .. }, _) => ErrorKind::Unsupported,
+        }
+    }
+    /// Location of the offending token, if known
+    pub fn position(&self) -> Option<Position> {
+        match *self {
+            Self::Io(_) => None,
+            Self::UnrecognizedToken(pos)
+            | Self::UnterminatedLiteral(pos)
+            | Self::UnterminatedBracket(pos)
+            | Self::UnterminatedBlockComment(pos)
+            | Self::BadVariableName(pos)
+            | Self::BadNumber(pos)
+            | Self::ExpectedEqualsSign(pos)
+            | Self::MalformedBlobLiteral(pos)
+            | Self::MalformedHexInteger(pos)
+            | Self::InvalidUtf8(pos)
+            | Self::ParserError(_, pos) => pos,
+        }
+    }
+    /// Type and text of the token which caused a syntax error
+    ///
+    /// For lexer errors, there is no token: the offending text is the
+    /// input at the [`position`](Self::position) range.
+    pub fn token(&self) -> Option<(TokenType, &str)> {
+        match self {
+            Self::ParserError(
+                ParserError::SyntaxError {
+                    token_type, found, ..
+                },
+                _,
+            ) => Some((*token_type, found)),
+            _ => None,
+        }
+    }
+
+    fn position_mut(&mut self) -> Option<&mut Option<Position>> {
+        match *self {
+            Self::Io(_) => None,
+            Self::UnrecognizedToken(ref mut pos)
+            | Self::UnterminatedLiteral(ref mut pos)
+            | Self::UnterminatedBracket(ref mut pos)
+            | Self::UnterminatedBlockComment(ref mut pos)
+            | Self::BadVariableName(ref mut pos)
+            | Self::BadNumber(ref mut pos)
+            | Self::ExpectedEqualsSign(ref mut pos)
+            | Self::MalformedBlobLiteral(ref mut pos)
+            | Self::MalformedHexInteger(ref mut pos)
+            | Self::InvalidUtf8(ref mut pos)
+            | Self::ParserError(_, ref mut pos) => Some(pos),
+        }
+    }
+    /// Shift byte offsets of an error found in a chunk of input starting at `offset`
+    pub(crate) fn adjust_offset(&mut self, offset: usize) {
+        if let Some(Some(pos)) = self.position_mut() {
+            pos.start += offset;
+            pos.end += offset;
+        }
+    }
+    /// Translate a position relative to a chunk of input starting at (`line`, `column`)
+    /// into a position relative to the whole input.
+    pub(crate) fn adjust_position(&mut self, line: u64, column: usize) {
+        if let Some(Some(pos)) = self.position_mut() {
+            if pos.line == 1 {
+                pos.column += column - 1;
+            }
+            pos.line += line - 1;
+        }
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
         Self::Io(err)
@@ -77,19 +268,25 @@ impl From<ParserError> for Error {
 }
 
 impl ScanError for Error {
-    fn position(&mut self, line: u64, column: usize) {
-        match *self {
-            Self::Io(_) => {}
-            Self::UnrecognizedToken(ref mut pos) => *pos = Some((line, column)),
-            Self::UnterminatedLiteral(ref mut pos) => *pos = Some((line, column)),
-            Self::UnterminatedBracket(ref mut pos) => *pos = Some((line, column)),
-            Self::UnterminatedBlockComment(ref mut pos) => *pos = Some((line, column)),
-            Self::BadVariableName(ref mut pos) => *pos = Some((line, column)),
-            Self::BadNumber(ref mut pos) => *pos = Some((line, column)),
-            Self::ExpectedEqualsSign(ref mut pos) => *pos = Some((line, column)),
-            Self::MalformedBlobLiteral(ref mut pos) => *pos = Some((line, column)),
-            Self::MalformedHexInteger(ref mut pos) => *pos = Some((line, column)),
-            Self::ParserError(_, ref mut pos) => *pos = Some((line, column)),
+    fn position(&mut self, offset: usize, line: u64, column: usize) {
+        if let Some(pos) = self.position_mut() {
+            // range of the offending text, if known by the splitter
+            let relative = pos.unwrap_or(Position {
+                start: 0,
+                end: 0,
+                line: 0,
+                column: 0,
+            });
+            *pos = Some(Position {
+                start: offset + relative.start,
+                end: offset + relative.end,
+                line: line + relative.line,
+                column: if relative.line == 0 {
+                    column + relative.start
+                } else {
+                    relative.column
+                },
+            });
         }
     }
 }
@@ -0,0 +1,143 @@
+//! Parsing of fragments: expression, type, ... instead of commands
+use super::{Error, Parser, TokenType};
+use crate::custom_err;
+use crate::dialect::{sentinel, TokenType::*};
+use crate::parser::ast::{ColumnDefinition, Expr, QualifiedName, SortedColumn, Span, Type};
+use crate::parser::parse::YYCODETYPE;
+use crate::parser::Fragment;
+
+impl<'input> Parser<'input> {
+    /// Parse the rest of the input as an expression, like a `CHECK`
+    /// constraint or a `DEFAULT` value.
+    ///
+    /// Trailing input, including a `;`, is an error.
+    /// With [`with_dqs`](Self::with_dqs), double-quoted identifiers are
+    /// resolved as if no column were in scope, like in `SELECT expr`.
+    ///
+    /// ```rust
+    /// use sqlite3_parser::ast::Dqs;
+    /// use sqlite3_parser::lexer::sql::Parser;
+    ///
+    /// let expr = Parser::new(b"a + 1 > b").parse_expr().unwrap();
+    /// assert_eq!(expr.to_string(), "a + 1 > b");
+    /// assert!(Parser::new(b"a + 1 b").parse_expr().is_err());
+    /// let mut parser = Parser::new(br#"a || "b""#).with_dqs(Dqs::default());
+    /// assert_eq!(parser.parse_expr().unwrap().to_string(), "a || 'b'");
+    /// ```
+    pub fn parse_expr(&mut self) -> Result<Expr<'input>, Error> {
+        match self.parse_fragment(TK_FRAGMENT_EXPR)? {
+            Fragment::Expr(expr) => Ok(expr),
+            _ => Err(fragment_mismatch()),
+        }
+    }
+    /// Parse the rest of the input as a type name, like `VARCHAR(10)`
+    pub fn parse_type(&mut self) -> Result<Type<'input>, Error> {
+        match self.parse_fragment(TK_FRAGMENT_TYPE)? {
+            Fragment::Type(ty) => Ok(ty),
+            _ => Err(fragment_mismatch()),
+        }
+    }
+    /// Parse the rest of the input as a column definition, like in
+    /// `ALTER TABLE ... ADD COLUMN`: it is checked likewise, and with
+    /// [`with_dqs`](Self::with_dqs) only the column itself is in scope.
+    pub fn parse_column_def(&mut self) -> Result<ColumnDefinition<'input>, Error> {
+        match self.parse_fragment(TK_FRAGMENT_COLUMN_DEF)? {
+            Fragment::ColumnDef(cd) => Ok(cd),
+            _ => Err(fragment_mismatch()),
+        }
+    }
+    /// Parse the rest of the input as a list of sorted columns, like an
+    /// `ORDER BY` clause (without `ORDER BY`).
+    /// Like in an `ORDER BY` clause, double-quoted identifiers are left
+    /// unchanged.
+    pub fn parse_sorted_columns(&mut self) -> Result<Vec<SortedColumn<'input>>, Error> {
+        match self.parse_fragment(TK_FRAGMENT_SORTED_COLUMNS)? {
+            Fragment::SortedColumns(columns) => Ok(columns),
+            _ => Err(fragment_mismatch()),
+        }
+    }
+    /// Parse the rest of the input as a name, maybe qualified by a schema
+    /// name, like `main.t`
+    pub fn parse_qualified_name(&mut self) -> Result<QualifiedName<'input>, Error> {
+        match self.parse_fragment(TK_FRAGMENT_QUALIFIED_NAME)? {
+            Fragment::QualifiedName(name) => Ok(name),
+            _ => Err(fragment_mismatch()),
+        }
+    }
+
+    /// Feed the parser with `marker` and then with all the tokens up to the
+    /// end of the input
+    fn parse_fragment(&mut self, marker: TokenType) -> Result<Fragment<'input>, Error> {
+        self.parser.ctx.reset();
+        self.dqs_fallbacks.clear();
+        let start = self.offset + self.scanner.offset();
+        let mut last_token = sentinel(start);
+        self.parse_token(marker, last_token)?;
+        let mut last_token_parsed = marker;
+        while self.parser.ctx.is_ok() {
+            let offset = self.offset;
+            let next = self.next_token(last_token_parsed).map_err(|mut err| {
+                err.adjust_offset(offset);
+                err
+            })?;
+            let Some((token_type, token)) = next else {
+                last_token = sentinel(self.offset + self.input.len());
+                self.parse_token(TK_EOF, last_token)?;
+                break;
+            };
+            if !self.parser.yy_accepts_all(&[token_type as YYCODETYPE])
+                && self.parser.yy_accepts_all(&[TK_EOF as YYCODETYPE])
+            {
+                let found = String::from_utf8_lossy(token.1);
+                let err = custom_err!("near \"{}\": trailing input", found);
+                return Err(Error::ParserError(
+                    err,
+                    Some(self.error_position(token.0, token.2)),
+                ));
+            }
+            self.check_length(start, token)?;
+            self.parse_token(token_type, token)?;
+            last_token_parsed = token_type;
+            last_token = token;
+        }
+        self.parser.sqlite3ParserFinalize();
+        if let Some(e) = self.parser.ctx.error() {
+            let pos = self.error_position(last_token.0, last_token.2);
+            return Err(Error::ParserError(e, Some(pos)));
+        }
+        let mut fragment = self.parser.ctx.fragment().ok_or_else(fragment_mismatch)?;
+        self.check_fragment(&mut fragment, Span::new(start, last_token.2))?;
+        Ok(fragment)
+    }
+
+    /// Like [`check`](Self::check) for a fragment spanning `span`
+    fn check_fragment(&mut self, fragment: &mut Fragment<'input>, span: Span) -> Result<(), Error> {
+        let res = match fragment {
+            Fragment::Expr(expr) => match self.dqs {
+                Some(dqs) => expr.apply_dqs(dqs),
+                None => Ok(vec![]),
+            },
+            Fragment::ColumnDef(cd) => cd.check().and_then(|_| match self.dqs {
+                Some(dqs) => cd.apply_dqs(dqs),
+                None => Ok(vec![]),
+            }),
+            _ => Ok(vec![]),
+        };
+        match res {
+            Ok(fallbacks) => {
+                self.dqs_fallbacks = fallbacks;
+                Ok(())
+            }
+            Err(e) => {
+                let span = e.span().unwrap_or(span);
+                let pos = self.error_position(span.start, span.end);
+                Err(Error::ParserError(e, Some(pos)))
+            }
+        }
+    }
+}
+
+/// The grammar builds the fragment requested by its marker
+fn fragment_mismatch() -> Error {
+    Error::ParserError(custom_err!("no fragment parsed"), None)
+}
@@ -0,0 +1,198 @@
+//! Syntax highlighting
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+
+use super::{Parser, TokenCategory, Tokenizer};
+use crate::dialect::TokenType::{self, *};
+use crate::lexer::scan::Splitter;
+use crate::parser::parse::{yyParser, yySimulator, YYCODETYPE};
+
+const RESET: &str = "\x1b[0m";
+
+/// Tokens of `input`, with their category in context, covering the whole
+/// input: white spaces and comments are included.
+///
+/// Keywords used as identifiers, through the `%fallback ID` directive of
+/// the grammar or like `WINDOW`, `OVER` and `FILTER` which are identifiers
+/// depending on the following tokens, are classified as identifiers.
+/// Text which cannot be tokenized has no category.
+///
+/// ```rust
+/// use sqlite3_parser::lexer::sql::{token_categories, TokenCategory};
+///
+/// let input = b"SELECT key FROM t -- key is a keyword";
+/// let tokens = token_categories(input);
+/// assert_eq!(tokens[0], (0..6, Some(TokenCategory::Keyword)));
+/// assert_eq!(tokens[2], (7..10, Some(TokenCategory::Identifier)));
+/// assert_eq!(tokens[8], (18..37, Some(TokenCategory::Comment)));
+/// ```
+pub fn token_categories(input: &[u8]) -> Vec<(Range<usize>, Option<TokenCategory>)> {
+    let mut parser = Parser::new(input).with_lossy_utf8(true);
+    let mut tokens = Vec::new();
+    // `None` after a syntax error, up to the end of the statement
+    let mut simulator = Some(yySimulator::new());
+    let mut last_token_parsed = TK_EOF;
+    let mut offset = 0;
+    loop {
+        match parser.next_token(last_token_parsed) {
+            Ok(Some((token_type, token))) => {
+                trivia(input, offset..token.0, &mut tokens);
+                let category = category(&mut simulator, token_type);
+                tokens.push((token.0..token.2, Some(category)));
+                offset = token.2;
+                last_token_parsed = token_type;
+            }
+            Ok(None) => break,
+            Err(err) => {
+                let start = parser.scanner.offset();
+                let end = err
+                    .position()
+                    .map_or(start, |pos| pos.end)
+                    .clamp(start + 1, input.len());
+                trivia(input, offset..start, &mut tokens);
+                tokens.push((start..end, None));
+                parser.scanner.skip_to(input, end);
+                offset = end;
+                simulator = None;
+            }
+        }
+    }
+    trivia(input, offset..input.len(), &mut tokens);
+    tokens
+}
+
+/// Category of `token_type` in context, then feed the `simulator` with it
+fn category(simulator: &mut Option<yySimulator>, token_type: TokenType) -> TokenCategory {
+    let category = token_type.category();
+    let Some(sim) = simulator else {
+        if token_type == TK_SEMI {
+            *simulator = Some(yySimulator::new());
+        }
+        return category;
+    };
+    let major = token_type as YYCODETYPE;
+    let fallback = category == TokenCategory::Keyword
+        && yyParser::parse_fallback(major) == TK_ID as YYCODETYPE
+        && !sim.yy_accepts_exactly(major)
+        && sim.yy_accepts_exactly(TK_ID as YYCODETYPE);
+    if !sim.yy_parse(major) {
+        *simulator = None;
+    }
+    if fallback {
+        TokenCategory::Identifier
+    } else {
+        category
+    }
+}
+
+/// White spaces and comments of `input[range]`
+fn trivia(
+    input: &[u8],
+    range: Range<usize>,
+    tokens: &mut Vec<(Range<usize>, Option<TokenCategory>)>,
+) {
+    let mut tokenizer = Tokenizer::new().with_trivia(true).with_lossy_utf8(true);
+    let mut offset = range.start;
+    while offset < range.end {
+        let (category, amt) = match tokenizer.split(&input[offset..range.end]) {
+            Ok((Some((_, token_type)), amt)) if amt > 0 => (Some(token_type.category()), amt),
+            // not expected: the parser has already skipped this text
+            _ => (None, range.end - offset),
+        };
+        tokens.push((offset..offset + amt, category));
+        offset += amt;
+    }
+}
+
+/// Syntax highlighted SQL, with ANSI escape sequences or HTML tags
+///
+/// ```rust
+/// use sqlite3_parser::lexer::sql::Highlighter;
+///
+/// let html = Highlighter::html(b"SELECT 'a<b' AS x").to_string();
+/// assert_eq!(
+///     html,
+///     "<span class=\"sql-keyword\">SELECT</span> \
+///      <span class=\"sql-string\">'a&lt;b'</span> \
+///      <span class=\"sql-keyword\">AS</span> \
+///      <span class=\"sql-identifier\">x</span>"
+/// );
+/// let ansi = Highlighter::ansi(b"SELECT 1").to_string();
+/// assert_eq!(ansi, "\x1b[1;34mSELECT\x1b[0m \x1b[33m1\x1b[0m");
+/// ```
+pub struct Highlighter<'a> {
+    input: &'a [u8],
+    html: bool,
+}
+
+impl<'a> Highlighter<'a> {
+    /// Highlight `input` with ANSI escape sequences, for terminals
+    pub fn ansi(input: &'a [u8]) -> Self {
+        Self { input, html: false }
+    }
+    /// Highlight `input` with `<span class="sql-{category}">` HTML tags,
+    /// to be styled with CSS
+    /// (see [`TokenCategory::name`](crate::dialect::TokenCategory::name)).
+    ///
+    /// Text without category is in a `sql-error` span.
+    pub fn html(input: &'a [u8]) -> Self {
+        Self { input, html: true }
+    }
+}
+
+impl Display for Highlighter<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (range, category) in token_categories(self.input) {
+            let text = String::from_utf8_lossy(&self.input[range]);
+            if self.html {
+                match category {
+                    Some(TokenCategory::Whitespace) => html_escape(f, &text)?,
+                    Some(category) => {
+                        write!(f, "<span class=\"sql-{}\">", category.name())?;
+                        html_escape(f, &text)?;
+                        f.write_str("</span>")?;
+                    }
+                    None => {
+                        f.write_str("<span class=\"sql-error\">")?;
+                        html_escape(f, &text)?;
+                        f.write_str("</span>")?;
+                    }
+                }
+            } else {
+                match ansi_style(category) {
+                    Some(style) => write!(f, "{style}{text}{RESET}")?,
+                    None => f.write_str(&text)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn ansi_style(category: Option<TokenCategory>) -> Option<&'static str> {
+    match category {
+        Some(TokenCategory::Keyword) => Some("\x1b[1;34m"),
+        Some(TokenCategory::String) => Some("\x1b[32m"),
+        Some(TokenCategory::Number | TokenCategory::Blob) => Some("\x1b[33m"),
+        Some(TokenCategory::Variable) => Some("\x1b[35m"),
+        Some(TokenCategory::Comment) => Some("\x1b[90m"),
+        None => Some("\x1b[4;31m"),
+        _ => None,
+    }
+}
+
+fn html_escape(f: &mut Formatter<'_>, text: &str) -> fmt::Result {
+    let mut last = 0;
+    for (i, c) in text.char_indices() {
+        let escaped = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            _ => continue,
+        };
+        f.write_str(&text[last..i])?;
+        f.write_str(escaped)?;
+        last = i + 1;
+    }
+    f.write_str(&text[last..])
+}
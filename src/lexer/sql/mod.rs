@@ -1,25 +1,47 @@
 //! Adaptation/port of [`SQLite` tokenizer](http://www.sqlite.org/src/artifact?ci=trunk&filename=src/tokenize.c)
+use std::str;
+
 use fallible_iterator::FallibleIterator;
-use memchr::memchr;
+use memchr::{memchr, memchr_iter, memrchr};
 
-pub use crate::dialect::TokenType;
+use crate::custom_err;
 use crate::dialect::TokenType::*;
 use crate::dialect::{
     is_identifier_continue, is_identifier_start, keyword_token, sentinel, MAX_KEYWORD_LEN,
 };
-use crate::parser::ast::Cmd;
+pub use crate::dialect::{TokenCategory, TokenType};
+use crate::parser::ast::{Cmd, Dqs, Span};
 use crate::parser::parse::{yyParser, YYCODETYPE};
-use crate::parser::Context;
+use crate::parser::{expected_tokens, Context};
 
+mod classify;
+mod complete;
+mod diagnostic;
 mod error;
+mod fragment;
+mod highlight;
+mod split;
+mod stream;
+mod suggest;
 #[cfg(test)]
 mod test;
+mod tolerant;
+mod utf16;
 
-use crate::lexer::scan::ScanError;
 use crate::lexer::scan::Splitter;
-use crate::lexer::Scanner;
-pub use crate::parser::ParserError;
-pub use error::Error;
+use crate::lexer::{LineIndex, Scanner};
+pub use crate::parser::{ParserError, ParserLimits};
+pub use classify::StmtInfo;
+use complete::State;
+pub use complete::{is_complete, CompleteChecker};
+pub use diagnostic::Diagnostic;
+pub use error::{Error, ErrorKind, Position};
+pub use highlight::{token_categories, Highlighter};
+pub use split::{Statement, StatementRanges, Statements};
+pub use stream::StreamParser;
+pub use suggest::{completions, Completion, Completions};
+pub use tolerant::Partial;
+pub use utf16::Utf16Input;
 
 // TODO Extract scanning stuff and move this into the parser crate
 // to make possible to use the tokenizer without depending on the parser...
@@ -27,27 +49,192 @@ pub use error::Error;
 /// SQL parser
 pub struct Parser<'input> {
     input: &'input [u8],
+    /// offset of `input` in the whole text, added to spans
+    offset: usize,
     scanner: Scanner<Tokenizer>,
     parser: yyParser<'input>,
+    /// token read but not parsed yet, by the tolerant parser
+    pending: Option<(TokenType, crate::dialect::Token<'input>)>,
+    /// double-quoted string literals fallback
+    dqs: Option<Dqs>,
+    /// identifiers replaced by string literals in the last statement
+    dqs_fallbacks: Vec<Span>,
 }
 
 impl<'input> Parser<'input> {
     /// Constructor
     pub fn new(input: &'input [u8]) -> Self {
+        Self::with_offset(input, 0)
+    }
+    /// Constructor for a chunk of text starting at byte `offset`
+    pub(crate) fn with_offset(input: &'input [u8], offset: usize) -> Self {
         let lexer = Tokenizer::new();
         let scanner = Scanner::new(lexer);
-        let ctx = Context::new(input);
+        let ctx = Context::new(input, offset);
         let parser = yyParser::new(ctx);
         Parser {
             input,
+            offset,
             scanner,
             parser,
+            pending: None,
+            dqs: None,
+            dqs_fallbacks: Vec::new(),
         }
+        .with_limits(ParserLimits::default())
+    }
+    /// Take double-quoted identifiers which cannot be columns as string
+    /// literals, like SQLite does by default.
+    ///
+    /// See [`Dqs`] for the identifiers which are resolved without schema,
+    /// and [`dqs_fallbacks`](Self::dqs_fallbacks) to find where the fallback
+    /// was applied. When the fallback is disabled for the kind of statement,
+    /// such an identifier is an error (`no such column`).
+    /// By default, double-quoted identifiers are left unchanged.
+    ///
+    /// ```rust
+    /// use fallible_iterator::FallibleIterator;
+    /// use sqlite3_parser::ast::Dqs;
+    /// use sqlite3_parser::lexer::sql::Parser;
+    ///
+    /// let input = br#"CREATE TABLE t(x DEFAULT "abc"); SELECT "abc";"#;
+    /// let mut parser = Parser::new(input).with_dqs(Dqs::default());
+    /// let cmd = parser.next().unwrap().unwrap();
+    /// assert_eq!(cmd.to_string(), "CREATE TABLE t (x DEFAULT 'abc');");
+    /// assert_eq!(parser.dqs_fallbacks()[0].start, 25);
+    ///
+    /// let mut parser = Parser::new(input).with_dqs(Dqs { dml: false, ddl: true });
+    /// assert!(parser.next().unwrap().is_some());
+    /// assert!(parser.next().is_err());
+    /// ```
+    pub fn with_dqs(mut self, dqs: Dqs) -> Self {
+        self.dqs = Some(dqs);
+        self
+    }
+    /// Restrict the syntax accepted by the tokenizer
+    ///
+    /// ```rust
+    /// use fallible_iterator::FallibleIterator;
+    /// use sqlite3_parser::lexer::sql::{Parser, TokenizerOptions};
+    ///
+    /// let options = TokenizerOptions {
+    ///     brackets: false,
+    ///     ..TokenizerOptions::default()
+    /// };
+    /// let mut parser = Parser::new(b"SELECT [a] FROM t").with_tokenizer_options(options);
+    /// assert!(parser.next().is_err());
+    /// ```
+    pub fn with_tokenizer_options(mut self, options: TokenizerOptions) -> Self {
+        self.scanner.splitter_mut().options = options;
+        self
+    }
+    /// Reject statements exceeding `limits`, instead of SQLite's default
+    /// limits
+    ///
+    /// ```rust
+    /// use fallible_iterator::FallibleIterator;
+    /// use sqlite3_parser::lexer::sql::{Parser, ParserLimits};
+    ///
+    /// let limits = ParserLimits {
+    ///     max_function_arg: 2,
+    ///     ..ParserLimits::default()
+    /// };
+    /// let mut parser = Parser::new(b"SELECT max(1, 2, 3)").with_limits(limits);
+    /// let err = parser.next().unwrap_err();
+    /// assert!(err.to_string().starts_with("too many arguments on function max"));
+    /// ```
+    pub fn with_limits(mut self, limits: ParserLimits) -> Self {
+        let max_depth = match limits.max_expr_depth {
+            0 => usize::MAX, // no limit
+            max_depth => max_depth,
+        };
+        self.parser.yy_set_max_depth(max_depth);
+        self.parser.ctx.set_limits(limits);
+        self
+    }
+    /// Reject syntax introduced after the target SQLite `version`, given
+    /// like `SQLITE_VERSION_NUMBER` (`3_031_000` for 3.31.0), with an
+    /// [`ErrorKind::Unsupported`] error located on the offending node or
+    /// token.
+    /// By default, all the supported syntax is accepted.
+    ///
+    /// ```rust
+    /// use fallible_iterator::FallibleIterator;
+    /// use sqlite3_parser::lexer::sql::{ErrorKind, Parser};
+    ///
+    /// let input = b"DELETE FROM t RETURNING *";
+    /// let mut parser = Parser::new(input).with_target_version(3_031_000);
+    /// let err = parser.next().unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::Unsupported);
+    /// assert!(err.to_string().starts_with("RETURNING requires SQLite 3.35.0"));
+    /// assert_eq!(err.position().unwrap().start, 14);
+    /// let mut parser = Parser::new(input).with_target_version(3_035_000);
+    /// assert!(parser.next().unwrap().is_some());
+    /// ```
+    pub fn with_target_version(mut self, version: u32) -> Self {
+        self.parser.ctx.set_target_version(version);
+        self
+    }
+    /// Keep parsing invalid UTF-8 input, replacing invalid sequences by
+    /// `U+FFFD` in the AST, instead of returning [`Error::InvalidUtf8`].
+    ///
+    /// ```rust
+    /// use fallible_iterator::FallibleIterator;
+    /// use sqlite3_parser::lexer::sql::{ErrorKind, Parser};
+    ///
+    /// let input = b"SELECT 'caf\xE9'";
+    /// let err = Parser::new(input).next().unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidUtf8);
+    /// assert_eq!(err.position().unwrap().start, 11);
+    /// let cmd = Parser::new(input).with_lossy_utf8(true).next().unwrap().unwrap();
+    /// assert_eq!(cmd.to_string(), "SELECT 'caf\u{FFFD}';");
+    /// ```
+    pub fn with_lossy_utf8(mut self, lossy: bool) -> Self {
+        self.scanner.splitter_mut().lossy_utf8 = lossy;
+        self
+    }
+    /// Locations of the double-quoted identifiers replaced by string
+    /// literals in the last statement or fragment parsed
+    pub fn dqs_fallbacks(&self) -> &[Span] {
+        &self.dqs_fallbacks
+    }
+    /// Compute line and column numbers with `index` of the input, instead of
+    /// counting new lines while scanning.
+    ///
+    /// The index is kept to convert positions afterwards
+    /// (see [`line_index`](Self::line_index)).
+    ///
+    /// ```rust
+    /// use fallible_iterator::FallibleIterator;
+    /// use sqlite3_parser::lexer::sql::Parser;
+    /// use sqlite3_parser::lexer::{ColumnUnit, LineIndex};
+    ///
+    /// let input = "SELECT 'é';\nSELECT 'é' FROM".as_bytes();
+    /// let mut parser = Parser::new(input).with_line_index(LineIndex::new(input));
+    /// assert!(parser.next().unwrap().is_some());
+    /// let pos = parser.next().unwrap_err().position().unwrap();
+    /// assert_eq!((pos.line, pos.column), (2, 17));
+    /// let index = parser.line_index().unwrap();
+    /// assert_eq!(index.line_col(pos.start, ColumnUnit::Char), (2, 16));
+    /// ```
+    pub fn with_line_index(mut self, index: LineIndex) -> Self {
+        self.scanner = self.scanner.with_line_index(index);
+        self
+    }
+    /// Index of the input, if any
+    pub fn line_index(&self) -> Option<&LineIndex> {
+        self.scanner.line_index()
     }
     /// Parse new `input`
+    ///
+    /// The [line index](Self::with_line_index) of the previous input, if any,
+    /// is dropped.
     pub fn reset(&mut self, input: &'input [u8]) {
         self.input = input;
+        self.scanner.take_line_index();
         self.scanner.reset();
+        self.pending = None;
+        self.dqs_fallbacks.clear();
     }
     /// Current line position in input
     pub fn line(&self) -> u64 {
@@ -152,58 +339,178 @@ fn analyze_filter_keyword(
     Ok(TK_ID)
 }
 
-macro_rules! try_with_position {
-    ($scanner:expr, $expr:expr) => {
-        match $expr {
-            Ok(val) => val,
+impl<'input> Parser<'input> {
+    /// Next token to feed the parser
+    fn next_token(
+        &mut self,
+        last_token_parsed: TokenType,
+    ) -> Result<Option<(TokenType, crate::dialect::Token<'input>)>, Error> {
+        if let Some(next) = self.pending.take() {
+            return Ok(Some(next));
+        }
+        let (start, (value, mut token_type), end) = match self.scanner.scan(self.input)? {
+            (_, None, _) => return Ok(None),
+            (start, Some(tuple), end) => (start, tuple, end),
+        };
+        if token_type >= TK_WINDOW {
+            debug_assert!(
+                token_type == TK_OVER || token_type == TK_FILTER || token_type == TK_WINDOW
+            );
+            self.scanner.mark();
+            if token_type == TK_WINDOW {
+                token_type = analyze_window_keyword(&mut self.scanner, self.input)?;
+            } else if token_type == TK_OVER {
+                token_type =
+                    analyze_over_keyword(&mut self.scanner, self.input, last_token_parsed)?;
+            } else if token_type == TK_FILTER {
+                token_type =
+                    analyze_filter_keyword(&mut self.scanner, self.input, last_token_parsed)?;
+            }
+            self.scanner.reset_to_mark();
+        }
+        let token = token_type.to_token(start + self.offset, value, end + self.offset);
+        Ok(Some((token_type, token)))
+    }
+
+    /// Reject `token` when the target version does not support it
+    fn check_token(
+        &self,
+        token_type: TokenType,
+        token: &crate::dialect::Token<'_>,
+    ) -> Result<(), Error> {
+        if matches!(token_type, TK_INTEGER | TK_FLOAT) && token.1.contains(&b'_') {
+            let span = Span::from(token);
+            if let Err(err) = self.parser.ctx.require("digit separators", 3_046_000, span) {
+                let pos = self.error_position(token.0, token.2);
+                return Err(Error::ParserError(err, Some(pos)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Feed the parser with `token`
+    fn parse_token(
+        &mut self,
+        token_type: TokenType,
+        token: crate::dialect::Token<'input>,
+    ) -> Result<(), Error> {
+        self.check_token(token_type, &token)?;
+        match self.parser.sqlite3Parser(token_type, token) {
+            Ok(()) => Ok(()),
             Err(err) => {
-                let mut err = Error::from(err);
-                err.position($scanner.line(), $scanner.column());
-                return Err(err);
+                let pos = match err.span() {
+                    Some(span) => self.error_position(span.start, span.end),
+                    None => self.error_position(token.0, token.2),
+                };
+                Err(Error::ParserError(err, Some(pos)))
             }
         }
-    };
-}
+    }
 
-impl FallibleIterator for Parser<'_> {
-    type Item = Cmd;
-    type Error = Error;
+    /// Check the length of the statement (or fragment) starting at byte
+    /// `start` and ending with `token`
+    fn check_length(
+        &self,
+        start: usize,
+        token: crate::dialect::Token<'input>,
+    ) -> Result<(), Error> {
+        if token.2 - start > self.parser.ctx.limits().max_sql_length {
+            let pos = self.error_position(token.0, token.2);
+            return Err(Error::ParserError(
+                custom_err!("statement too long"),
+                Some(pos),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Syntax error on `token`, or unexpected end of input when `at_end`
+    fn syntax_error(
+        &self,
+        token_type: TokenType,
+        token: crate::dialect::Token<'input>,
+        at_end: bool,
+        expected: Vec<YYCODETYPE>,
+    ) -> Error {
+        let err = if at_end {
+            ParserError::UnexpectedEof
+        } else {
+            ParserError::SyntaxError {
+                token_type,
+                found: String::from_utf8_lossy(token.1).into_owned(),
+                expected: expected_tokens(expected),
+            }
+        };
+        Error::ParserError(err, Some(self.error_position(token.0, token.2)))
+    }
 
-    fn next(&mut self) -> Result<Option<Cmd>, Error> {
+    /// Position of the `start..end` byte range, which must not be after
+    /// the scanner current position
+    fn error_position(&self, start: usize, end: usize) -> Position {
+        let offset = self.scanner.offset();
+        let rel_start = start.saturating_sub(self.offset).min(offset);
+        let skipped = &self.input[rel_start..offset];
+        let newlines = memchr_iter(b'\n', skipped).count();
+        let column = if newlines == 0 {
+            self.scanner.column() - skipped.len()
+        } else {
+            match memrchr(b'\n', &self.input[..rel_start]) {
+                Some(i) => rel_start - i,
+                None => rel_start + 1,
+            }
+        };
+        Position {
+            start,
+            end,
+            line: self.scanner.line() - newlines as u64,
+            column,
+        }
+    }
+
+    /// Skip the rest of the statement starting at byte `start`, up to the
+    /// first statement boundary not before byte `error_end`.
+    ///
+    /// Boundaries are found like `sqlite3_complete` does: a `;` inside the
+    /// body of a `CREATE TRIGGER` does not end the statement.
+    fn skip_statement(&mut self, start: usize, error_end: usize) {
+        let mut state = State::Invalid;
+        let mut offset = start;
+        while offset < self.input.len() {
+            let (amt, end) = state.scan(&self.input[offset..], true);
+            offset += amt;
+            if !end || offset >= error_end {
+                break;
+            }
+        }
+        self.scanner.skip_to(self.input, offset);
+    }
+
+    fn parse_cmd(&mut self) -> Result<Option<Cmd<'input>>, Error> {
         //print!("line: {}, column: {}: ", self.scanner.line(), self.scanner.column());
         self.parser.ctx.reset();
+        self.dqs_fallbacks.clear();
         let mut last_token_parsed = TK_EOF;
+        let mut last_token = sentinel(self.offset);
+        let mut start = self.offset;
         let mut eof = false;
         loop {
-            let (start, (value, mut token_type), end) = match self.scanner.scan(self.input)? {
-                (_, None, _) => {
-                    eof = true;
-                    break;
-                }
-                (start, Some(tuple), end) => (start, tuple, end),
-            };
-            let token = if token_type >= TK_WINDOW {
-                debug_assert!(
-                    token_type == TK_OVER || token_type == TK_FILTER || token_type == TK_WINDOW
-                );
-                self.scanner.mark();
-                if token_type == TK_WINDOW {
-                    token_type = analyze_window_keyword(&mut self.scanner, self.input)?;
-                } else if token_type == TK_OVER {
-                    token_type =
-                        analyze_over_keyword(&mut self.scanner, self.input, last_token_parsed)?;
-                } else if token_type == TK_FILTER {
-                    token_type =
-                        analyze_filter_keyword(&mut self.scanner, self.input, last_token_parsed)?;
-                }
-                self.scanner.reset_to_mark();
-                token_type.to_token(start, value, end)
-            } else {
-                token_type.to_token(start, value, end)
+            let offset = self.offset;
+            let next = self.next_token(last_token_parsed).map_err(|mut err| {
+                err.adjust_offset(offset);
+                err
+            })?;
+            let Some((token_type, token)) = next else {
+                eof = true;
+                break;
             };
             //println!("({:?}, {:?})", token_type, token);
-            try_with_position!(self.scanner, self.parser.sqlite3Parser(token_type, token));
+            if last_token_parsed == TK_EOF {
+                start = token.0;
+            }
+            self.check_length(start, token)?;
+            self.parse_token(token_type, token)?;
             last_token_parsed = token_type;
+            last_token = token;
             if self.parser.ctx.done() {
                 //println!();
                 break;
@@ -215,46 +522,176 @@ impl FallibleIterator for Parser<'_> {
         /* Upon reaching the end of input, call the parser two more times
         with tokens TK_SEMI and 0, in that order. */
         if eof && self.parser.ctx.is_ok() {
+            last_token = sentinel(self.offset + self.input.len());
             if last_token_parsed != TK_SEMI {
-                try_with_position!(
-                    self.scanner,
-                    self.parser
-                        .sqlite3Parser(TK_SEMI, sentinel(self.input.len()))
-                );
-            }
-            try_with_position!(
-                self.scanner,
-                self.parser
-                    .sqlite3Parser(TK_EOF, sentinel(self.input.len()))
-            );
+                self.parse_token(TK_SEMI, last_token)?;
+            }
+            self.parse_token(TK_EOF, last_token)?;
         }
         self.parser.sqlite3ParserFinalize();
         if let Some(e) = self.parser.ctx.error() {
-            let err = Error::ParserError(e, Some((self.scanner.line(), self.scanner.column())));
-            return Err(err);
+            let pos = self.error_position(last_token.0, last_token.2);
+            return Err(Error::ParserError(e, Some(pos)));
         }
-        let cmd = self.parser.ctx.cmd();
-        if let Some(ref cmd) = cmd {
-            if let Err(e) = cmd.check() {
-                let err = Error::ParserError(e, Some((self.scanner.line(), self.scanner.column())));
-                return Err(err);
-            }
+        let mut cmd = self.parser.ctx.cmd();
+        if let Some(ref mut cmd) = cmd {
+            self.check(cmd)?;
         }
         Ok(cmd)
     }
+
+    /// Additional checks, then double-quoted string literals fallback
+    fn check(&mut self, cmd: &mut Cmd<'input>) -> Result<(), Error> {
+        let res = cmd.check().and_then(|_| match self.dqs {
+            Some(dqs) => {
+                self.dqs_fallbacks = cmd.apply_dqs(dqs)?;
+                Ok(())
+            }
+            None => Ok(()),
+        });
+        res.map_err(|e| {
+            let span = e.span().unwrap_or_else(|| cmd.span());
+            let pos = self.error_position(span.start, span.end);
+            Error::ParserError(e, Some(pos))
+        })
+    }
+}
+
+impl<'input> FallibleIterator for Parser<'input> {
+    type Item = Cmd<'input>;
+    type Error = Error;
+
+    /// Parse the next statement.
+    ///
+    /// After an error, the rest of the broken statement is skipped so that
+    /// the following call resumes at the next statement.
+    fn next(&mut self) -> Result<Option<Cmd<'input>>, Error> {
+        let start = self.scanner.offset();
+        self.parse_cmd().inspect_err(|err| {
+            self.parser.sqlite3ParserFinalize();
+            let error_end = err
+                .position()
+                .map_or(0, |pos| pos.end.saturating_sub(self.offset));
+            self.skip_statement(start, error_end.max(self.scanner.offset()));
+        })
+    }
 }
 
 /// SQL token
 pub type Token<'input> = (&'input [u8], TokenType);
 
+/// Syntax accepted by the [`Tokenizer`], to reject what older SQLite
+/// versions or specific builds do not support.
+///
+/// By default, everything supported by the latest SQLite version is
+/// accepted.
+///
+/// ```rust
+/// use sqlite3_parser::lexer::sql::{Tokenizer, TokenizerOptions};
+/// use sqlite3_parser::lexer::Scanner;
+///
+/// let options = TokenizerOptions {
+///     digit_separators: false,
+///     parameter_prefixes: b":",
+///     ..TokenizerOptions::default()
+/// };
+/// let mut s = Scanner::new(Tokenizer::new().with_options(options));
+/// assert!(s.scan(b"1_000").is_err());
+/// s.reset();
+/// assert!(s.scan(b"@p").is_err());
+/// s.reset();
+/// assert!(s.scan(b":p").is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenizerOptions {
+    /// Accept `_` digit separators in numeric literals (SQLite 3.46)
+    pub digit_separators: bool,
+    /// Accepted prefixes of named parameters, among `$`, `@`, `#` and `:`
+    /// (`?` is always accepted)
+    pub parameter_prefixes: &'static [u8],
+    /// Accept MS Access / SQL Server style `[...]` identifiers
+    pub brackets: bool,
+    /// Accept MySQL style `` `...` `` identifiers
+    pub backticks: bool,
+}
+
+impl Default for TokenizerOptions {
+    fn default() -> Self {
+        Self {
+            digit_separators: true,
+            parameter_prefixes: b"$@#:",
+            brackets: true,
+            backticks: true,
+        }
+    }
+}
+
 /// SQL lexer
 #[derive(Default)]
-pub struct Tokenizer {}
+pub struct Tokenizer {
+    /// whether white spaces and comments are returned as tokens or skipped
+    trivia: bool,
+    options: TokenizerOptions,
+    /// whether invalid UTF-8 sequences are accepted
+    lossy_utf8: bool,
+}
 
 impl Tokenizer {
     /// Constructor
     pub fn new() -> Self {
-        Self {}
+        Self {
+            trivia: false,
+            options: TokenizerOptions::default(),
+            lossy_utf8: false,
+        }
+    }
+    /// Accept invalid UTF-8 sequences instead of returning
+    /// [`Error::InvalidUtf8`].
+    ///
+    /// Tokens are left untouched but invalid sequences are replaced by
+    /// `U+FFFD` in the AST.
+    pub fn with_lossy_utf8(mut self, lossy: bool) -> Self {
+        self.lossy_utf8 = lossy;
+        self
+    }
+    /// Whether invalid UTF-8 sequences are accepted
+    pub fn lossy_utf8(&self) -> bool {
+        self.lossy_utf8
+    }
+    /// Restrict the accepted syntax
+    pub fn with_options(mut self, options: TokenizerOptions) -> Self {
+        self.options = options;
+        self
+    }
+    /// Accepted syntax
+    pub fn options(&self) -> &TokenizerOptions {
+        &self.options
+    }
+    /// Return white spaces as `TK_SPACE` and comments as `TK_COMMENT` tokens
+    /// instead of skipping them.
+    ///
+    /// Then the ranges of all tokens returned by [`Scanner::scan`] cover the
+    /// whole input, without gap.
+    /// Should not be used to feed the parser.
+    pub fn with_trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
+    /// Whether white spaces and comments are returned as tokens
+    pub fn trivia(&self) -> bool {
+        self.trivia
+    }
+    fn skip<'input>(
+        &self,
+        data: &'input [u8],
+        amt: usize,
+        token_type: TokenType,
+    ) -> (Option<Token<'input>>, usize) {
+        if self.trivia {
+            (Some((&data[..amt], token_type)), amt)
+        } else {
+            (None, amt)
+        }
     }
 }
 
@@ -276,15 +713,39 @@ impl Splitter for Tokenizer {
     fn split<'input>(
         &mut self,
         data: &'input [u8],
+    ) -> Result<(Option<Token<'input>>, usize), Error> {
+        let (token, amt) = self.split_token(data)?;
+        let consumed = &data[..amt];
+        if !self.lossy_utf8 && !consumed.is_ascii() {
+            if let Err(err) = str::from_utf8(consumed) {
+                let start = err.valid_up_to();
+                // a truncated sequence at the end of the token is invalid too
+                let len = err.error_len().unwrap_or(amt - start);
+                return Err(Error::InvalidUtf8(Position::relative_range(
+                    data,
+                    start,
+                    start + len,
+                )));
+            }
+        }
+        Ok((token, amt))
+    }
+}
+
+impl Tokenizer {
+    fn split_token<'input>(
+        &mut self,
+        data: &'input [u8],
     ) -> Result<(Option<Token<'input>>, usize), Error> {
         if data[0].is_ascii_whitespace() {
             // eat as much space as possible
-            return Ok((
-                None,
+            return Ok(self.skip(
+                data,
                 match data.iter().skip(1).position(|&b| !b.is_ascii_whitespace()) {
                     Some(i) => i + 1,
                     _ => data.len(),
                 },
+                TK_SPACE,
             ));
         }
         match data[0] {
@@ -293,9 +754,14 @@ impl Splitter for Tokenizer {
                     if *b == b'-' {
                         // eat comment
                         if let Some(i) = memchr(b'\n', data) {
-                            Ok((None, i + 1))
+                            if self.trivia {
+                                // the new line is returned as a space
+                                Ok(self.skip(data, i, TK_COMMENT))
+                            } else {
+                                Ok((None, i + 1))
+                            }
                         } else {
-                            Ok((None, data.len()))
+                            Ok(self.skip(data, data.len(), TK_COMMENT))
                         }
                     } else if *b == b'>' {
                         if let Some(b) = data.get(2) {
@@ -330,9 +796,11 @@ impl Splitter for Tokenizer {
                             pb = *b;
                         }
                         if let Some(i) = end {
-                            Ok((None, i + 1))
+                            Ok(self.skip(data, i + 1, TK_COMMENT))
                         } else {
-                            Err(Error::UnterminatedBlockComment(None))
+                            Err(Error::UnterminatedBlockComment(Position::relative(
+                                data.len(),
+                            )))
                         }
                     } else {
                         Ok((Some((&data[..1], TK_SLASH)), 1))
@@ -381,10 +849,10 @@ impl Splitter for Tokenizer {
                     if *b == b'=' {
                         Ok((Some((&data[..2], TK_NE)), 2))
                     } else {
-                        Err(Error::ExpectedEqualsSign(None))
+                        Err(Error::ExpectedEqualsSign(Position::relative(1)))
                     }
                 } else {
-                    Err(Error::ExpectedEqualsSign(None))
+                    Err(Error::ExpectedEqualsSign(Position::relative(1)))
                 }
             }
             b'|' => {
@@ -401,11 +869,12 @@ impl Splitter for Tokenizer {
             b',' => Ok((Some((&data[..1], TK_COMMA)), 1)),
             b'&' => Ok((Some((&data[..1], TK_BITAND)), 1)),
             b'~' => Ok((Some((&data[..1], TK_BITNOT)), 1)),
+            b'`' if !self.options.backticks => Err(Error::UnrecognizedToken(Position::relative(1))),
             quote @ (b'`' | b'\'' | b'"') => literal(data, quote),
             b'.' => {
                 if let Some(b) = data.get(1) {
                     if b.is_ascii_digit() {
-                        fractional_part(data, 0)
+                        fractional_part(data, 0, self.options.digit_separators)
                     } else {
                         Ok((Some((&data[..1], TK_DOT)), 1))
                     }
@@ -413,13 +882,14 @@ impl Splitter for Tokenizer {
                     Ok((Some((&data[..1], TK_DOT)), 1))
                 }
             }
-            b'0'..=b'9' => number(data),
+            b'0'..=b'9' => number(data, self.options.digit_separators),
+            b'[' if !self.options.brackets => Err(Error::UnrecognizedToken(Position::relative(1))),
             b'[' => {
                 if let Some(i) = memchr(b']', data) {
                     // Keep original quotes / '[' ... ’]'
                     Ok((Some((&data[0..=i], TK_ID)), i + 1))
                 } else {
-                    Err(Error::UnterminatedBracket(None))
+                    Err(Error::UnterminatedBracket(Position::relative(data.len())))
                 }
             }
             b'?' => {
@@ -431,20 +901,23 @@ impl Splitter for Tokenizer {
                     None => Ok((Some((&data[1..], TK_VARIABLE)), data.len())),
                 }
             }
+            b'$' | b'@' | b'#' | b':' if !self.options.parameter_prefixes.contains(&data[0]) => {
+                Err(Error::UnrecognizedToken(Position::relative(1)))
+            }
             b'$' | b'@' | b'#' | b':' => {
                 match data
                     .iter()
                     .skip(1)
                     .position(|&b| !is_identifier_continue(b))
                 {
-                    Some(0) => Err(Error::BadVariableName(None)),
+                    Some(0) => Err(Error::BadVariableName(Position::relative(1))),
                     Some(i) => {
                         // '$' is included as part of the name
                         Ok((Some((&data[..=i], TK_VARIABLE)), i + 1))
                     }
                     None => {
                         if data.len() == 1 {
-                            return Err(Error::BadVariableName(None));
+                            return Err(Error::BadVariableName(Position::relative(1)));
                         }
                         Ok((Some((data, TK_VARIABLE)), data.len()))
                     }
@@ -461,7 +934,7 @@ impl Splitter for Tokenizer {
                     Ok(self.identifierish(data))
                 }
             }
-            _ => Err(Error::UnrecognizedToken(None)),
+            _ => Err(Error::UnrecognizedToken(Position::relative(1))),
         }
     }
 }
@@ -493,7 +966,7 @@ fn literal(data: &[u8], quote: u8) -> Result<(Option<Token<'_>>, usize), Error>
         // keep original quotes in the token
         Ok((Some((&data[0..i], tt)), i))
     } else {
-        Err(Error::UnterminatedLiteral(None))
+        Err(Error::UnterminatedLiteral(Position::relative(data.len())))
     }
 }
 
@@ -507,32 +980,32 @@ fn blob_literal(data: &[u8]) -> Result<(Option<Token<'_>>, usize), Error> {
         .find(|&(_, &b)| !b.is_ascii_hexdigit())
     {
         if *b != b'\'' || i % 2 != 0 {
-            return Err(Error::MalformedBlobLiteral(None));
+            return Err(Error::MalformedBlobLiteral(Position::relative(i + 1)));
         }
         Ok((Some((&data[2..i], TK_BLOB)), i + 1))
     } else {
-        Err(Error::MalformedBlobLiteral(None))
+        Err(Error::MalformedBlobLiteral(Position::relative(data.len())))
     }
 }
 
-fn number(data: &[u8]) -> Result<(Option<Token<'_>>, usize), Error> {
+fn number(data: &[u8], separators: bool) -> Result<(Option<Token<'_>>, usize), Error> {
     debug_assert!(data[0].is_ascii_digit());
     if data[0] == b'0' {
         if let Some(b) = data.get(1) {
             if *b == b'x' || *b == b'X' {
-                return hex_integer(data);
+                return hex_integer(data, separators);
             }
         } else {
             return Ok((Some((data, TK_INTEGER)), data.len()));
         }
     }
-    if let Some((i, b)) = find_end_of_number(data, 1, u8::is_ascii_digit)? {
+    if let Some((i, b)) = find_end_of_number(data, 1, u8::is_ascii_digit, separators)? {
         if b == b'.' {
-            return fractional_part(data, i);
+            return fractional_part(data, i, separators);
         } else if b == b'e' || b == b'E' {
-            return exponential_part(data, i);
+            return exponential_part(data, i, separators);
         } else if is_identifier_start(b) {
-            return Err(Error::BadNumber(None));
+            return Err(Error::BadNumber(end_of_word(data, i)));
         }
         Ok((Some((&data[..i], TK_INTEGER)), i))
     } else {
@@ -540,31 +1013,35 @@ fn number(data: &[u8]) -> Result<(Option<Token<'_>>, usize), Error> {
     }
 }
 
-fn hex_integer(data: &[u8]) -> Result<(Option<Token<'_>>, usize), Error> {
+fn hex_integer(data: &[u8], separators: bool) -> Result<(Option<Token<'_>>, usize), Error> {
     debug_assert_eq!(data[0], b'0');
     debug_assert!(data[1] == b'x' || data[1] == b'X');
-    if let Some((i, b)) = find_end_of_number(data, 2, u8::is_ascii_hexdigit)? {
+    if let Some((i, b)) = find_end_of_number(data, 2, u8::is_ascii_hexdigit, separators)? {
         // Must not be empty (Ox is invalid)
         if i == 2 || is_identifier_start(b) {
-            return Err(Error::MalformedHexInteger(None));
+            return Err(Error::MalformedHexInteger(end_of_word(data, i)));
         }
         Ok((Some((&data[..i], TK_INTEGER)), i))
     } else {
         // Must not be empty (Ox is invalid)
         if data.len() == 2 {
-            return Err(Error::MalformedHexInteger(None));
+            return Err(Error::MalformedHexInteger(Position::relative(2)));
         }
         Ok((Some((data, TK_INTEGER)), data.len()))
     }
 }
 
-fn fractional_part(data: &[u8], i: usize) -> Result<(Option<Token<'_>>, usize), Error> {
+fn fractional_part(
+    data: &[u8],
+    i: usize,
+    separators: bool,
+) -> Result<(Option<Token<'_>>, usize), Error> {
     debug_assert_eq!(data[i], b'.');
-    if let Some((i, b)) = find_end_of_number(data, i + 1, u8::is_ascii_digit)? {
+    if let Some((i, b)) = find_end_of_number(data, i + 1, u8::is_ascii_digit, separators)? {
         if b == b'e' || b == b'E' {
-            return exponential_part(data, i);
+            return exponential_part(data, i, separators);
         } else if is_identifier_start(b) {
-            return Err(Error::BadNumber(None));
+            return Err(Error::BadNumber(end_of_word(data, i)));
         }
         Ok((Some((&data[..i], TK_FLOAT)), i))
     } else {
@@ -572,24 +1049,28 @@ fn fractional_part(data: &[u8], i: usize) -> Result<(Option<Token<'_>>, usize),
     }
 }
 
-fn exponential_part(data: &[u8], i: usize) -> Result<(Option<Token<'_>>, usize), Error> {
+fn exponential_part(
+    data: &[u8],
+    i: usize,
+    separators: bool,
+) -> Result<(Option<Token<'_>>, usize), Error> {
     debug_assert!(data[i] == b'e' || data[i] == b'E');
     // data[i] == 'e'|'E'
     if let Some(b) = data.get(i + 1) {
         let i = if *b == b'+' || *b == b'-' { i + 1 } else { i };
-        if let Some((j, b)) = find_end_of_number(data, i + 1, u8::is_ascii_digit)? {
+        if let Some((j, b)) = find_end_of_number(data, i + 1, u8::is_ascii_digit, separators)? {
             if j == i + 1 || is_identifier_start(b) {
-                return Err(Error::BadNumber(None));
+                return Err(Error::BadNumber(end_of_word(data, j)));
             }
             Ok((Some((&data[..j], TK_FLOAT)), j))
         } else {
             if data.len() == i + 1 {
-                return Err(Error::BadNumber(None));
+                return Err(Error::BadNumber(Position::relative(data.len())));
             }
             Ok((Some((data, TK_FLOAT)), data.len()))
         }
     } else {
-        Err(Error::BadNumber(None))
+        Err(Error::BadNumber(Position::relative(data.len())))
     }
 }
 
@@ -597,15 +1078,16 @@ fn find_end_of_number(
     data: &[u8],
     i: usize,
     test: fn(&u8) -> bool,
+    separators: bool,
 ) -> Result<Option<(usize, u8)>, Error> {
     for (j, &b) in data.iter().enumerate().skip(i) {
         if test(&b) {
             continue;
-        } else if b == b'_' {
+        } else if b == b'_' && separators {
             if j >= 1 && data.get(j - 1).is_some_and(test) && data.get(j + 1).is_some_and(test) {
                 continue;
             }
-            return Err(Error::BadNumber(None));
+            return Err(Error::BadNumber(Position::relative(j + 1)));
         } else {
             return Ok(Some((j, b)));
         }
@@ -613,6 +1095,16 @@ fn find_end_of_number(
     Ok(None)
 }
 
+/// Position of a malformed number: up to the end of the identifier-like
+/// characters following `data[..i]`
+fn end_of_word(data: &[u8], i: usize) -> Option<Position> {
+    let n = data[i..]
+        .iter()
+        .take_while(|&&b| is_identifier_continue(b))
+        .count();
+    Position::relative(i + n)
+}
+
 impl Tokenizer {
     fn identifierish<'input>(&mut self, data: &'input [u8]) -> (Option<Token<'input>>, usize) {
         debug_assert!(is_identifier_start(data[0]));
@@ -663,6 +1155,201 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn trivia() -> Result<(), Error> {
+        let input = b"-- header\nSELECT/* c */x'01', ?1\t, 'a' --";
+        let tokenizer = Tokenizer::new().with_trivia(true);
+        let mut s = Scanner::new(tokenizer);
+        let mut output = Vec::new();
+        let mut types = Vec::new();
+        loop {
+            match s.scan(input)? {
+                (_, None, _) => break,
+                (start, Some((_, tt)), end) => {
+                    output.extend_from_slice(&input[start..end]);
+                    types.push(tt);
+                }
+            }
+        }
+        assert_eq!(input[..], output[..]);
+        use TokenType::*;
+        assert_eq!(
+            types,
+            [
+                TK_COMMENT,
+                TK_SPACE,
+                TK_SELECT,
+                TK_COMMENT,
+                TK_BLOB,
+                TK_COMMA,
+                TK_SPACE,
+                TK_VARIABLE,
+                TK_SPACE,
+                TK_COMMA,
+                TK_SPACE,
+                TK_STRING,
+                TK_SPACE,
+                TK_COMMENT
+            ]
+        );
+        // codes unchanged by the tokens added to the grammar since
+        assert_eq!((TK_SPACE as u16, TK_COMMENT as u16), (168, 169));
+        Ok(())
+    }
+
+    #[test]
+    fn token_type_from_code() {
+        use TokenType::*;
+        for code in 0..=TK_FRAGMENT_QUALIFIED_NAME as u16 {
+            assert_eq!(TokenType::try_from(code).map(|tt| tt as u16), Ok(code));
+        }
+        assert_eq!(TokenType::try_from(175), Err(175));
+        assert_eq!(TokenType::try_from(u16::MAX), Err(u16::MAX));
+    }
+
+    #[test]
+    fn tokenizer_options() {
+        use super::{ErrorKind, TokenizerOptions};
+        fn scan(options: TokenizerOptions, input: &[u8]) -> Result<Vec<u8>, ErrorKind> {
+            let mut s = Scanner::new(Tokenizer::new().with_options(options));
+            match s.scan(input) {
+                Ok((_, Some((token, _)), _)) => Ok(token.to_vec()),
+                Ok((_, None, _)) => Ok(vec![]),
+                Err(err) => Err(err.kind()),
+            }
+        }
+        let default = TokenizerOptions::default();
+        for input in [
+            &b"1_000"[..],
+            b"0x1_0",
+            b"1.0_1",
+            b".1_1",
+            b"1e1_0",
+            b"$a",
+            b"@a",
+            b"#a",
+            b":a",
+            b"[a]",
+            b"`a`",
+        ] {
+            assert_eq!(scan(default, input), Ok(input.to_vec()));
+        }
+        let strict = TokenizerOptions {
+            digit_separators: false,
+            parameter_prefixes: b":",
+            brackets: false,
+            backticks: false,
+        };
+        for (input, kind) in [
+            (&b"1_000"[..], ErrorKind::BadNumber),
+            (b"0x1_0", ErrorKind::MalformedHexInteger),
+            (b"1.0_1", ErrorKind::BadNumber),
+            (b".1_1", ErrorKind::BadNumber),
+            (b"1e1_0", ErrorKind::BadNumber),
+            (b"$a", ErrorKind::UnrecognizedToken),
+            (b"@a", ErrorKind::UnrecognizedToken),
+            (b"#a", ErrorKind::UnrecognizedToken),
+            (b"[a]", ErrorKind::UnrecognizedToken),
+            (b"`a`", ErrorKind::UnrecognizedToken),
+        ] {
+            assert_eq!(scan(strict, input), Err(kind));
+        }
+        for input in [
+            &b"1000"[..],
+            b"0x10",
+            b"1.01e10",
+            b":a",
+            b"?1",
+            b"\"a\"",
+            b"'['",
+        ] {
+            assert_eq!(scan(strict, input).map(|_| ()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn is_complete() {
+        use super::is_complete;
+        assert!(!is_complete(b""));
+        assert!(!is_complete(b" -- ;"));
+        assert!(is_complete(b";"));
+        assert!(is_complete(b"SELECT 1;"));
+        assert!(is_complete(b"SELECT 1; /* ; */\n"));
+        assert!(!is_complete(b"SELECT 1"));
+        assert!(!is_complete(b"SELECT 1 -- ;"));
+        assert!(!is_complete(b"SELECT 1 /* ; */"));
+        assert!(!is_complete(b"SELECT 1 /* ;"));
+        assert!(!is_complete(b"SELECT 'a;"));
+        assert!(is_complete(b"SELECT 'a;';"));
+        assert!(is_complete(b"SELECT \"a;\", [b;], `c;`;"));
+        assert!(!is_complete(b"SELECT 1; SELECT 2"));
+        let trigger = b"CREATE TEMP TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1; SELECT 2;";
+        assert!(!is_complete(trigger));
+        assert!(!is_complete(
+            b"CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1; END"
+        ));
+        assert!(is_complete(
+            b"CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1; END;"
+        ));
+        assert!(is_complete(
+            b"EXPLAIN CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1; END;"
+        ));
+        assert!(!is_complete(
+            b"EXPLAIN CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1;"
+        ));
+        // not a trigger
+        assert!(is_complete(b"CREATE TABLE trigger(x); "));
+    }
+
+    #[test]
+    fn complete_checker() {
+        use super::CompleteChecker;
+        let mut checker = CompleteChecker::new();
+        assert!(!checker.feed(b"CREATE TRIGGER tr AFTER INSERT ON t BEGIN\n"));
+        assert!(!checker.feed(b"  SELECT ';\n"));
+        assert!(!checker.feed(b"'; -- END;\n"));
+        assert!(!checker.feed(b"E"));
+        assert!(!checker.feed(b"ND"));
+        assert!(checker.feed(b";"));
+        assert!(!checker.feed(b" SELECT /*"));
+        assert!(!checker.feed(b";*"));
+        assert!(!checker.feed(b"/ 1"));
+        assert!(checker.feed(b";\n"));
+        checker.reset();
+        assert!(!checker.is_complete());
+        assert!(!checker.feed(b"SELECT x'"));
+        assert!(checker.feed(b"00';"));
+        checker.reset();
+        assert!(checker.feed(b";"));
+        for (chunks, complete) in [
+            (&[&b"SELECT 'a'"[..], b"'b'", b";"][..], true),
+            (&[b"SELECT 'a'", b"';"], false),
+            (&[b"SELECT [a", b";]", b";"], true),
+            (&[b"SELECT 1 /", b"* ;*", b"/;"], true),
+            (&[b"SELECT 1 /*", b"/;"], false),
+            (&[b"SELECT 1; --", b" ;"], true),
+            (&[b"SELECT 1 --", b" ;\n", b";"], true),
+        ] {
+            let mut checker = CompleteChecker::new();
+            for chunk in chunks {
+                checker.feed(chunk);
+            }
+            assert_eq!(checker.is_complete(), complete, "{chunks:?}");
+        }
+    }
+
+    #[test]
+    fn complete_checker_long_comment() {
+        use super::CompleteChecker;
+        let mut checker = CompleteChecker::new();
+        assert!(!checker.feed(b"SELECT 1 /* a comment;\n"));
+        for _ in 0..10_000 {
+            assert!(!checker.feed(b"which spans many lines; * / ' \"\n"));
+        }
+        assert!(!checker.feed(b"*/"));
+        assert!(checker.feed(b";\n"));
+    }
+
     fn expect_token(
         s: &mut Scanner<Tokenizer>,
         input: &[u8],
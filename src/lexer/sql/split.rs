@@ -0,0 +1,184 @@
+//! Statement splitting, keeping the original source text
+use std::ops::Range;
+
+use fallible_iterator::FallibleIterator;
+
+use super::complete::State;
+use super::{Error, Parser, ParserLimits, Tokenizer, TokenizerOptions};
+use crate::dialect::TokenType::*;
+use crate::lexer::scan::Splitter;
+use crate::parser::ast::{Cmd, Dqs};
+
+/// Statement with its original source text
+#[derive(Debug)]
+pub struct Statement<'input> {
+    /// Byte range of the statement in the input.
+    /// Includes the comments preceding the statement and its terminating `;`
+    /// if any.
+    pub range: Range<usize>,
+    /// Original text of the statement: `&input[range]`
+    pub sql: &'input [u8],
+    /// Parsed statement
+    pub cmd: Cmd<'input>,
+}
+
+/// Iterator over the statements of an input, with their original text.
+///
+/// Like [`Parser`], after an error, the rest of the broken statement is
+/// skipped so that the following call resumes at the next statement.
+///
+/// ```rust
+/// use fallible_iterator::FallibleIterator;
+/// use sqlite3_parser::lexer::sql::Statements;
+///
+/// let sql = b"-- first\nSELECT 1; /* second */ SELECT 2";
+/// let mut statements = Statements::new(sql);
+/// let stmt = statements.next().unwrap().unwrap();
+/// assert_eq!(stmt.range, 0..18);
+/// assert_eq!(stmt.sql, b"-- first\nSELECT 1;");
+/// let stmt = statements.next().unwrap().unwrap();
+/// assert_eq!(stmt.sql, b"/* second */ SELECT 2");
+/// assert_eq!(stmt.cmd.to_string(), "SELECT 2;");
+/// assert!(statements.next().unwrap().is_none());
+/// ```
+pub struct Statements<'input> {
+    parser: Parser<'input>,
+}
+
+impl<'input> Statements<'input> {
+    /// Constructor
+    pub fn new(input: &'input [u8]) -> Self {
+        Self {
+            parser: Parser::new(input),
+        }
+    }
+    /// Like [`Parser::with_dqs`]
+    pub fn with_dqs(mut self, dqs: Dqs) -> Self {
+        self.parser = self.parser.with_dqs(dqs);
+        self
+    }
+    /// Like [`Parser::with_tokenizer_options`]
+    pub fn with_tokenizer_options(mut self, options: TokenizerOptions) -> Self {
+        self.parser = self.parser.with_tokenizer_options(options);
+        self
+    }
+    /// Like [`Parser::with_limits`]
+    pub fn with_limits(mut self, limits: ParserLimits) -> Self {
+        self.parser = self.parser.with_limits(limits);
+        self
+    }
+    /// Like [`Parser::with_target_version`]
+    pub fn with_target_version(mut self, version: u32) -> Self {
+        self.parser = self.parser.with_target_version(version);
+        self
+    }
+    /// Like [`Parser::with_lossy_utf8`]
+    pub fn with_lossy_utf8(mut self, lossy: bool) -> Self {
+        self.parser = self.parser.with_lossy_utf8(lossy);
+        self
+    }
+}
+
+impl<'input> FallibleIterator for Statements<'input> {
+    type Item = Statement<'input>;
+    type Error = Error;
+
+    fn next(&mut self) -> Result<Option<Statement<'input>>, Error> {
+        let input = self.parser.input;
+        let start = self.parser.scanner.offset();
+        let Some(cmd) = self.parser.next()? else {
+            return Ok(None);
+        };
+        let start = start + leading_trivia(&input[start..]);
+        let end = trim_end(input, self.parser.scanner.offset()).max(start);
+        let offset = self.parser.offset;
+        Ok(Some(Statement {
+            range: offset + start..offset + end,
+            sql: &input[start..end],
+            cmd,
+        }))
+    }
+}
+
+/// Iterator over the statements of an input, with their original text,
+/// found by the tokenizer only: statements are neither parsed nor checked.
+///
+/// Boundaries are found like [`is_complete`](super::is_complete) does.
+/// Comments are attached to the statement which follows them and empty
+/// statements are skipped.
+///
+/// ```rust
+/// use sqlite3_parser::lexer::sql::StatementRanges;
+///
+/// let sql = b"SELECT ';'; ; CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1; END";
+/// let statements: Vec<_> = StatementRanges::new(sql).collect();
+/// assert_eq!(statements.len(), 2);
+/// assert_eq!(statements[0], (0..11, &b"SELECT ';';"[..]));
+/// assert_eq!(statements[1].0, 14..sql.len());
+/// ```
+pub struct StatementRanges<'input> {
+    input: &'input [u8],
+    offset: usize,
+}
+
+impl<'input> StatementRanges<'input> {
+    /// Constructor
+    pub fn new(input: &'input [u8]) -> Self {
+        Self { input, offset: 0 }
+    }
+}
+
+impl<'input> Iterator for StatementRanges<'input> {
+    type Item = (Range<usize>, &'input [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.input;
+        if self.offset >= input.len() {
+            return None;
+        }
+        let start = self.offset + leading_trivia(&input[self.offset..]);
+        let mut state = State::Invalid;
+        let (amt, _) = state.scan(&input[start..], true);
+        self.offset = start + amt;
+        if state == State::Invalid {
+            // only comments up to the end of input
+            self.offset = input.len();
+            return None;
+        }
+        let end = trim_end(input, self.offset);
+        Some((start..end, &input[start..end]))
+    }
+}
+
+/// Length of the white spaces, comments and `;` at the start of `data`
+/// which precede the next statement.
+/// Comments after the last `;` belong to the next statement.
+fn leading_trivia(data: &[u8]) -> usize {
+    let mut tokenizer = Tokenizer::new().with_trivia(true).with_lossy_utf8(true);
+    let mut offset = 0;
+    let mut last_semi = 0;
+    while offset < data.len() {
+        match tokenizer.split(&data[offset..]) {
+            Ok((Some((_, TK_SPACE | TK_COMMENT)), amt)) => offset += amt,
+            Ok((Some((_, TK_SEMI)), amt)) => {
+                offset += amt;
+                last_semi = offset;
+            }
+            _ => break,
+        }
+    }
+    let data = &data[last_semi..];
+    last_semi
+        + data
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or(data.len())
+}
+
+/// `end` without the white spaces which precede it
+fn trim_end(input: &[u8], end: usize) -> usize {
+    input[..end]
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(0, |i| i + 1)
+}
@@ -0,0 +1,224 @@
+//! Reader-backed SQL parser
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+use fallible_iterator::FallibleIterator;
+use memchr::{memchr_iter, memrchr};
+
+use super::complete::State;
+use super::{Error, Parser, ParserLimits, TokenizerOptions};
+use crate::parser::ast::{Cmd, Dqs, IntoOwned};
+
+const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/// SQL parser over an [`io::Read`]
+///
+/// Input is buffered one statement at a time: once a complete statement has
+/// been read, it is parsed and its bytes are discarded. So memory usage is
+/// bounded by the largest statement, not by the size of the input.
+/// Spans are byte offsets in the whole input.
+///
+/// ```rust
+/// use fallible_iterator::FallibleIterator;
+/// use sqlite3_parser::lexer::sql::StreamParser;
+///
+/// let input: &[u8] = b"CREATE TABLE t(x); INSERT INTO t VALUES (1);";
+/// let mut parser = StreamParser::new(input);
+/// assert!(parser.next().unwrap().is_some());
+/// assert!(parser.next().unwrap().is_some());
+/// assert!(parser.next().unwrap().is_none());
+/// ```
+pub struct StreamParser<R: Read> {
+    reader: R,
+    /// bytes read but not parsed yet
+    buf: Vec<u8>,
+    /// minimal amount of bytes requested on each read
+    buf_size: usize,
+    /// offset in `buf` up to which statement boundaries have been looked for
+    scanned: usize,
+    /// completeness state at `scanned`
+    state: State,
+    eof: bool,
+    /// line number of `buf[0]` in the input
+    line: u64,
+    /// column number of `buf[0]` in the input
+    column: usize,
+    /// byte offset of `buf[0]` in the input
+    offset: usize,
+    /// commands or error parsed but not returned yet
+    pending: VecDeque<Result<Cmd<'static>, Error>>,
+    /// whether invalid UTF-8 sequences are accepted
+    lossy_utf8: bool,
+    /// options of the parser of each chunk
+    dqs: Option<Dqs>,
+    tokenizer_options: TokenizerOptions,
+    limits: ParserLimits,
+    target_version: Option<u32>,
+}
+
+impl<R: Read> StreamParser<R> {
+    /// Constructor
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, reader)
+    }
+    /// Constructor with the specified initial buffer capacity
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            reader,
+            buf: Vec::with_capacity(capacity),
+            buf_size: capacity,
+            scanned: 0,
+            state: State::Invalid,
+            eof: false,
+            line: 1,
+            column: 1,
+            offset: 0,
+            pending: VecDeque::new(),
+            lossy_utf8: false,
+            dqs: None,
+            tokenizer_options: TokenizerOptions::default(),
+            limits: ParserLimits::default(),
+            target_version: None,
+        }
+    }
+    /// Like [`Parser::with_lossy_utf8`]
+    pub fn with_lossy_utf8(mut self, lossy: bool) -> Self {
+        self.lossy_utf8 = lossy;
+        self
+    }
+    /// Like [`Parser::with_dqs`]
+    pub fn with_dqs(mut self, dqs: Dqs) -> Self {
+        self.dqs = Some(dqs);
+        self
+    }
+    /// Like [`Parser::with_tokenizer_options`].
+    ///
+    /// Statement boundaries are still found with the default options.
+    pub fn with_tokenizer_options(mut self, options: TokenizerOptions) -> Self {
+        self.tokenizer_options = options;
+        self
+    }
+    /// Like [`Parser::with_limits`]
+    pub fn with_limits(mut self, limits: ParserLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+    /// Like [`Parser::with_target_version`]
+    pub fn with_target_version(mut self, version: u32) -> Self {
+        self.target_version = Some(version);
+        self
+    }
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+    /// Unwraps this `StreamParser`, returning the underlying reader.
+    ///
+    /// Buffered data is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+    /// Line number where the next statement starts
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+    /// Column number where the next statement starts (byte offset, not char offset)
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Read more data at the end of the buffer.
+    fn fill_buf(&mut self) -> Result<(), Error> {
+        let len = self.buf.len();
+        let amt = self.buf_size.max(self.buf.capacity() - len);
+        self.buf.resize(len + amt, 0);
+        let res = loop {
+            match self.reader.read(&mut self.buf[len..]) {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                res => break res,
+            }
+        };
+        match res {
+            Ok(n) => {
+                self.buf.truncate(len + n);
+                if n == 0 {
+                    self.eof = true;
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.buf.truncate(len);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Look for the end of the first statement in the buffer,
+    /// starting where the previous call stopped.
+    fn find_end_of_statement(&mut self) -> Option<usize> {
+        let (amt, end) = self.state.scan(&self.buf[self.scanned..], self.eof);
+        self.scanned += amt;
+        end.then_some(self.scanned)
+    }
+
+    /// Parse `buf[..end]` and then discard it.
+    fn parse(&mut self, end: usize) {
+        let mut parser = Parser::with_offset(&self.buf[..end], self.offset)
+            .with_lossy_utf8(self.lossy_utf8)
+            .with_tokenizer_options(self.tokenizer_options)
+            .with_limits(self.limits);
+        if let Some(dqs) = self.dqs {
+            parser = parser.with_dqs(dqs);
+        }
+        if let Some(version) = self.target_version {
+            parser = parser.with_target_version(version);
+        }
+        loop {
+            match parser.next() {
+                Ok(Some(cmd)) => self.pending.push_back(Ok(cmd.into_owned())),
+                Ok(None) => break,
+                Err(mut err) => {
+                    err.adjust_position(self.line, self.column);
+                    self.pending.push_back(Err(err));
+                }
+            }
+        }
+        let consumed = &self.buf[..end];
+        if let Some(i) = memrchr(b'\n', consumed) {
+            self.line += memchr_iter(b'\n', consumed).count() as u64;
+            self.column = end - i;
+        } else {
+            self.column += end;
+        }
+        self.offset += end;
+        self.buf.drain(..end);
+        self.scanned -= end;
+    }
+}
+
+impl<R: Read> FallibleIterator for StreamParser<R> {
+    type Item = Cmd<'static>;
+    type Error = Error;
+
+    fn next(&mut self) -> Result<Option<Cmd<'static>>, Error> {
+        loop {
+            if let Some(res) = self.pending.pop_front() {
+                return res.map(Some);
+            }
+            if let Some(end) = self.find_end_of_statement() {
+                self.parse(end);
+            } else if self.eof {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                // last statement, without semicolon
+                self.scanned = self.buf.len();
+                self.parse(self.buf.len());
+                self.state = State::Invalid;
+            } else {
+                self.fill_buf()?;
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Completion of keywords and names, driven by the parser tables
+use std::ops::Range;
+
+use super::{Error, Parser, TokenCategory, TokenType};
+use crate::dialect::{is_identifier_continue, TokenType::*};
+use crate::parser::parse::{yySimulator, YYCODETYPE};
+
+/// Completion candidate at the cursor
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Completion {
+    /// A table name is expected, maybe qualified by a schema name:
+    /// after `FROM`, `JOIN`, `INTO`, `UPDATE`, `DROP TABLE`, ...
+    TableName,
+    /// Any other name is expected: column, alias, function, ...
+    Identifier,
+    /// Keyword or symbol, like `SELECT` or `(`
+    Keyword(&'static str),
+}
+
+/// Completion candidates returned by [`completions`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Completions {
+    /// Byte range of the word being typed, to be replaced by the candidate
+    /// (empty when the cursor is not at the end of a word)
+    pub range: Range<usize>,
+    /// Candidates, names first then keywords in alphabetical order.
+    /// Keywords not starting with the word being typed are filtered out.
+    pub candidates: Vec<Completion>,
+}
+
+/// Completion candidates at the end of `prefix`, which is the input up to
+/// the cursor.
+///
+/// The tokens before the cursor are fed to the parser, which is then asked
+/// for the terminals it accepts: keywords and symbols, or a name.
+/// Keywords only accepted as names, through the `%fallback ID` directive,
+/// are not suggested.
+///
+/// Return an error when `prefix` cannot be tokenized or parsed.
+///
+/// ```rust
+/// use sqlite3_parser::lexer::sql::{completions, Completion};
+///
+/// let c = completions(b"SELECT * FROM t WH").unwrap();
+/// assert_eq!(c.range, 16..18);
+/// // `WH` may also be an alias
+/// assert_eq!(
+///     c.candidates,
+///     [Completion::Identifier, Completion::Keyword("WHERE")]
+/// );
+///
+/// let c = completions(b"DELETE FROM ").unwrap();
+/// assert_eq!(c.candidates, [Completion::TableName]);
+/// ```
+pub fn completions(prefix: &[u8]) -> Result<Completions, Error> {
+    let mut parser = Parser::new(prefix).with_lossy_utf8(true);
+    let mut simulator = yySimulator::new();
+    // `TABLE`, `INDEX`, ... of the current statement
+    let mut object = TK_EOF;
+    let mut range = prefix.len()..prefix.len();
+    let mut last_token_parsed = TK_EOF;
+    while let Some((token_type, token)) = parser.next_token(last_token_parsed)? {
+        if token.2 == prefix.len()
+            && matches!(
+                token_type.category(),
+                TokenCategory::Keyword | TokenCategory::Identifier
+            )
+            && token.1.iter().all(|&b| is_identifier_continue(b))
+        {
+            // word being typed
+            range = token.0..token.2;
+            break;
+        }
+        if !simulator.yy_parse(token_type as YYCODETYPE) {
+            let expected = simulator.yy_expected_tokens();
+            return Err(parser.syntax_error(token_type, token, false, expected));
+        }
+        match token_type {
+            TK_SEMI => object = TK_EOF,
+            TK_TABLE | TK_VIEW | TK_INDEX | TK_TRIGGER | TK_PRAGMA | TK_REINDEX | TK_ANALYZE => {
+                object = token_type;
+            }
+            _ => {}
+        }
+        last_token_parsed = token_type;
+    }
+    let word = String::from_utf8_lossy(&prefix[range.clone()]).to_ascii_uppercase();
+    let mut candidates = Vec::new();
+    for major in simulator.yy_expected_tokens() {
+        let Ok(token_type) = TokenType::try_from(major) else {
+            continue;
+        };
+        match token_type {
+            // like `LEFT OUTER JOIN`, the grammar expects names after the first
+            // keyword of a join operator
+            TK_ID if is_join_operator(&simulator) => {
+                candidates.extend(JOIN_KEYWORDS.map(Completion::Keyword));
+            }
+            TK_ID => candidates.push(name(&simulator, last_token_parsed, object)),
+            // also accepted as names
+            TK_JOIN_KW if is_keyword(&simulator, major, TK_JOIN) => {
+                candidates.extend(JOIN_KEYWORDS.map(Completion::Keyword));
+            }
+            TK_INDEXED if is_keyword(&simulator, major, TK_BY) => {
+                candidates.push(Completion::Keyword("INDEXED"));
+            }
+            TK_JOIN_KW | TK_INDEXED => {}
+            TK_LIKE_KW => candidates.extend(["GLOB", "LIKE", "REGEXP"].map(Completion::Keyword)),
+            TK_CTIME_KW => candidates.extend(
+                ["CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP"].map(Completion::Keyword),
+            ),
+            TK_PTR => candidates.extend(["->", "->>"].map(Completion::Keyword)),
+            _ => {
+                if let Some(keyword) = token_type.as_str() {
+                    candidates.push(Completion::Keyword(keyword));
+                }
+            }
+        }
+    }
+    candidates.retain(|c| match c {
+        Completion::Keyword(keyword) => keyword.starts_with(word.as_str()),
+        _ => true,
+    });
+    candidates.sort_unstable();
+    candidates.dedup();
+    Ok(Completions { range, candidates })
+}
+
+const JOIN_KEYWORDS: [&str; 7] = [
+    "CROSS", "FULL", "INNER", "LEFT", "NATURAL", "OUTER", "RIGHT",
+];
+
+/// Whether the name expected by `simulator` is part of a join operator
+fn is_join_operator(simulator: &yySimulator) -> bool {
+    let (id, join) = (TK_ID as YYCODETYPE, TK_JOIN as YYCODETYPE);
+    simulator
+        .yy_reductions(&[id, join, id])
+        .is_some_and(|reductions| {
+            reductions.iter().any(|(name, range)| {
+                *name == "joinop" && range.start <= Some(0) && range.end >= Some(1)
+            })
+        })
+}
+
+/// Whether `major`, followed by `next`, is accepted by `simulator` as a
+/// keyword and not as a name (or as an expression) on its own
+fn is_keyword(simulator: &yySimulator, major: YYCODETYPE, next: TokenType) -> bool {
+    simulator
+        .yy_reductions(&[major, next as YYCODETYPE])
+        .is_some_and(|reductions| {
+            !reductions
+                .iter()
+                .any(|(_, range)| *range == (Some(0)..Some(1)))
+        })
+}
+
+/// Kind of name expected by `simulator` after `previous`, in a statement
+/// about `object`
+fn name(simulator: &yySimulator, previous: TokenType, object: TokenType) -> Completion {
+    if previous == TK_REFERENCES {
+        return Completion::TableName;
+    }
+    let id = TK_ID as YYCODETYPE;
+    let mut next = simulator.clone();
+    next.yy_parse(id);
+    for lookahead in next.yy_expected_tokens() {
+        let Some(reductions) = simulator.yy_reductions(&[id, lookahead]) else {
+            continue;
+        };
+        // nonterminals covering the name, innermost first
+        let names: Vec<_> = reductions
+            .into_iter()
+            .filter(|(_, range)| range.start <= Some(0) && range.end >= Some(1))
+            .map(|(name, _)| name)
+            .collect();
+        let Some(i) = names
+            .iter()
+            .position(|&n| n == "fullname" || n == "xfullname")
+        else {
+            continue;
+        };
+        let table = names[i] == "xfullname"
+            || match names.get(i + 1) {
+                // `FROM`, `JOIN` or `IN`
+                Some(&"seltablist" | &"expr") => true,
+                Some(&"cmd") => object == TK_TABLE,
+                _ => false,
+            };
+        if table {
+            return Completion::TableName;
+        }
+    }
+    Completion::Identifier
+}
@@ -1,11 +1,19 @@
 use fallible_iterator::FallibleIterator;
 
-use super::{Error, Parser};
+use super::{
+    Diagnostic, Error, ErrorKind, Parser, ParserLimits, Position, StatementRanges, Statements,
+    StreamParser, TokenType, TokenizerOptions, Utf16Input,
+};
 use crate::parser::ast::fmt::ToTokens;
 use crate::parser::{
-    ast::{Cmd, Name, ParameterInfo, QualifiedName, Stmt},
+    ast::{
+        check::ColumnCount, Cmd, ColumnConstraint, Dqs, Expr, ExprKind, FromClause, IntoOwned,
+        Literal, Name, OneSelect, ParameterInfo, QualifiedName, ResultColumn, SelectTable,
+        SortOrder, Span, Stmt, StmtKind, TypeSize,
+    },
     ParserError,
 };
+use std::borrow::Cow;
 
 #[test]
 fn count_placeholders() {
@@ -62,8 +70,182 @@ fn duplicate_column() {
 fn create_table_without_column() {
     expect_parser_err(
         b"CREATE TABLE t ()",
-        ParserError::SyntaxError(")".to_owned()),
+        ParserError::SyntaxError {
+            token_type: TokenType::TK_RP,
+            found: ")".to_owned(),
+            expected: vec![
+                TokenType::TK_ID,
+                TokenType::TK_INDEXED,
+                TokenType::TK_STRING,
+                TokenType::TK_JOIN_KW,
+            ],
+        },
+    );
+}
+
+#[test]
+fn syntax_error_expected_tokens() {
+    let err = parse(b"CREATE TABLE t ()").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "near \")\": syntax error, expected one of: identifier, INDEXED, string, join operator at (1, 17)"
+    );
+    let err = parse(b"SELECT 1 FROM t ORDER x").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "near \"x\": syntax error, expected BY at (1, 23)"
+    );
+    // tokens accepted before the default reductions triggered by `y`
+    let Error::ParserError(ParserError::SyntaxError { expected, .. }, _) =
+        parse(b"SELECT 1 FROM t x y").unwrap_err()
+    else {
+        panic!("unexpected error type")
+    };
+    for token_type in [
+        TokenType::TK_SEMI,
+        TokenType::TK_COMMA,
+        TokenType::TK_WHERE,
+        TokenType::TK_ORDER,
+        TokenType::TK_JOIN,
+    ] {
+        assert!(expected.contains(&token_type), "{token_type:?}");
+    }
+    // keywords only accepted as identifiers are not listed
+    assert!(!expected.contains(&TokenType::TK_ABORT));
+}
+
+#[test]
+fn syntax_error_position() {
+    let sql = b"SELECT 1 FROM t WHERE\n  x = = 2";
+    let err = parse(sql).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert_eq!(err.token(), Some((TokenType::TK_EQ, "=")));
+    assert_eq!(
+        err.position(),
+        Some(Position {
+            start: 28,
+            end: 29,
+            line: 2,
+            column: 7
+        })
+    );
+    let msg = err.to_string();
+    assert!(msg.starts_with("near \"=\": syntax error, expected one of: "));
+    assert!(msg.ends_with(" at (2, 7)"));
+
+    let err = parse(b"SELECT 1 FROM").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(err.position().map(|p| (p.start, p.end)), Some((13, 13)));
+}
+
+#[test]
+fn lexer_error_position() {
+    let err = parse(b"SELECT 1,\n 12abc").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BadNumber);
+    assert_eq!(
+        err.position(),
+        Some(Position {
+            start: 11,
+            end: 16,
+            line: 2,
+            column: 2
+        })
+    );
+    let err = parse(b"SELECT 'abc").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnterminatedLiteral);
+    assert_eq!(err.position().map(|p| (p.start, p.end)), Some((7, 11)));
+}
+
+#[test]
+fn error_display_without_position() {
+    let err = Error::from(ParserError::UnexpectedEof);
+    assert_eq!(err.position(), None);
+    assert_eq!(err.to_string(), "unexpected end of input");
+    let err = Error::UnrecognizedToken(None);
+    assert_eq!(err.to_string(), "unrecognized token");
+}
+
+#[test]
+fn diagnostic() {
+    let sql = b"SELECT 1;\nSELECT a FROM\tt ORDER x;";
+    let err = parse_all(sql).unwrap_err();
+    assert_eq!(
+        Diagnostic::new(&err, sql).to_string(),
+        "error: near \"x\": syntax error, expected BY
+ --> 2:23
+  |
+2 | SELECT a FROM\tt ORDER x;
+  |              \t        ^
+"
+    );
+    let sql = b"CREATE TABLE t(x TEXT, x TEXT)";
+    let err = parse(sql).unwrap_err();
+    assert_eq!(
+        Diagnostic::new(&err, sql).to_string(),
+        "error: duplicate column name: x
+ --> 1:24
+  |
+1 | CREATE TABLE t(x TEXT, x TEXT)
+  |                        ^
+note: previous definition here
+  |
+1 | CREATE TABLE t(x TEXT, x TEXT)
+  |                -
+"
+    );
+    let sql = b"WITH\n  c AS (SELECT 1),\n  c AS (SELECT 2)\nSELECT * FROM c";
+    let err = parse(sql).unwrap_err();
+    let report = Diagnostic::new(&err, sql).to_string();
+    assert!(report.starts_with("error: duplicate WITH table name: c\n --> 3:3\n"));
+    assert!(
+        report.ends_with("note: previous definition here\n  |\n2 |   c AS (SELECT 1),\n  |   -\n")
+    );
+}
+
+#[test]
+fn diagnostic_multi_line_span() {
+    let sql = b"INSERT INTO t (a, b)\nSELECT 1;";
+    let err = parse(sql).unwrap_err();
+    assert_eq!(
+        Diagnostic::new(&err, sql).to_string(),
+        "error: 1 values for 2 columns
+ --> 1:1
+  |
+1 | INSERT INTO t (a, b)
+  | ^^^^^^^^^^^^^^^^^^^^
+2 | SELECT 1;
+  | ^^^^^^^^
+"
+    );
+}
+
+#[test]
+fn diagnostic_ansi() {
+    let sql = b"SELECT 'x";
+    let err = parse(sql).unwrap_err();
+    assert_eq!(
+        Diagnostic::new(&err, sql).with_ansi(true).to_string(),
+        "\x1b[1;31merror\x1b[0m\x1b[1m: non-terminated literal\x1b[0m
+\x1b[1;34m -->\x1b[0m 1:8
+\x1b[1;34m  |\x1b[0m
+\x1b[1;34m1 |\x1b[0m SELECT 'x
+\x1b[1;34m  |\x1b[0m        \x1b[1;31m^^\x1b[0m
+"
+    );
+}
+
+#[test]
+fn diagnostic_without_position() {
+    let err = Error::from(ParserError::UnexpectedEof);
+    assert_eq!(
+        Diagnostic::new(&err, b"").to_string(),
+        "error: unexpected end of input\n"
     );
+    // source not matching the error position
+    let err = parse(b"SELECT 1 FROM t ORDER x").unwrap_err();
+    assert!(Diagnostic::new(&err, b"SELECT")
+        .to_string()
+        .starts_with("error: near \"x\": syntax error, expected BY\n"));
 }
 
 #[test]
@@ -73,15 +255,19 @@ fn vtab_args() -> Result<(), Error> {
   body TEXT CHECK(length(body)<10240)
 );";
     let r = parse_cmd(sql);
-    let Cmd::Stmt(Stmt::CreateVirtualTable {
-        tbl_name: QualifiedName {
-            name: Name(tbl_name),
+    let Cmd::Stmt(
+        Stmt::CreateVirtualTable {
+            tbl_name:
+                QualifiedName {
+                    name: Name(tbl_name, _),
+                    ..
+                },
+            module_name: Name(module_name, _),
+            args: Some(args),
             ..
         },
-        module_name: Name(module_name),
-        args: Some(args),
-        ..
-    }) = r
+        _,
+    ) = r
     else {
         panic!("unexpected AST")
     };
@@ -114,11 +300,11 @@ fn extra_semicolons_between_statements() {
         let mut parser = Parser::new(sql.as_bytes());
         assert!(matches!(
             parser.next().unwrap(),
-            Some(Cmd::Stmt(Stmt::Select { .. }))
+            Some(Cmd::Stmt(Stmt::Select { .. }, _))
         ));
         assert!(matches!(
             parser.next().unwrap(),
-            Some(Cmd::Stmt(Stmt::Select { .. }))
+            Some(Cmd::Stmt(Stmt::Select { .. }, _))
         ));
         assert_eq!(parser.next().unwrap(), None);
     }
@@ -136,11 +322,11 @@ fn extra_comments_between_statements() {
         let mut parser = Parser::new(sql.as_bytes());
         assert!(matches!(
             parser.next().unwrap(),
-            Some(Cmd::Stmt(Stmt::Select { .. }))
+            Some(Cmd::Stmt(Stmt::Select { .. }, _))
         ));
         assert!(matches!(
             parser.next().unwrap(),
-            Some(Cmd::Stmt(Stmt::Select { .. }))
+            Some(Cmd::Stmt(Stmt::Select { .. }, _))
         ));
         assert_eq!(parser.next().unwrap(), None);
     }
@@ -356,8 +542,1246 @@ fn indexed_by_clause_within_triggers() {
     );
 }
 
+#[test]
+fn zero_copy() {
+    let sql = String::from("SELECT x FROM t WHERE y = 'abc'");
+    let cmd = parse_cmd(sql.as_bytes());
+    let Cmd::Stmt(Stmt::Select(ref select), _) = cmd else {
+        panic!("unexpected AST")
+    };
+    let OneSelect::Select {
+        where_clause:
+            Some(Expr {
+                kind: ExprKind::Binary(_, _, ref rhs),
+                ..
+            }),
+        ..
+    } = select.body.select
+    else {
+        panic!("unexpected AST")
+    };
+    let ExprKind::Literal(Literal::String(Cow::Borrowed(s))) = rhs.kind else {
+        panic!("unexpected AST")
+    };
+    assert_eq!(s, "'abc'");
+
+    let owned = cmd.clone().into_owned();
+    drop(sql);
+    assert_eq!(owned.to_string(), "SELECT x FROM t WHERE y = 'abc';");
+}
+
+#[test]
+fn spans() {
+    let sql = "SELECT 1;\n  EXPLAIN SELECT a.b + f(x, 2) AS c FROM main.t -- t\n;";
+    let mut parser = Parser::new(sql.as_bytes());
+    let cmd = parser.next().unwrap().unwrap();
+    assert_eq!(&sql[cmd.span().range()], "SELECT 1");
+    let cmd = parser.next().unwrap().unwrap();
+    assert_eq!(
+        &sql[cmd.span().range()],
+        "EXPLAIN SELECT a.b + f(x, 2) AS c FROM main.t"
+    );
+    assert_eq!(cmd.span().start_position(sql.as_bytes()), (2, 3));
+    assert_eq!(cmd.span().end_position(sql.as_bytes()), (2, 48));
+
+    let Stmt::Select(select) = cmd.stmt() else {
+        panic!("unexpected AST")
+    };
+    let OneSelect::Select {
+        ref columns,
+        from: Some(ref from),
+        ..
+    } = select.body.select
+    else {
+        panic!("unexpected AST")
+    };
+    let ResultColumn::Expr(expr, _) = &columns[0] else {
+        panic!("unexpected AST")
+    };
+    assert_eq!(&sql[expr.span.range()], "a.b + f(x, 2)");
+    let ExprKind::Binary(lhs, _, rhs) = &expr.kind else {
+        panic!("unexpected AST")
+    };
+    assert_eq!(&sql[lhs.span.range()], "a.b");
+    assert_eq!(&sql[rhs.span.range()], "f(x, 2)");
+    let ExprKind::Qualified(tbl, col) = &lhs.kind else {
+        panic!("unexpected AST")
+    };
+    assert_eq!(&sql[tbl.1.range()], "a");
+    assert_eq!(&sql[col.1.range()], "b");
+    let Some(SelectTable::Table(tbl_name, ..)) = from.select.as_deref() else {
+        panic!("unexpected AST")
+    };
+    assert_eq!(&sql[tbl_name.span().range()], "main.t");
+}
+
+#[test]
+fn spans_are_ignored_by_eq() {
+    let cmd = parse_cmd(b"SELECT 1");
+    assert_eq!(cmd, parse_cmd(b"  SELECT  1"));
+    assert_eq!(Span::new(0, 1), Span::new(2, 3));
+}
+
+#[test]
+fn stream_parser() {
+    let sql = b"-- dump
+CREATE TABLE t (x TEXT, y BLOB); /* ; */
+INSERT INTO t VALUES ('a;b', x'0102'), (\"c\", 1_000.5e-3);
+CREATE TRIGGER tr AFTER INSERT ON t BEGIN
+  DELETE FROM t WHERE x = 'end;';
+  UPDATE t SET y = NULL;
+END;
+EXPLAIN CREATE TEMP TRIGGER tr2 AFTER DELETE ON t BEGIN SELECT 1; END;;
+SELECT [x;] FROM t";
+    let parser = Parser::new(sql);
+    let expected: Vec<Cmd> = parser.iterator().map(Result::unwrap).collect();
+    assert_eq!(expected.len(), 5);
+    for n in 1..8 {
+        let reader = Chunks { input: sql, n };
+        let parser = StreamParser::with_capacity(1, reader);
+        let cmds: Vec<Cmd> = parser.iterator().map(Result::unwrap).collect();
+        assert_eq!(cmds, expected);
+        for (cmd, expected) in cmds.iter().zip(&expected) {
+            assert_eq!(cmd.span().range(), expected.span().range());
+        }
+    }
+}
+
+#[test]
+fn stream_parser_error_position() {
+    let sql = b"SELECT 1;\nSELECT 2; SELECT 3 FROM;\nSELECT 4;";
+    let mut parser = StreamParser::with_capacity(1, Chunks { input: sql, n: 3 });
+    assert!(parser.next().unwrap().is_some());
+    assert!(parser.next().unwrap().is_some());
+    let Err(Error::ParserError(_, pos)) = parser.next() else {
+        panic!("unexpected result")
+    };
+    assert_eq!(
+        pos,
+        Some(Position {
+            start: 33,
+            end: 34,
+            line: 2,
+            column: 24
+        })
+    );
+    assert!(parser.next().unwrap().is_some());
+    assert_eq!(parser.next().unwrap(), None);
+}
+
+#[test]
+fn stream_parser_options() {
+    let sql = b"SELECT f(1, 2, 3); SELECT \"abc\"; SELECT 1_000";
+    let limits = ParserLimits {
+        max_function_arg: 2,
+        ..ParserLimits::default()
+    };
+    let options = TokenizerOptions {
+        digit_separators: false,
+        ..TokenizerOptions::default()
+    };
+    let mut parser = StreamParser::with_capacity(1, Chunks { input: sql, n: 3 })
+        .with_limits(limits)
+        .with_dqs(Dqs::default())
+        .with_tokenizer_options(options);
+    let mut statements = Statements::new(sql)
+        .with_limits(limits)
+        .with_dqs(Dqs::default())
+        .with_tokenizer_options(options);
+    for msg in [
+        parser.next().unwrap_err().to_string(),
+        statements.next().unwrap_err().to_string(),
+    ] {
+        assert!(msg.starts_with("too many arguments on function f"), "{msg}");
+    }
+    assert_eq!(parser.next().unwrap().unwrap().to_string(), "SELECT 'abc';");
+    let stmt = statements.next().unwrap().unwrap();
+    assert_eq!(stmt.cmd.to_string(), "SELECT 'abc';");
+    assert!(parser.next().is_err());
+    assert!(statements.next().is_err());
+}
+
+#[test]
+fn error_recovery() {
+    let sql = b"SELECT 1;
+SELECT * FROM;
+CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT x FROM; SELECT 2; END;
+SELECT 3 #;
+CREATE TABLE t(a, a);
+SELECT 4;
+SELECT 'x; SELECT 5;";
+    let results = |iter: &mut dyn FnMut() -> Result<Option<Cmd<'static>>, Error>| {
+        let mut results = vec![];
+        while let Some(res) = iter().transpose() {
+            results.push(res.map(|cmd| cmd.span().range()).map_err(|e| {
+                let pos = e.position().unwrap();
+                (e.kind(), pos.line)
+            }));
+        }
+        results
+    };
+    let expected = vec![
+        Ok(0..8),
+        Err((ErrorKind::SyntaxError, 2)),
+        Err((ErrorKind::SyntaxError, 3)),
+        Err((ErrorKind::BadVariableName, 4)),
+        Err((ErrorKind::Custom, 5)),
+        Ok(131..139),
+        Err((ErrorKind::UnterminatedLiteral, 7)),
+    ];
+    let mut parser = Parser::new(sql);
+    assert_eq!(
+        results(&mut || parser.next().map(|cmd| cmd.map(IntoOwned::into_owned))),
+        expected
+    );
+    for n in 1..8 {
+        let mut parser = StreamParser::with_capacity(1, Chunks { input: sql, n });
+        assert_eq!(results(&mut || parser.next()), expected);
+    }
+}
+
+#[test]
+fn partial_missing_expr_and_name() {
+    let mut parser = Parser::new(b"SELECT a, FROM t WHERE");
+    let partial = parser.next_partial().unwrap();
+    let kinds: Vec<_> = partial.errors.iter().map(Error::kind).collect();
+    assert_eq!(kinds, [ErrorKind::SyntaxError, ErrorKind::UnexpectedEof]);
+    let Some(Cmd::Stmt(Stmt::Select(select), span)) = partial.cmd else {
+        panic!("unexpected cmd")
+    };
+    assert_eq!(span.range(), 0..22);
+    let OneSelect::Select {
+        columns,
+        where_clause,
+        ..
+    } = select.body.select
+    else {
+        panic!("unexpected select")
+    };
+    let ResultColumn::Expr(expr, _) = &columns[1] else {
+        panic!("unexpected column")
+    };
+    assert_eq!(expr.kind, ExprKind::Error);
+    assert_eq!(expr.span.range(), 10..10);
+    let where_clause = where_clause.unwrap();
+    assert_eq!(where_clause.kind, ExprKind::Error);
+    assert_eq!(where_clause.span.range(), 22..22);
+    assert_eq!(partial.placeholders, [Span::new(10, 10), Span::new(22, 22)]);
+    assert!(parser.next_partial().is_none());
+
+    let partial = Parser::new(b"CREATE TABLE (a").next_partial().unwrap();
+    assert_eq!(partial.errors.len(), 2);
+    let Some(Cmd::Stmt(Stmt::CreateTable { tbl_name, .. }, _)) = partial.cmd else {
+        panic!("unexpected cmd")
+    };
+    assert_eq!(partial.placeholders, [Span::new(13, 13)]);
+    assert_eq!(tbl_name.name.0, "");
+    assert_eq!(tbl_name.name.1.range(), 13..13);
+}
+
+#[test]
+fn partial_statements() {
+    let sql = b"SELECT 1 SELECT 2; FROM t; CREATE TABLE t(a, a); SELECT 3 #;
+CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 4;";
+    let mut parser = Parser::new(sql);
+    let mut results = vec![];
+    while let Some(partial) = parser.next_partial() {
+        results.push((
+            partial.cmd.map(|cmd| cmd.to_string()),
+            partial.errors.iter().map(Error::kind).collect::<Vec<_>>(),
+        ));
+    }
+    assert_eq!(
+        results,
+        vec![
+            (Some("SELECT 1;".to_owned()), vec![ErrorKind::SyntaxError]),
+            (Some("SELECT 2;".to_owned()), vec![]),
+            (None, vec![ErrorKind::SyntaxError, ErrorKind::SyntaxError]),
+            (None, vec![ErrorKind::Custom]),
+            (
+                Some("SELECT 3;".to_owned()),
+                vec![ErrorKind::BadVariableName]
+            ),
+            (
+                Some("CREATE TRIGGER tr AFTER INSERT ON t BEGIN\nSELECT 4;\nEND;".to_owned()),
+                vec![ErrorKind::UnexpectedEof]
+            ),
+        ]
+    );
+    // strict mode is unchanged
+    assert!(parse(b"SELECT a, FROM t").is_err());
+}
+
+#[test]
+fn statements() {
+    let sql = b"  ;; -- comment of an empty statement
+;
+-- first
+SELECT 1; -- second
+/* second */ CREATE TRIGGER tr AFTER INSERT ON t BEGIN
+  SELECT ';'; SELECT 2;
+END ;
+SELECT 3 FROM;
+SELECT \"4\" -- trailing
+  \n";
+    let expected: [&[u8]; 4] = [
+        b"-- first\nSELECT 1;",
+        b"-- second\n/* second */ CREATE TRIGGER tr AFTER INSERT ON t BEGIN\n  SELECT ';'; SELECT 2;\nEND ;",
+        b"SELECT 3 FROM;",
+        b"SELECT \"4\" -- trailing",
+    ];
+    let ranges: Vec<_> = StatementRanges::new(sql).collect();
+    assert_eq!(ranges.len(), expected.len());
+    for ((range, text), expected) in ranges.iter().zip(expected) {
+        assert_eq!(*text, expected);
+        assert_eq!(&sql[range.clone()], expected);
+    }
+
+    let mut statements = Statements::new(sql);
+    let mut results = vec![];
+    loop {
+        match statements.next() {
+            Ok(Some(stmt)) => {
+                assert_eq!(&sql[stmt.range.clone()], stmt.sql);
+                results.push(Ok((stmt.range, stmt.cmd.to_string())));
+            }
+            Ok(None) => break,
+            Err(err) => results.push(Err(err.kind())),
+        }
+    }
+    assert_eq!(results.len(), 4);
+    assert_eq!(
+        results[0],
+        Ok((ranges[0].0.clone(), "SELECT 1;".to_owned()))
+    );
+    assert!(
+        matches!(&results[1], Ok((range, cmd)) if *range == ranges[1].0 && cmd.starts_with("CREATE TRIGGER"))
+    );
+    assert_eq!(results[2], Err(ErrorKind::SyntaxError));
+    assert_eq!(
+        results[3],
+        Ok((ranges[3].0.clone(), "SELECT \"4\";".to_owned()))
+    );
+
+    assert_eq!(StatementRanges::new(b" ; -- nothing").count(), 0);
+    assert!(Statements::new(b" ; -- nothing").next().unwrap().is_none());
+}
+
+#[test]
+fn classify() {
+    let sql = b"ALTER TABLE t ADD COLUMN c;
+ANALYZE; ATTACH 'f' AS db; BEGIN IMMEDIATE; COMMIT; END;
+CREATE UNIQUE INDEX i ON t(a); CREATE TEMP TABLE t(a); CREATE VIRTUAL TABLE v USING m;
+CREATE TRIGGER tr AFTER INSERT ON t BEGIN INSERT INTO x VALUES (1); DELETE FROM y; END;
+CREATE VIEW v AS SELECT 1; DELETE FROM t WHERE a = ?; DETACH db;
+DROP INDEX i; DROP TABLE t; DROP TRIGGER tr; DROP VIEW v;
+INSERT INTO t VALUES (?1, ?3); REPLACE INTO t SELECT :a, :a, $b;
+PRAGMA foreign_keys = ON; PRAGMA user_version; PRAGMA main.\"table_info\"(t); REINDEX; RELEASE sp; ROLLBACK TO sp; SAVEPOINT sp;
+SELECT 1; VALUES (1); WITH x AS (SELECT 1 UNION SELECT 2) SELECT * FROM x;
+WITH x(a) AS MATERIALIZED (VALUES (?)) UPDATE t SET a = (SELECT a FROM x);
+WITH RECURSIVE x AS (SELECT 1) INSERT INTO t SELECT * FROM x;
+UPDATE t SET a = 1; VACUUM INTO 'f';
+EXPLAIN SELECT 1; EXPLAIN QUERY PLAN DELETE FROM t;
+SELECT sum(a) OVER (PARTITION BY b) FROM t WINDOW w AS (ORDER BY a)";
+    let mut classifier = Parser::new(sql);
+    let mut parser = Parser::new(sql);
+    let mut n = 0;
+    while let Some(cmd) = parser.next().unwrap() {
+        let info = classifier.next_info().unwrap().unwrap();
+        let mut params = ParameterInfo::default();
+        cmd.to_tokens(&mut params).unwrap();
+        assert_eq!(info.kind, cmd.stmt().kind(), "{cmd}");
+        assert_eq!(info.is_explain, cmd.is_explain(), "{cmd}");
+        assert_eq!(info.readonly, cmd.readonly(), "{cmd}");
+        assert_eq!(info.param_count, params.count, "{cmd}");
+        n += 1;
+    }
+    assert_eq!(n, 36);
+    assert!(classifier.next_info().unwrap().is_none());
+    let readonly = |sql: &[u8]| Parser::new(sql).next_info().unwrap().unwrap().readonly;
+    assert!(readonly(b"PRAGMA user_version"));
+    assert!(!readonly(b"PRAGMA user_version = 1"));
+    assert!(!readonly(b"PRAGMA main.wal_checkpoint(FULL)"));
+    assert!(readonly(b"PRAGMA table_info(t)"));
+}
+
+#[test]
+fn classify_errors() {
+    let mut parser = Parser::new(b"SELECT 1 FROM; ;; SELECT (1; EXPLAIN; DROP TABLE t");
+    let err = parser.next_info().unwrap_err();
+    assert_eq!(err.to_string(), "near \";\": syntax error, expected one of: '(', identifier, INDEXED, string, join operator at (1, 14)");
+    let err = parser.next_info().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    let err = parser.next_info().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    let info = parser.next_info().unwrap().unwrap();
+    assert_eq!(info.kind, StmtKind::DropTable);
+    assert!(parser.next_info().unwrap().is_none());
+
+    let mut parser = Parser::new(b"SELECT (1");
+    let err = parser.next_info().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    // same errors as the parser
+    for sql in [
+        &b"SELECT 1 FROM;"[..],
+        b"CREATE TABLE",
+        b"SELECT * FROM t ORDER x",
+    ] {
+        let expected = Parser::new(sql).next().unwrap_err().to_string();
+        assert_eq!(
+            Parser::new(sql).next_info().unwrap_err().to_string(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn dqs_fallback() {
+    fn apply(sql: &str) -> (String, usize) {
+        let mut parser = Parser::new(sql.as_bytes()).with_dqs(Dqs::default());
+        let cmd = parser.next().unwrap().unwrap();
+        (cmd.to_string(), parser.dqs_fallbacks().len())
+    }
+    let cases = [
+        (r#"SELECT "a""b", "it's""#, "SELECT 'a\"b', 'it''s';", 2),
+        (r#"VALUES ("x"), (1)"#, "VALUES ('x'), (1);", 1),
+        (
+            r#"INSERT INTO t VALUES ("x")"#,
+            "INSERT INTO t VALUES ('x');",
+            1,
+        ),
+        (
+            r#"INSERT INTO t SELECT "x" WHERE "y" IN (SELECT "z")"#,
+            "INSERT INTO t SELECT 'x' WHERE 'y' IN (SELECT 'z');",
+            3,
+        ),
+        // columns in scope
+        (
+            r#"SELECT "a" FROM t WHERE x = "foo""#,
+            r#"SELECT "a" FROM t WHERE x = "foo";"#,
+            0,
+        ),
+        (
+            r#"SELECT * FROM (SELECT "a") JOIN t"#,
+            "SELECT * FROM (SELECT 'a') JOIN t;",
+            1,
+        ),
+        (r#"SELECT 1 ORDER BY "a""#, r#"SELECT 1 ORDER BY "a";"#, 0),
+        (
+            r#"ALTER TABLE t ADD COLUMN b DEFAULT "x" CHECK ("b" <> "y")"#,
+            r#"ALTER TABLE t ADD COLUMN b DEFAULT 'x' CHECK ("b" <> 'y');"#,
+            2,
+        ),
+        (
+            r#"WITH c AS (SELECT "a") DELETE FROM t WHERE x = "b""#,
+            r#"WITH c AS (SELECT 'a') DELETE FROM t WHERE x = "b";"#,
+            1,
+        ),
+        (
+            r#"CREATE VIEW v AS SELECT "a""#,
+            "CREATE VIEW v AS SELECT 'a';",
+            1,
+        ),
+        (
+            r#"CREATE TABLE t (x DEFAULT "abc", y DEFAULT ("a" || "b"))"#,
+            "CREATE TABLE t (x DEFAULT 'abc', y DEFAULT ('a' || 'b'));",
+            3,
+        ),
+        (
+            r#"CREATE TABLE t (x CHECK ("X" <> "x"), y AS ("Y" || "z"), CHECK ("a" > 0))"#,
+            r#"CREATE TABLE t (x CHECK ("X" <> "x"), y AS ("Y" || 'z'), CHECK ('a' > 0));"#,
+            2,
+        ),
+        (
+            r#"CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT "a"; END"#,
+            "CREATE TRIGGER tr AFTER INSERT ON t BEGIN\nSELECT \"a\";\nEND;",
+            0,
+        ),
+    ];
+    for (sql, expected, fallbacks) in cases {
+        assert_eq!(apply(sql), (expected.to_owned(), fallbacks), "{sql}");
+    }
+
+    let sql = b"SELECT 1;\nSELECT 2, \"a\"";
+    let mut parser = Parser::new(sql).with_dqs(Dqs::default());
+    parser.next().unwrap();
+    assert!(parser.dqs_fallbacks().is_empty());
+    parser.next().unwrap();
+    let span = parser.dqs_fallbacks()[0];
+    assert_eq!(&sql[span.start..span.end], b"\"a\"");
+    // disabled by default
+    let cmd = parse_cmd(br#"SELECT "a""#);
+    assert_eq!(cmd.to_string(), r#"SELECT "a";"#);
+}
+
+#[test]
+fn dqs_disabled() {
+    let dml = Dqs {
+        dml: false,
+        ddl: true,
+    };
+    let mut parser = Parser::new(b"SELECT 1, \"a\"").with_dqs(dml);
+    let err = parser.next().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "no such column: \"a\" - should this be a string literal in single-quotes? at (1, 11)"
+    );
+    assert_eq!(err.kind(), ErrorKind::Custom);
+    let mut parser =
+        Parser::new(br#"CREATE TABLE t (x CHECK (x <> "y")); SELECT "a" FROM t"#).with_dqs(dml);
+    assert!(parser.next().unwrap().is_some());
+    assert!(parser.next().unwrap().is_some());
+
+    let ddl = Dqs {
+        dml: true,
+        ddl: false,
+    };
+    for (sql, ok) in [
+        (&br#"CREATE TABLE t (x DEFAULT "a")"#[..], true),
+        (br#"CREATE TABLE t (x DEFAULT ("a"))"#, false),
+        (br#"CREATE TABLE t (x CHECK (x <> "y"))"#, false),
+        (br#"CREATE TABLE t (x CHECK ("x" > 0))"#, true),
+        (br#"SELECT "a""#, true),
+    ] {
+        let res = Parser::new(sql).with_dqs(ddl).next();
+        assert_eq!(res.is_ok(), ok, "{}", String::from_utf8_lossy(sql));
+    }
+    // reported by the tolerant parser
+    let mut parser = Parser::new(b"SELECT \"a\"").with_dqs(dml);
+    let partial = parser.next_partial().unwrap();
+    assert!(partial.cmd.is_some());
+    assert_eq!(partial.errors.len(), 1);
+}
+
+#[test]
+fn invalid_utf8() {
+    for (input, start, end, line, column) in [
+        (&b"SELECT \xFF"[..], 7, 8, 1, 8),
+        (b"SELECT 'caf\xE9'", 11, 12, 1, 12),
+        (b"SELECT 1;\nSELECT \"a\nb\xC3\"", 21, 22, 3, 2),
+        // truncated sequence
+        (b"SELECT x\xE2\x82", 8, 10, 1, 9),
+        (b"SELECT 1 -- \xE9\n", 12, 13, 1, 13),
+    ] {
+        let mut parser = Parser::new(input);
+        let err = loop {
+            match parser.next() {
+                Ok(Some(_)) => {}
+                Ok(None) => panic!("no error"),
+                Err(err) => break err,
+            }
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidUtf8);
+        assert_eq!(
+            err.position(),
+            Some(Position {
+                start,
+                end,
+                line,
+                column
+            }),
+            "{}",
+            String::from_utf8_lossy(input)
+        );
+    }
+    assert_eq!(
+        Parser::new(b"SELECT \xFF;").next().unwrap_err().to_string(),
+        "invalid UTF-8 sequence at (1, 8)"
+    );
+
+    let input = b"SELECT caf\xE9, 'caf\xE9' FROM \"t\xFF\"; SELECT 1";
+    assert!(super::is_complete(b"SELECT 'caf\xE9';"));
+    let mut parser = Parser::new(input).with_lossy_utf8(true);
+    let cmd = parser.next().unwrap().unwrap();
+    assert_eq!(
+        cmd.to_string(),
+        "SELECT caf\u{FFFD}, 'caf\u{FFFD}' FROM \"t\u{FFFD}\";"
+    );
+    assert!(parser.next().unwrap().is_some());
+    let mut parser = StreamParser::new(Chunks { input, n: 3 }).with_lossy_utf8(true);
+    assert!(parser.next().unwrap().is_some());
+    assert!(parser.next().unwrap().is_some());
+    let mut parser = StreamParser::new(Chunks { input, n: 3 });
+    assert_eq!(parser.next().unwrap_err().kind(), ErrorKind::InvalidUtf8);
+    assert!(parser.next().unwrap().is_some());
+    assert_eq!(crate::dialect::keyword_token(b"SELEC\xD4"), None);
+}
+
+#[test]
+fn utf16_input() {
+    let sql = "SELECT '😀', \"é\"\n FROM t WHERE";
+    let units: Vec<u16> = sql.encode_utf16().collect();
+    let input = Utf16Input::new(&units);
+    assert_eq!(input.as_bytes(), sql.as_bytes());
+    let err = input.parser().next().unwrap_err();
+    let pos = err.position().unwrap();
+    assert_eq!((pos.start, pos.line, pos.column), (33, 2, 14));
+    let pos16 = input.position(pos);
+    assert_eq!((pos16.start, pos16.line, pos16.column), (30, 2, 14));
+    assert_eq!(sql.encode_utf16().count(), pos16.end);
+    let units: Vec<u16> = "SELECT '😀' FROM".encode_utf16().collect();
+    let input = Utf16Input::new(&units);
+    let pos = input.parser().next().unwrap_err().position().unwrap();
+    assert_eq!((pos.start, pos.column), (18, 19));
+    let pos16 = input.position(pos);
+    assert_eq!((pos16.start, pos16.column), (16, 17));
+
+    let sql: Vec<u16> = "SELECT 'é😀x' AS y".encode_utf16().collect();
+    let input = Utf16Input::new(&sql);
+    let cmd = input.parser().next().unwrap().unwrap();
+    let Cmd::Stmt(Stmt::Select(select), _) = cmd else {
+        unreachable!()
+    };
+    let OneSelect::Select { columns, .. } = select.body.select else {
+        unreachable!()
+    };
+    let ResultColumn::Expr(expr, _) = &columns[0] else {
+        unreachable!()
+    };
+    assert_eq!(expr.span.range(), 7..16);
+    assert_eq!(input.span(expr.span), 7..13);
+    assert_eq!(
+        &sql[input.span(expr.span)],
+        "'é😀x'".encode_utf16().collect::<Vec<_>>()
+    );
+    // unpaired surrogate
+    let input = Utf16Input::new(&[0x27, 0xD800, 0x27, 0x20]);
+    assert_eq!(input.as_bytes(), "'\u{FFFD}' ".as_bytes());
+    assert_eq!(input.utf16_offset(5), 3);
+}
+
+#[test]
+fn line_index_positions() {
+    use crate::lexer::LineIndex;
+    let input = "SELECT 1;\n SELECT 'é', \n x FROM; SELECT\n 2 ! 3;\nSELECT 'é\n".as_bytes();
+    let positions = |mut parser: Parser<'_>| {
+        let mut positions = vec![];
+        loop {
+            match parser.next() {
+                Ok(Some(_)) => positions.push((parser.line(), parser.column(), None)),
+                Ok(None) => break,
+                Err(err) => positions.push((parser.line(), parser.column(), err.position())),
+            }
+        }
+        positions
+    };
+    let expected = positions(Parser::new(input));
+    assert_eq!(expected.iter().filter(|p| p.2.is_some()).count(), 3);
+    let indexed = Parser::new(input).with_line_index(LineIndex::new(input));
+    assert_eq!(positions(indexed), expected);
+}
+
+#[test]
+fn token_categories() {
+    use super::{token_categories, Highlighter, TokenCategory};
+    fn categories(input: &str) -> Vec<(&str, Option<TokenCategory>)> {
+        let tokens = token_categories(input.as_bytes());
+        // the whole input is covered
+        let mut offset = 0;
+        for (range, _) in &tokens {
+            assert_eq!(range.start, offset, "{input}");
+            offset = range.end;
+        }
+        assert_eq!(offset, input.len());
+        tokens
+            .into_iter()
+            .filter(|(_, c)| *c != Some(TokenCategory::Whitespace))
+            .map(|(range, category)| (&input[range], category))
+            .collect()
+    }
+    use TokenCategory::*;
+    assert_eq!(
+        categories(
+            "REPLACE INTO t(key, x) VALUES (?1, x'00'); -- c\nSELECT replace(a, 1.5, :b) FROM t"
+        ),
+        [
+            ("REPLACE", Some(Keyword)),
+            ("INTO", Some(Keyword)),
+            ("t", Some(Identifier)),
+            ("(", Some(Punctuation)),
+            ("key", Some(Identifier)),
+            (",", Some(Punctuation)),
+            ("x", Some(Identifier)),
+            (")", Some(Punctuation)),
+            ("VALUES", Some(Keyword)),
+            ("(", Some(Punctuation)),
+            ("?1", Some(Variable)),
+            (",", Some(Punctuation)),
+            ("x'00'", Some(Blob)),
+            (")", Some(Punctuation)),
+            (";", Some(Punctuation)),
+            ("-- c", Some(Comment)),
+            ("SELECT", Some(Keyword)),
+            ("replace", Some(Identifier)),
+            ("(", Some(Punctuation)),
+            ("a", Some(Identifier)),
+            (",", Some(Punctuation)),
+            ("1.5", Some(Number)),
+            (",", Some(Punctuation)),
+            (":b", Some(Variable)),
+            (")", Some(Punctuation)),
+            ("FROM", Some(Keyword)),
+            ("t", Some(Identifier)),
+        ]
+    );
+    // WINDOW / OVER / FILTER
+    assert_eq!(
+        categories("SELECT sum(x) OVER w, window FROM t WINDOW w AS ()"),
+        [
+            ("SELECT", Some(Keyword)),
+            ("sum", Some(Identifier)),
+            ("(", Some(Punctuation)),
+            ("x", Some(Identifier)),
+            (")", Some(Punctuation)),
+            ("OVER", Some(Keyword)),
+            ("w", Some(Identifier)),
+            (",", Some(Punctuation)),
+            ("window", Some(Identifier)),
+            ("FROM", Some(Keyword)),
+            ("t", Some(Identifier)),
+            ("WINDOW", Some(Keyword)),
+            ("w", Some(Identifier)),
+            ("AS", Some(Keyword)),
+            ("(", Some(Punctuation)),
+            (")", Some(Punctuation)),
+        ]
+    );
+    // keywords stay keywords after a syntax error, up to the next statement
+    assert_eq!(
+        categories("SELECT FROM key; SELECT key 'a"),
+        [
+            ("SELECT", Some(Keyword)),
+            ("FROM", Some(Keyword)),
+            ("key", Some(Keyword)),
+            (";", Some(Punctuation)),
+            ("SELECT", Some(Keyword)),
+            ("key", Some(Identifier)),
+            ("'a", None),
+        ]
+    );
+    assert_eq!(
+        categories("SELECT a->>'$.b' || -1 <> 2"),
+        [
+            ("SELECT", Some(Keyword)),
+            ("a", Some(Identifier)),
+            ("->>", Some(Operator)),
+            ("'$.b'", Some(String)),
+            ("||", Some(Operator)),
+            ("-", Some(Operator)),
+            ("1", Some(Number)),
+            ("<>", Some(Operator)),
+            ("2", Some(Number)),
+        ]
+    );
+
+    assert_eq!(
+        Highlighter::html(b"SELECT 1 & 2 /* <b> */ FROM ]").to_string(),
+        "<span class=\"sql-keyword\">SELECT</span> <span class=\"sql-number\">1</span> \
+         <span class=\"sql-operator\">&amp;</span> <span class=\"sql-number\">2</span> \
+         <span class=\"sql-comment\">/* &lt;b&gt; */</span> \
+         <span class=\"sql-keyword\">FROM</span> <span class=\"sql-error\">]</span>"
+    );
+    assert_eq!(
+        Highlighter::ansi(b"SELECT x, 'a' -- c").to_string(),
+        "\x1b[1;34mSELECT\x1b[0m x, \x1b[32m'a'\x1b[0m \x1b[90m-- c\x1b[0m"
+    );
+}
+
+#[test]
+fn completions() {
+    use super::{completions, Completion};
+    use Completion::*;
+    fn candidates(prefix: &str) -> Vec<Completion> {
+        completions(prefix.as_bytes()).unwrap().candidates
+    }
+    let first = candidates("");
+    assert!(first.contains(&Keyword("SELECT")));
+    assert!(!first.contains(&Identifier));
+    // word being typed
+    let c = completions(b"SELECT 1; sel").unwrap();
+    assert_eq!(c.range, 10..13);
+    assert_eq!(c.candidates, [Keyword("SELECT")]);
+    assert_eq!(
+        candidates("SELECT a FROM t o"),
+        [
+            Identifier,
+            Keyword("ON"),
+            Keyword("ORDER"),
+            Keyword("OUTER")
+        ]
+    );
+    // names
+    for prefix in [
+        "SELECT * FROM ",
+        "SELECT * FROM t JOIN main.",
+        "SELECT * FROM t WHERE x NOT IN ",
+        "INSERT INTO ",
+        "DELETE FROM ",
+        "DROP TABLE IF EXISTS ",
+        "CREATE TABLE t(x REFERENCES ",
+    ] {
+        assert_eq!(candidates(prefix)[0], TableName, "{prefix}");
+    }
+    for prefix in ["SELECT ", "DROP VIEW ", "ALTER TABLE t RENAME TO "] {
+        assert_eq!(candidates(prefix)[0], Identifier, "{prefix}");
+    }
+    // keywords only accepted as names are not suggested
+    assert!(!candidates("SELECT ").contains(&Keyword("INDEXED")));
+    assert_eq!(candidates("SELECT * FROM "), [TableName, Keyword("(")]);
+    assert!(candidates("SELECT * FROM t ").contains(&Keyword("LEFT")));
+    assert!(candidates("SELECT * FROM t LEFT ").contains(&Keyword("OUTER")));
+    assert!(candidates("SELECT a ").contains(&Keyword("LIKE")));
+
+    let err = completions(b"SELECT FROM t WH").unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ParserError(ParserError::SyntaxError { .. }, _)
+    ));
+}
+
+#[test]
+fn fragments() {
+    let expr = Parser::new(b"a BETWEEN 1 AND 2 OR b IS NOT NULL")
+        .parse_expr()
+        .unwrap();
+    assert_eq!(expr.to_string(), "a BETWEEN 1 AND 2 OR b IS NOT NULL");
+    assert_eq!(expr.span.range(), 0..34);
+    let ty = Parser::new(b"DECIMAL(10, 5)").parse_type().unwrap();
+    assert_eq!(ty.name, "DECIMAL");
+    assert!(matches!(ty.size, Some(TypeSize::TypeSize(..))));
+    let cd = Parser::new(b"x INT NOT NULL DEFAULT 0 CHECK (x >= 0)")
+        .parse_column_def()
+        .unwrap();
+    assert_eq!(cd.col_name, "x");
+    assert_eq!(cd.col_type.unwrap().name, "INT");
+    assert_eq!(cd.constraints.len(), 3);
+    let columns = Parser::new(b"a DESC NULLS LAST, b + 1")
+        .parse_sorted_columns()
+        .unwrap();
+    assert_eq!(columns.len(), 2);
+    assert_eq!(columns[0].order, Some(SortOrder::Desc));
+    assert_eq!(columns[1].expr.to_string(), "b + 1");
+    let name = Parser::new(b"main.\"t 1\"").parse_qualified_name().unwrap();
+    assert_eq!(name.db_name.unwrap(), "main");
+    assert_eq!(name.name, "t 1");
+
+    for (input, message, start) in [
+        ("a b", "near \"b\": trailing input", 2),
+        ("a;", "near \";\": trailing input", 1),
+        ("a +", "unexpected end of input", 3),
+        ("", "unexpected end of input", 0),
+    ] {
+        let err = Parser::new(input.as_bytes()).parse_expr().unwrap_err();
+        let Error::ParserError(err, Some(pos)) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(err.to_string(), message, "{input}");
+        assert_eq!(pos.start, start, "{input}");
+    }
+    let err = Parser::new(b"INT PRIMARY KEY").parse_type().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "near \"PRIMARY\": trailing input at (1, 5)"
+    );
+    assert!(Parser::new(b"x INT, y").parse_column_def().is_err());
+    assert!(Parser::new(b"t AS u").parse_qualified_name().is_err());
+    assert!(Parser::new(b"ORDER BY a").parse_sorted_columns().is_err());
+
+    // checked and resolved like in a statement
+    let mut parser = Parser::new(br#""x" + 1"#).with_dqs(Dqs::default());
+    assert_eq!(parser.parse_expr().unwrap().to_string(), "'x' + 1");
+    assert_eq!(parser.dqs_fallbacks().len(), 1);
+    let mut parser = Parser::new(br#"SELECT "x" + 1"#).with_dqs(Dqs::default());
+    assert_eq!(
+        parser.next().unwrap().unwrap().to_string(),
+        "SELECT 'x' + 1;"
+    );
+    let mut parser = Parser::new(br#""x""#).with_dqs(Dqs {
+        dml: false,
+        ddl: true,
+    });
+    assert!(matches!(
+        parser.parse_expr().unwrap_err(),
+        Error::ParserError(ParserError::NoSuchColumn { .. }, _)
+    ));
+    assert_eq!(
+        Parser::new(br#""x""#).parse_expr().unwrap().to_string(),
+        "\"x\""
+    );
+    let mut parser = Parser::new(br#"x CHECK ("x" > "y") DEFAULT "a""#).with_dqs(Dqs::default());
+    let cd = parser.parse_column_def().unwrap();
+    let exprs: Vec<_> = cd
+        .constraints
+        .iter()
+        .map(|c| match &c.constraint {
+            ColumnConstraint::Check(expr) | ColumnConstraint::Default(expr) => expr.to_string(),
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(exprs, ["\"x\" > 'y'", "'a'"]);
+    assert_eq!(
+        Parser::new(b"x PRIMARY KEY")
+            .parse_column_def()
+            .unwrap_err()
+            .to_string(),
+        "Cannot add a PRIMARY KEY column at (1, 1)"
+    );
+    let mut parser = Parser::new(br#""x" DESC"#).with_dqs(Dqs::default());
+    let columns = parser.parse_sorted_columns().unwrap();
+    assert_eq!(columns[0].expr.to_string(), "\"x\"");
+}
+
+#[test]
+fn limits() {
+    // default limits, without overflowing the stack
+    let deep = format!("SELECT {}1{}", "(".repeat(100_000), ")".repeat(100_000));
+    expect_parser_err_msg(
+        deep.as_bytes(),
+        "Expression tree is too large (maximum depth 1000)",
+    );
+    let long = format!("SELECT 1{}", "+1".repeat(100_000));
+    expect_parser_err_msg(
+        long.as_bytes(),
+        "Expression tree is too large (maximum depth 1000)",
+    );
+    let subqueries = format!("SELECT {}1{}", "(SELECT ".repeat(600), ")".repeat(600));
+    expect_parser_err_msg(
+        subqueries.as_bytes(),
+        "Expression tree is too large (maximum depth 1000)",
+    );
+    parse_cmd(format!("SELECT {}1{}", "(".repeat(998), ")".repeat(998)).as_bytes());
+    let joins = format!(
+        "SELECT * FROM {}t{}",
+        "(".repeat(100_000),
+        ")".repeat(100_000)
+    );
+    expect_parser_err_msg(
+        joins.as_bytes(),
+        "Expression tree is too large (maximum depth 1000)",
+    );
+    let upserts = format!(
+        "INSERT INTO t VALUES (1){}",
+        " ON CONFLICT (a) DO NOTHING".repeat(10_000)
+    );
+    expect_parser_err_msg(
+        upserts.as_bytes(),
+        "Expression tree is too large (maximum depth 1000)",
+    );
+    parse_cmd(format!("SELECT * FROM {}t{}", "(".repeat(500), ")".repeat(500)).as_bytes());
+    expect_parser_err_msg(
+        b"SELECT ?0",
+        "variable number must be between ?1 and ?32766",
+    );
+    expect_parser_err_msg(
+        b"SELECT ?99999999999999999999",
+        "variable number must be between ?1 and ?32766",
+    );
+
+    let limits = ParserLimits {
+        max_sql_length: 40,
+        max_expr_depth: 3,
+        max_compound_select: 2,
+        max_column: 2,
+        max_function_arg: 2,
+        max_variable_number: 2,
+    };
+    for (input, message) in [
+        (
+            "SELECT 1 + 1 + 1",
+            "Expression tree is too large (maximum depth 3)",
+        ),
+        (
+            "SELECT 1 UNION SELECT 2 UNION SELECT 3",
+            "too many terms in compound SELECT",
+        ),
+        ("SELECT a, b, c", "too many columns in result set"),
+        ("CREATE TABLE t(a, b, c)", "too many columns on t"),
+        (
+            "UPDATE t SET a = 1, b = 2, c = 3",
+            "too many columns in set list",
+        ),
+        (
+            "SELECT 1 ORDER BY a, b, c",
+            "too many terms in ORDER BY clause",
+        ),
+        (
+            "SELECT 1 GROUP BY a, b, c",
+            "too many terms in GROUP BY clause",
+        ),
+        ("SELECT f(1, 2, 3)", "too many arguments on function f"),
+        ("SELECT ?3", "variable number must be between ?1 and ?2"),
+        ("SELECT ?, :a, :a, ?", "too many SQL variables"),
+        (
+            "SELECT 'a string longer than forty bytes'",
+            "statement too long",
+        ),
+    ] {
+        let err = Parser::new(input.as_bytes())
+            .with_limits(limits)
+            .next()
+            .unwrap_err();
+        let Error::ParserError(err, _) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(err.to_string(), message, "{input}");
+    }
+    for input in [
+        "SELECT 1 + 1",
+        "SELECT 1 UNION SELECT 2",
+        "SELECT f(a, b)",
+        "SELECT ?, :a WHERE :a",
+        "SELECT ?2, ?1 WHERE ?2",
+        "SELECT 'thirty-two bytes long';\n  SELECT 'thirty-two bytes long'",
+    ] {
+        let mut parser = Parser::new(input.as_bytes()).with_limits(limits);
+        let cmds: Result<Vec<_>, _> = parser.by_ref().collect();
+        assert!(cmds.is_ok(), "{input}: {cmds:?}");
+    }
+    let err = Parser::new(b"1 + 1 + 1 + 1")
+        .with_limits(limits)
+        .parse_expr()
+        .unwrap_err();
+    assert!(err.to_string().starts_with("Expression tree is too large"));
+    // no limit on the expression depth, like in SQLite
+    let unlimited = ParserLimits {
+        max_expr_depth: 0,
+        ..ParserLimits::default()
+    };
+    let deep = format!("SELECT {}1{}", "(".repeat(2000), ")".repeat(2000));
+    let mut parser = Parser::new(deep.as_bytes()).with_limits(unlimited);
+    assert!(parser.next().unwrap().is_some());
+}
+
+#[test]
+fn no_panic() {
+    // token inserted by the tolerant parser, without text
+    let mut parser =
+        Parser::new(b"CREATE TABLE t(DEFAULT INTERSECT AUTOINCREMENT + = JOIN IN CASE REPLACE");
+    while parser.next_partial().is_some() {}
+    // dangling constraint name
+    let cmd = parse_cmd(b"CREATE TABLE t(a, CONSTRAINT c)");
+    assert_eq!(cmd.to_string(), "CREATE TABLE t (a);");
+    assert_eq!(cmd, parse_cmd(cmd.to_string().as_bytes()));
+    // nodes which are not produced by the parser
+    assert!(matches!(
+        OneSelect::Values(vec![]).column_count(),
+        ColumnCount::None
+    ));
+    assert!(OneSelect::push(&mut vec![], vec![]).is_ok());
+    let mut params = ParameterInfo::default();
+    FromClause::empty().to_tokens(&mut params).unwrap();
+}
+
+#[test]
+fn target_version() {
+    for (input, feature, version) in [
+        ("DELETE FROM t RETURNING *", "RETURNING", "3.35.0"),
+        ("DELETE FROM t WHERE 1 RETURNING *", "RETURNING", "3.35.0"),
+        (
+            "INSERT INTO t VALUES (1) RETURNING *",
+            "RETURNING",
+            "3.35.0",
+        ),
+        (
+            "INSERT INTO t VALUES (1) ON CONFLICT DO NOTHING RETURNING *",
+            "RETURNING",
+            "3.35.0",
+        ),
+        ("CREATE TABLE t (x INT) STRICT", "STRICT", "3.37.0"),
+        ("SELECT x -> '$.a'", "-> operator", "3.38.0"),
+        ("SELECT x ->> '$.a'", "->> operator", "3.38.0"),
+        ("SELECT * FROM a RIGHT JOIN b", "RIGHT JOIN", "3.39.0"),
+        ("SELECT * FROM a FULL OUTER JOIN b", "FULL JOIN", "3.39.0"),
+        ("SELECT a IS DISTINCT FROM b", "IS DISTINCT FROM", "3.39.0"),
+        (
+            "SELECT a IS NOT DISTINCT FROM b",
+            "IS NOT DISTINCT FROM",
+            "3.39.0",
+        ),
+        ("SELECT 1 ORDER BY a NULLS FIRST", "NULLS FIRST", "3.30.0"),
+        ("SELECT 1_000", "digit separators", "3.46.0"),
+        ("SELECT 1_000.5", "digit separators", "3.46.0"),
+        ("ALTER TABLE t DROP COLUMN x", "DROP COLUMN", "3.35.0"),
+        (
+            "WITH c AS MATERIALIZED (SELECT 1) SELECT * FROM c",
+            "MATERIALIZED",
+            "3.35.0",
+        ),
+        ("UPDATE t SET a = 1 FROM u", "UPDATE FROM", "3.33.0"),
+        (
+            "CREATE TRIGGER r AFTER INSERT ON t BEGIN UPDATE t SET a = 1 FROM u; END",
+            "UPDATE FROM",
+            "3.33.0",
+        ),
+        (
+            "SELECT group_concat(a, ',' ORDER BY a) FROM t",
+            "ORDER BY in aggregate function arguments",
+            "3.44.0",
+        ),
+        (
+            "INSERT INTO t VALUES (1) ON CONFLICT (a) DO NOTHING ON CONFLICT DO NOTHING",
+            "multiple ON CONFLICT clauses",
+            "3.35.0",
+        ),
+        (
+            "INSERT INTO t VALUES (1) ON CONFLICT DO NOTHING",
+            "upsert",
+            "3.24.0",
+        ),
+        (
+            "INSERT INTO t VALUES (1) ON CONFLICT DO UPDATE SET a = 1",
+            "ON CONFLICT DO UPDATE without a conflict target",
+            "3.35.0",
+        ),
+        (
+            "SELECT count(*) OVER () FROM t",
+            "window functions",
+            "3.25.0",
+        ),
+        (
+            "SELECT 1 FROM t WINDOW w AS (ORDER BY a)",
+            "window functions",
+            "3.25.0",
+        ),
+        (
+            "SELECT count(*) FILTER (WHERE a) FROM t",
+            "FILTER",
+            "3.30.0",
+        ),
+        (
+            "ALTER TABLE t RENAME COLUMN a TO b",
+            "RENAME COLUMN",
+            "3.25.0",
+        ),
+        ("ALTER TABLE t RENAME a TO b", "RENAME COLUMN", "3.25.0"),
+    ] {
+        // just before the required version
+        let required = version
+            .split('.')
+            .fold(0, |v, n| v * 1000 + n.parse::<u32>().unwrap());
+        let err = Parser::new(input.as_bytes())
+            .with_target_version(required - 1)
+            .next()
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported, "{input}");
+        let Error::ParserError(err, _) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(
+            err.to_string(),
+            format!("{feature} requires SQLite {version}"),
+            "{input}"
+        );
+        let mut parser = Parser::new(input.as_bytes()).with_target_version(3_046_000);
+        assert!(parser.next().unwrap().is_some(), "{input}");
+    }
+    // located on the offending token or node
+    for (input, start, end) in [
+        ("UPDATE t SET a = 1 RETURNING a", 19, 28),
+        ("SELECT a ->> 'b'", 9, 12),
+        ("SELECT * FROM a LEFT JOIN b RIGHT JOIN c", 28, 38),
+        ("SELECT 1 + 1_0", 11, 14),
+        ("SELECT f(a ORDER BY b)", 11, 16),
+        ("UPDATE t SET a = 1 FROM u", 19, 23),
+        (
+            "INSERT INTO t VALUES (1) ON CONFLICT (a) DO NOTHING ON CONFLICT (b) DO UPDATE SET c = 1 WHERE d",
+            52,
+            95,
+        ),
+        ("ALTER TABLE t RENAME COLUMN a TO b", 14, 34),
+        ("ALTER TABLE t DROP COLUMN x", 14, 27),
+    ] {
+        let err = Parser::new(input.as_bytes())
+            .with_target_version(3_024_000)
+            .next()
+            .unwrap_err();
+        let pos = err.position().unwrap();
+        assert_eq!((pos.start, pos.end), (start, end), "{input}");
+    }
+    // supported by the target version, or no target version
+    for input in [
+        "SELECT 1 ORDER BY a NULLS LAST",
+        "SELECT * FROM a LEFT JOIN b",
+        "CREATE TABLE t (x PRIMARY KEY) WITHOUT ROWID",
+        "UPDATE t SET a = 1",
+        "INSERT INTO t VALUES (1) ON CONFLICT (a) DO NOTHING",
+    ] {
+        let mut parser = Parser::new(input.as_bytes()).with_target_version(3_031_000);
+        assert!(parser.next().unwrap().is_some(), "{input}");
+    }
+    parse_cmd(b"DELETE FROM t RETURNING *");
+    // digit separators, whatever the entry point
+    let sql = b"SELECT 1_000";
+    let mut parser = Parser::new(sql).with_target_version(3_045_000);
+    assert_eq!(
+        parser.next_info().unwrap_err().kind(),
+        ErrorKind::Unsupported
+    );
+    let mut parser = StreamParser::new(&sql[..]).with_target_version(3_045_000);
+    assert_eq!(parser.next().unwrap_err().kind(), ErrorKind::Unsupported);
+    let mut statements = Statements::new(sql).with_target_version(3_045_000);
+    assert_eq!(
+        statements.next().unwrap_err().kind(),
+        ErrorKind::Unsupported
+    );
+    let mut parser = Parser::new(sql).with_target_version(3_046_000);
+    assert!(parser.next_info().unwrap().is_some());
+}
+
+#[test]
+fn format_round_trip() {
+    for (input, expected) in [
+        (
+            "ROLLBACK TO SAVEPOINT SAVEPOINT",
+            "ROLLBACK TO \"SAVEPOINT\";",
+        ),
+        (
+            "ATTACH DATABASE DATABASE AS aux KEY 'k'",
+            "ATTACH DATABASE DATABASE AS aux KEY 'k';",
+        ),
+        (
+            "SELECT a IS NOT DISTINCT FROM NOT b",
+            "SELECT a IS NOT DISTINCT FROM NOT b;",
+        ),
+        (
+            "SELECT a IS DISTINCT FROM NOT b",
+            "SELECT a IS DISTINCT FROM NOT b;",
+        ),
+        ("SELECT a IS NOT b", "SELECT a IS NOT b;"),
+    ] {
+        let cmd = parse_cmd(input.as_bytes());
+        let sql = cmd.to_string();
+        assert_eq!(sql, expected);
+        assert_eq!(parse_cmd(sql.as_bytes()), cmd, "{sql}");
+    }
+}
+
+/// Reader returning at most `n` bytes at a time
+struct Chunks<'a> {
+    input: &'a [u8],
+    n: usize,
+}
+
+impl std::io::Read for Chunks<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.n.min(buf.len()).min(self.input.len());
+        buf[..n].copy_from_slice(&self.input[..n]);
+        self.input = &self.input[n..];
+        Ok(n)
+    }
+}
+
 fn expect_parser_err_msg(input: &[u8], error_msg: &str) {
-    expect_parser_err(input, ParserError::Custom(error_msg.to_owned()))
+    let r = parse(input);
+    if let Error::ParserError(
+        ParserError::Custom(msg) | ParserError::Duplicate { message: msg, .. },
+        _,
+    ) = r.unwrap_err()
+    {
+        assert_eq!(msg, error_msg);
+    } else {
+        panic!("unexpected error type")
+    };
 }
 fn expect_parser_err(input: &[u8], err: ParserError) {
     let r = parse(input);
@@ -367,10 +1791,13 @@ fn expect_parser_err(input: &[u8], err: ParserError) {
         panic!("unexpected error type")
     };
 }
-fn parse_cmd(input: &[u8]) -> Cmd {
+fn parse_cmd(input: &[u8]) -> Cmd<'_> {
     parse(input).unwrap().unwrap()
 }
-fn parse(input: &[u8]) -> Result<Option<Cmd>, Error> {
+fn parse(input: &[u8]) -> Result<Option<Cmd<'_>>, Error> {
     let mut parser = Parser::new(input);
     parser.next()
 }
+fn parse_all(input: &[u8]) -> Result<Vec<Cmd<'_>>, Error> {
+    Parser::new(input).collect()
+}
@@ -0,0 +1,219 @@
+//! Fault-tolerant parsing, for editors
+use super::{Error, Parser, TokenType};
+use crate::dialect::{sentinel, Token, TokenType::*};
+use crate::parser::ast::{Cmd, Span};
+use crate::parser::parse::YYCODETYPE;
+use crate::parser::ParserError;
+
+/// Tokens inserted in priority when one is missing before a look-ahead
+const MISSING: [TokenType; 5] = [TK_ID, TK_RP, TK_COMMA, TK_SEMI, TK_END];
+/// Maximum number of tokens inserted at the end of the input
+const MAX_INSERTED: usize = 32;
+
+/// Statement parsed by [`Parser::next_partial`]
+#[derive(Debug)]
+pub struct Partial<'input> {
+    /// Command, with [`ExprKind::Error`](crate::ast::ExprKind::Error) and
+    /// empty names where the input was missing or unexpected. `None` when
+    /// nothing could be parsed.
+    pub cmd: Option<Cmd<'input>>,
+    /// Errors found while parsing the statement, in input order
+    pub errors: Vec<Error>,
+    /// Empty spans where a missing identifier was inserted: the command
+    /// holds an `ExprKind::Error` or an empty name there.
+    pub placeholders: Vec<Span>,
+}
+
+impl<'input> Parser<'input> {
+    /// Parse the next statement, without stopping at the first error.
+    ///
+    /// Missing identifiers, `)` or `,` are inserted and unexpected tokens are
+    /// skipped. Each repair is reported in [`Partial::errors`].
+    /// Errors raised by grammar actions (like duplicate column names) or by
+    /// additional checks cannot be repaired: the rest of the statement is
+    /// skipped and `cmd` is `None`.
+    ///
+    /// Return `None` at the end of the input.
+    ///
+    /// ```rust
+    /// use sqlite3_parser::ast::{Cmd, ExprKind, OneSelect, ResultColumn, Stmt};
+    /// use sqlite3_parser::lexer::sql::Parser;
+    ///
+    /// let mut parser = Parser::new(b"SELECT a, FROM t WHERE");
+    /// let partial = parser.next_partial().unwrap();
+    /// assert_eq!(partial.errors.len(), 2);
+    /// let Some(Cmd::Stmt(Stmt::Select(select), _)) = partial.cmd else {
+    ///     unreachable!()
+    /// };
+    /// let OneSelect::Select { columns, where_clause, .. } = select.body.select else {
+    ///     unreachable!()
+    /// };
+    /// assert!(matches!(&columns[1], ResultColumn::Expr(e, _) if e.kind == ExprKind::Error));
+    /// assert_eq!(where_clause.unwrap().kind, ExprKind::Error);
+    /// assert!(parser.next_partial().is_none());
+    /// ```
+    pub fn next_partial(&mut self) -> Option<Partial<'input>> {
+        let start = self.scanner.offset();
+        self.parser.ctx.reset();
+        self.dqs_fallbacks.clear();
+        let mut errors = Vec::new();
+        let mut last_token_parsed = TK_EOF;
+        let mut eof = false;
+        loop {
+            let next = match self.next_token(last_token_parsed) {
+                Ok(next) => next,
+                Err(mut err) => {
+                    // skip the offending text
+                    err.adjust_offset(self.offset);
+                    let end = match err {
+                        Error::UnterminatedLiteral(_)
+                        | Error::UnterminatedBracket(_)
+                        | Error::UnterminatedBlockComment(_) => self.input.len(),
+                        _ => err.position().map_or(0, |pos| pos.end - self.offset),
+                    };
+                    let end = end.max(self.scanner.offset() + 1);
+                    self.scanner.skip_to(self.input, end);
+                    errors.push(err);
+                    continue;
+                }
+            };
+            let Some((token_type, token)) = next else {
+                eof = true;
+                break;
+            };
+            match self.repair(token_type, token, &mut errors) {
+                Ok(Fed::Parsed) => last_token_parsed = token_type,
+                Ok(Fed::Skipped) => continue,
+                Ok(Fed::Deferred) => {
+                    last_token_parsed = TK_SEMI;
+                    break;
+                }
+                Err(err) => return Some(self.abort(start, err, errors)),
+            }
+            if self.parser.ctx.done() {
+                break;
+            }
+            if token_type == TK_SEMI
+                && !errors.is_empty()
+                && self.parser.yy_accepts_all(&[TK_EOF as YYCODETYPE])
+            {
+                // empty statement after unexpected tokens
+                break;
+            }
+        }
+        if last_token_parsed == TK_EOF && errors.is_empty() {
+            return None; // empty input
+        }
+        if eof && last_token_parsed != TK_EOF {
+            let token = sentinel(self.offset + self.input.len());
+            let res = if last_token_parsed == TK_SEMI {
+                Ok(Fed::Parsed)
+            } else {
+                self.repair(TK_SEMI, token, &mut errors)
+            }
+            .and_then(|_| self.repair(TK_EOF, token, &mut errors));
+            if let Err(err) = res {
+                return Some(self.abort(start, err, errors));
+            }
+        }
+        self.parser.sqlite3ParserFinalize();
+        let mut cmd = self.parser.ctx.cmd();
+        if let Some(ref mut cmd) = cmd {
+            if let Err(e) = self.check(cmd) {
+                errors.push(e);
+            }
+        }
+        let placeholders = self.placeholders();
+        Some(Partial {
+            cmd,
+            errors,
+            placeholders,
+        })
+    }
+
+    /// Feed the parser with `token`, after inserting the missing tokens
+    fn repair(
+        &mut self,
+        token_type: TokenType,
+        token: Token<'input>,
+        errors: &mut Vec<Error>,
+    ) -> Result<Fed, Error> {
+        let at_end = token.1.is_empty();
+        let code = token_type as YYCODETYPE;
+        let mut inserted = 0;
+        while !self.parser.yy_accepts_all(&[code]) {
+            if inserted == 0 && !(at_end && errors.last().is_some_and(is_eof_error)) {
+                let expected = self.parser.yy_expected_tokens();
+                errors.push(self.syntax_error(token_type, token, at_end, expected));
+            }
+            let missing = MISSING
+                .into_iter()
+                .find(|&t| self.parser.yy_accepts_all(&[t as YYCODETYPE, code]))
+                .or_else(|| {
+                    if !at_end || inserted >= MAX_INSERTED {
+                        return None;
+                    }
+                    // insert anything acceptable, until the statement is complete
+                    MISSING
+                        .into_iter()
+                        .find(|&t| self.parser.yy_accepts_all(&[t as YYCODETYPE]))
+                        .or_else(|| {
+                            let expected = self.parser.yy_expected_tokens();
+                            expected.first().and_then(|&t| TokenType::try_from(t).ok())
+                        })
+                });
+            let Some(missing) = missing else {
+                return Ok(Fed::Skipped);
+            };
+            if missing == TK_ID {
+                self.parser.ctx.insert_placeholder(token.0);
+            }
+            self.parse_token(missing, sentinel(token.0))?;
+            inserted += 1;
+            if self.parser.ctx.done() {
+                // `;` inserted: `token` starts the next statement
+                if !at_end {
+                    self.pending = Some((token_type, token));
+                }
+                return Ok(Fed::Deferred);
+            }
+        }
+        self.parse_token(token_type, token)?;
+        Ok(Fed::Parsed)
+    }
+
+    /// Give up on the current statement after an unrecoverable error
+    fn abort(&mut self, start: usize, err: Error, mut errors: Vec<Error>) -> Partial<'input> {
+        self.parser.sqlite3ParserFinalize();
+        let error_end = err
+            .position()
+            .map_or(0, |pos| pos.end.saturating_sub(self.offset));
+        self.skip_statement(start, error_end.max(self.scanner.offset()));
+        errors.push(err);
+        Partial {
+            cmd: None,
+            errors,
+            placeholders: vec![],
+        }
+    }
+
+    /// Spans of the identifiers inserted in the current statement
+    fn placeholders(&self) -> Vec<Span> {
+        let offsets = self.parser.ctx.placeholders();
+        offsets.iter().map(|&o| Span::new(o, o)).collect()
+    }
+}
+
+/// Outcome of [`Parser::repair`]
+enum Fed {
+    /// token parsed
+    Parsed,
+    /// unexpected token skipped
+    Skipped,
+    /// statement ended before the token, which is kept for the next one
+    Deferred,
+}
+
+fn is_eof_error(err: &Error) -> bool {
+    matches!(err, Error::ParserError(ParserError::UnexpectedEof, _))
+}
@@ -0,0 +1,87 @@
+//! UTF-16 input, like `sqlite3_prepare16`
+use std::ops::Range;
+
+use super::{Parser, Position};
+use crate::parser::ast::Span;
+
+/// UTF-16 input transcoded to UTF-8 for the [`Parser`], which keeps track of
+/// the original offsets.
+///
+/// Spans and error positions are reported by the parser as UTF-8 byte
+/// offsets: they can be converted to UTF-16 code units, to line up with
+/// what an editor working on UTF-16 text expects.
+/// Unpaired surrogates are replaced by `U+FFFD`.
+///
+/// ```rust
+/// use fallible_iterator::FallibleIterator;
+/// use sqlite3_parser::lexer::sql::Utf16Input;
+///
+/// let sql: Vec<u16> = "SELECT 'été', x FROM t".encode_utf16().collect();
+/// let input = Utf16Input::new(&sql);
+/// let mut parser = input.parser();
+/// let cmd = parser.next().unwrap().unwrap();
+/// assert_eq!(cmd.span().range(), 0..24); // bytes
+/// assert_eq!(input.span(cmd.span()), 0..22); // code units
+/// assert_eq!(input.utf16_offset(16), 14); // `x`
+/// ```
+pub struct Utf16Input {
+    utf8: Vec<u8>,
+    /// (UTF-8 offset, UTF-16 offset) at the end of each non-ASCII character:
+    /// offsets increase in step between two checkpoints
+    checkpoints: Vec<(usize, usize)>,
+}
+
+impl Utf16Input {
+    /// Transcode `input`
+    pub fn new(input: &[u16]) -> Self {
+        let mut utf8 = Vec::with_capacity(input.len());
+        let mut checkpoints = Vec::new();
+        let mut utf16_offset = 0;
+        for c in char::decode_utf16(input.iter().copied()) {
+            let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+            let mut buf = [0; 4];
+            utf8.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            // an unpaired surrogate is one code unit, like U+FFFD
+            utf16_offset += c.len_utf16();
+            if !c.is_ascii() {
+                checkpoints.push((utf8.len(), utf16_offset));
+            }
+        }
+        Self { utf8, checkpoints }
+    }
+    /// Transcoded input, as UTF-8
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.utf8
+    }
+    /// Parser of the transcoded input
+    pub fn parser(&self) -> Parser<'_> {
+        Parser::new(&self.utf8)
+    }
+    /// Offset in UTF-16 code units of the UTF-8 byte `offset`, which is
+    /// expected to be at a character boundary
+    pub fn utf16_offset(&self, offset: usize) -> usize {
+        let i = self
+            .checkpoints
+            .partition_point(|&(utf8, _)| utf8 <= offset);
+        match i.checked_sub(1).map(|i| self.checkpoints[i]) {
+            Some((utf8, utf16)) => utf16 + (offset - utf8),
+            None => offset,
+        }
+    }
+    /// Range of `span` in UTF-16 code units
+    pub fn span(&self, span: Span) -> Range<usize> {
+        self.utf16_offset(span.start)..self.utf16_offset(span.end)
+    }
+    /// `pos` with offsets and column in UTF-16 code units
+    /// (the line number is unchanged)
+    pub fn position(&self, pos: Position) -> Position {
+        let line_start = self.utf16_offset(pos.start + 1 - pos.column.max(1));
+        let start = self.utf16_offset(pos.start);
+        Position {
+            start,
+            end: self.utf16_offset(pos.end),
+            line: pos.line,
+            column: start - line_start + 1,
+        }
+    }
+}
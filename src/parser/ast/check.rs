@@ -3,26 +3,32 @@ use crate::ast::*;
 use crate::custom_err;
 use std::fmt::{Display, Formatter};
 
-impl Cmd {
+impl Cmd<'_> {
     /// Statement accessor
-    pub fn stmt(&self) -> &Stmt {
+    pub fn stmt(&self) -> &Stmt<'_> {
         match self {
-            Self::Explain(stmt) => stmt,
-            Self::ExplainQueryPlan(stmt) => stmt,
-            Self::Stmt(stmt) => stmt,
+            Self::Explain(stmt, _) => stmt,
+            Self::ExplainQueryPlan(stmt, _) => stmt,
+            Self::Stmt(stmt, _) => stmt,
+        }
+    }
+    /// Location, from `EXPLAIN` if any to the end of the statement (without `;`)
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Explain(_, span) | Self::ExplainQueryPlan(_, span) | Self::Stmt(_, span) => *span,
         }
     }
     /// Like `sqlite3_column_count` but more limited
     pub fn column_count(&self) -> ColumnCount {
         match self {
-            Self::Explain(_) => ColumnCount::Fixed(8),
-            Self::ExplainQueryPlan(_) => ColumnCount::Fixed(4),
-            Self::Stmt(stmt) => stmt.column_count(),
+            Self::Explain(..) => ColumnCount::Fixed(8),
+            Self::ExplainQueryPlan(..) => ColumnCount::Fixed(4),
+            Self::Stmt(stmt, _) => stmt.column_count(),
         }
     }
     /// Like `sqlite3_stmt_isexplain`
     pub fn is_explain(&self) -> bool {
-        matches!(self, Self::Explain(_) | Self::ExplainQueryPlan(_))
+        matches!(self, Self::Explain(..) | Self::ExplainQueryPlan(..))
     }
     /// Like `sqlite3_stmt_readonly`
     pub fn readonly(&self) -> bool {
@@ -34,6 +40,50 @@ impl Cmd {
     }
 }
 
+impl StmtKind {
+    /// Like `sqlite3_stmt_readonly`.
+    ///
+    /// A `PRAGMA` is taken as readonly: see [`Stmt::readonly`] to take its
+    /// value into account.
+    pub fn readonly(self) -> bool {
+        matches!(
+            self,
+            Self::Attach
+                | Self::Begin
+                | Self::Commit
+                | Self::Detach
+                | Self::Pragma
+                | Self::Reindex
+                | Self::Release
+                | Self::Rollback
+                | Self::Savepoint
+                | Self::Select
+        )
+    }
+}
+
+/// Pragmas whose argument only tells what to inspect
+const INSPECTION_PRAGMAS: [&str; 10] = [
+    "foreign_key_check",
+    "foreign_key_list",
+    "index_info",
+    "index_list",
+    "index_xinfo",
+    "integrity_check",
+    "quick_check",
+    "table_info",
+    "table_list",
+    "table_xinfo",
+];
+
+/// Like `sqlite3_stmt_readonly` for `PRAGMA name`, with or without a value
+pub(crate) fn pragma_readonly(name: &str, has_value: bool) -> bool {
+    !has_value
+        || INSPECTION_PRAGMAS
+            .iter()
+            .any(|pragma| name.eq_ignore_ascii_case(pragma))
+}
+
 /// Column count
 pub enum ColumnCount {
     /// With `SELECT *` / PRAGMA
@@ -52,7 +102,7 @@ impl ColumnCount {
     }
 }
 
-impl Stmt {
+impl Stmt<'_> {
     /// Like `sqlite3_column_count` but more limited
     pub fn column_count(&self) -> ColumnCount {
         match self {
@@ -74,20 +124,46 @@ impl Stmt {
         }
     }
 
-    /// Like `sqlite3_stmt_readonly`
+    /// Kind of statement
+    pub fn kind(&self) -> StmtKind {
+        match self {
+            Self::AlterTable(..) => StmtKind::AlterTable,
+            Self::Analyze(..) => StmtKind::Analyze,
+            Self::Attach { .. } => StmtKind::Attach,
+            Self::Begin(..) => StmtKind::Begin,
+            Self::Commit(..) => StmtKind::Commit,
+            Self::CreateIndex { .. } => StmtKind::CreateIndex,
+            Self::CreateTable { .. } => StmtKind::CreateTable,
+            Self::CreateTrigger { .. } => StmtKind::CreateTrigger,
+            Self::CreateView { .. } => StmtKind::CreateView,
+            Self::CreateVirtualTable { .. } => StmtKind::CreateVirtualTable,
+            Self::Delete { .. } => StmtKind::Delete,
+            Self::Detach(..) => StmtKind::Detach,
+            Self::DropIndex { .. } => StmtKind::DropIndex,
+            Self::DropTable { .. } => StmtKind::DropTable,
+            Self::DropTrigger { .. } => StmtKind::DropTrigger,
+            Self::DropView { .. } => StmtKind::DropView,
+            Self::Insert { .. } => StmtKind::Insert,
+            Self::Pragma(..) => StmtKind::Pragma,
+            Self::Reindex { .. } => StmtKind::Reindex,
+            Self::Release(..) => StmtKind::Release,
+            Self::Rollback { .. } => StmtKind::Rollback,
+            Self::Savepoint(..) => StmtKind::Savepoint,
+            Self::Select(..) => StmtKind::Select,
+            Self::Update { .. } => StmtKind::Update,
+            Self::Vacuum(..) => StmtKind::Vacuum,
+        }
+    }
+
+    /// Like `sqlite3_stmt_readonly`.
+    ///
+    /// A `PRAGMA` with a value (`PRAGMA x = 1` or `PRAGMA x(1)`) is taken as
+    /// writing, except the ones whose argument only tells what to inspect,
+    /// like `PRAGMA table_info(t)`.
     pub fn readonly(&self) -> bool {
         match self {
-            Self::Attach { .. } => true,
-            Self::Begin(..) => true,
-            Self::Commit(..) => true,
-            Self::Detach(..) => true,
-            Self::Pragma(..) => true, // TODO check all
-            Self::Reindex { .. } => true,
-            Self::Release(..) => true,
-            Self::Rollback { .. } => true,
-            Self::Savepoint(..) => true,
-            Self::Select(..) => true,
-            _ => false,
+            Self::Pragma(name, body) => pragma_readonly(&name.name.unquoted(), body.is_some()),
+            _ => self.kind().readonly(),
         }
     }
 
@@ -103,16 +179,7 @@ impl Stmt {
                 }
                 Ok(())
             }
-            Self::AlterTable(.., AlterTableBody::AddColumn(cd)) => {
-                for c in cd {
-                    if let ColumnConstraint::PrimaryKey { .. } = c {
-                        return Err(custom_err!("Cannot add a PRIMARY KEY column"));
-                    } else if let ColumnConstraint::Unique(..) = c {
-                        return Err(custom_err!("Cannot add a UNIQUE column"));
-                    }
-                }
-                Ok(())
-            }
+            Self::AlterTable(.., AlterTableBody::AddColumn(cd)) => cd.check(),
             Self::CreateTable {
                 temporary,
                 tbl_name,
@@ -138,7 +205,11 @@ impl Stmt {
                 for (i, c) in columns.iter().enumerate() {
                     for o in &columns[i + 1..] {
                         if c.col_name == o.col_name {
-                            return Err(custom_err!("duplicate column name: {}", c.col_name,));
+                            return Err(ParserError::Duplicate {
+                                message: format!("duplicate column name: {}", c.col_name),
+                                span: o.col_name.1,
+                                previous: c.col_name.1,
+                            });
                         }
                     }
                 }
@@ -183,7 +254,7 @@ impl Stmt {
     }
 }
 
-impl CreateTableBody {
+impl CreateTableBody<'_> {
     /// check for extra rules
     pub fn check(&self, tbl_name: &QualifiedName) -> Result<(), ParserError> {
         if let Self::ColumnsAndConstraints {
@@ -269,11 +340,25 @@ impl CreateTableBody {
     }
 }
 
-impl<'a> IntoIterator for &'a ColumnDefinition {
-    type Item = &'a ColumnConstraint;
+impl ColumnDefinition<'_> {
+    /// check for extra rules, as a column added by `ALTER TABLE ... ADD COLUMN`
+    pub fn check(&self) -> Result<(), ParserError> {
+        for c in self {
+            if let ColumnConstraint::PrimaryKey { .. } = c {
+                return Err(custom_err!("Cannot add a PRIMARY KEY column"));
+            } else if let ColumnConstraint::Unique(..) = c {
+                return Err(custom_err!("Cannot add a UNIQUE column"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'i> IntoIterator for &'a ColumnDefinition<'i> {
+    type Item = &'a ColumnConstraint<'i>;
     type IntoIter = std::iter::Map<
-        std::slice::Iter<'a, NamedColumnConstraint>,
-        fn(&'a NamedColumnConstraint) -> &'a ColumnConstraint,
+        std::slice::Iter<'a, NamedColumnConstraint<'i>>,
+        fn(&'a NamedColumnConstraint<'i>) -> &'a ColumnConstraint<'i>,
     >;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -281,27 +366,27 @@ impl<'a> IntoIterator for &'a ColumnDefinition {
     }
 }
 
-impl Select {
+impl Select<'_> {
     /// Like `sqlite3_column_count` but more limited
     pub fn column_count(&self) -> ColumnCount {
         self.body.select.column_count()
     }
 }
 
-impl OneSelect {
+impl OneSelect<'_> {
     /// Like `sqlite3_column_count` but more limited
     pub fn column_count(&self) -> ColumnCount {
         match self {
             Self::Select { columns, .. } => column_count(columns),
-            Self::Values(values) => {
-                assert!(!values.is_empty()); // TODO Validate
-                ColumnCount::Fixed(values[0].len())
-            }
+            Self::Values(values) => match values.first() {
+                Some(row) => ColumnCount::Fixed(row.len()),
+                None => ColumnCount::None,
+            },
         }
     }
     /// Check all VALUES have the same number of terms
-    pub fn push(values: &mut Vec<Vec<Expr>>, v: Vec<Expr>) -> Result<(), ParserError> {
-        if values[0].len() != v.len() {
+    pub fn push<'i>(values: &mut Vec<Vec<Expr<'i>>>, v: Vec<Expr<'i>>) -> Result<(), ParserError> {
+        if values.first().is_some_and(|row| row.len() != v.len()) {
             return Err(custom_err!("all VALUES must have the same number of terms"));
         }
         values.push(v);
@@ -309,13 +394,13 @@ impl OneSelect {
     }
 }
 
-impl Display for QualifiedName {
+impl Display for QualifiedName<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         self.to_fmt(f)
     }
 }
 
-impl ResultColumn {
+impl ResultColumn<'_> {
     fn column_count(&self) -> ColumnCount {
         match self {
             Self::Expr(..) => ColumnCount::Fixed(1),
@@ -324,7 +409,9 @@ impl ResultColumn {
     }
 }
 fn column_count(cols: &[ResultColumn]) -> ColumnCount {
-    assert!(!cols.is_empty());
+    if cols.is_empty() {
+        return ColumnCount::None;
+    }
     let mut count = ColumnCount::Fixed(0);
     for col in cols {
         match col.column_count() {
@@ -0,0 +1,404 @@
+//! Double-quoted string literals fallback
+use std::borrow::Cow;
+
+use super::value::{is_quoted, unquote};
+use super::*;
+
+/// Settings of the double-quoted string literals fallback, like
+/// `SQLITE_DBCONFIG_DQS_DML` and `SQLITE_DBCONFIG_DQS_DDL`:
+/// a double-quoted identifier which does not match any column is taken as
+/// a string literal when enabled, else it is an error.
+///
+/// Without the database schema, only the identifiers where no column is in
+/// scope are resolved:
+/// - in DML statements (including `CREATE VIEW` and `CREATE TABLE ... AS`):
+///   `VALUES` rows and `SELECT`s without `FROM` clause (and their
+///   subqueries), except in `ORDER BY` clauses which may refer to result
+///   columns,
+/// - in `CREATE TABLE` and `ALTER TABLE ... ADD COLUMN`: `DEFAULT`
+///   expressions, and `CHECK` and generated column expressions which do not
+///   match a column of the table.
+///
+/// `DEFAULT "x"` (without parentheses) is always a string, like in SQLite.
+/// Trigger bodies, `CREATE INDEX`, `UPDATE` and `DELETE` are left
+/// unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dqs {
+    /// Fallback enabled in DML statements
+    pub dml: bool,
+    /// Fallback enabled in DDL statements
+    pub ddl: bool,
+}
+
+/// Like SQLite default build (`SQLITE_DQS=3`): enabled
+impl Default for Dqs {
+    fn default() -> Self {
+        Self {
+            dml: true,
+            ddl: true,
+        }
+    }
+}
+
+impl Cmd<'_> {
+    /// Replace double-quoted identifiers which cannot be columns with string
+    /// literals, according to `dqs`.
+    ///
+    /// Return the locations of the replaced identifiers, or
+    /// [`ParserError::NoSuchColumn`] when the fallback is disabled.
+    ///
+    /// ```rust
+    /// use fallible_iterator::FallibleIterator;
+    /// use sqlite3_parser::ast::Dqs;
+    /// use sqlite3_parser::lexer::sql::Parser;
+    ///
+    /// let mut parser = Parser::new(br#"SELECT "a", "b" FROM t"#);
+    /// let mut cmd = parser.next().unwrap().unwrap();
+    /// assert!(cmd.apply_dqs(Dqs::default()).unwrap().is_empty());
+    ///
+    /// let mut parser = Parser::new(br#"INSERT INTO t VALUES ("a")"#);
+    /// let mut cmd = parser.next().unwrap().unwrap();
+    /// assert_eq!(cmd.apply_dqs(Dqs::default()).unwrap().len(), 1);
+    /// assert_eq!(cmd.to_string(), "INSERT INTO t VALUES ('a');");
+    /// ```
+    pub fn apply_dqs(&mut self, dqs: Dqs) -> Result<Vec<Span>, ParserError> {
+        let stmt = match self {
+            Self::Explain(stmt, _) | Self::ExplainQueryPlan(stmt, _) | Self::Stmt(stmt, _) => stmt,
+        };
+        let mut resolver = Resolver {
+            enabled: dqs.dml,
+            columns: Vec::new(),
+            fallbacks: Vec::new(),
+        };
+        resolver.stmt(stmt, dqs.ddl)?;
+        Ok(resolver.fallbacks)
+    }
+}
+
+impl Expr<'_> {
+    /// Like [`Cmd::apply_dqs`] for an expression on its own, where no
+    /// column is in scope (like in `SELECT expr`)
+    pub(crate) fn apply_dqs(&mut self, dqs: Dqs) -> Result<Vec<Span>, ParserError> {
+        let mut resolver = Resolver {
+            enabled: dqs.dml,
+            columns: Vec::new(),
+            fallbacks: Vec::new(),
+        };
+        resolver.expr(self)?;
+        Ok(resolver.fallbacks)
+    }
+}
+
+impl ColumnDefinition<'_> {
+    /// Like [`Cmd::apply_dqs`] for a column definition on its own, where
+    /// only the column itself is in scope (like in `ALTER TABLE ... ADD COLUMN`)
+    pub(crate) fn apply_dqs(&mut self, dqs: Dqs) -> Result<Vec<Span>, ParserError> {
+        let mut resolver = Resolver {
+            enabled: dqs.ddl,
+            columns: Vec::new(),
+            fallbacks: Vec::new(),
+        };
+        let names = [self.col_name.normalized().into_owned()];
+        resolver.column_def(self, &names)?;
+        Ok(resolver.fallbacks)
+    }
+}
+
+struct Resolver {
+    /// Whether the fallback is enabled for the current statement
+    enabled: bool,
+    /// Normalized names of the columns in scope
+    columns: Vec<String>,
+    /// Locations of the identifiers replaced
+    fallbacks: Vec<Span>,
+}
+
+impl Resolver {
+    fn stmt(&mut self, stmt: &mut Stmt<'_>, ddl: bool) -> Result<(), ParserError> {
+        match stmt {
+            Stmt::Select(select) => self.select(select),
+            Stmt::Insert { with, body, .. } => {
+                self.with(with)?;
+                match body {
+                    InsertBody::Select(select, _) => self.select(select),
+                    InsertBody::DefaultValues => Ok(()),
+                }
+            }
+            Stmt::Update { with, .. } | Stmt::Delete { with, .. } => self.with(with),
+            Stmt::CreateView { select, .. } => self.select(select),
+            Stmt::CreateTable {
+                body: CreateTableBody::AsSelect(select),
+                ..
+            } => self.select(select),
+            Stmt::CreateTable {
+                body:
+                    CreateTableBody::ColumnsAndConstraints {
+                        columns,
+                        constraints,
+                        ..
+                    },
+                ..
+            } => {
+                self.enabled = ddl;
+                let names: Vec<String> = columns
+                    .values()
+                    .map(|cd| cd.col_name.normalized().into_owned())
+                    .collect();
+                for cd in columns.values_mut() {
+                    self.column_def(cd, &names)?;
+                }
+                for constraint in constraints.iter_mut().flatten() {
+                    if let TableConstraint::Check(expr) = &mut constraint.constraint {
+                        self.columns.clone_from(&names);
+                        self.expr(expr)?;
+                    }
+                }
+                Ok(())
+            }
+            Stmt::AlterTable(_, AlterTableBody::AddColumn(cd)) => {
+                self.enabled = ddl;
+                let names = [cd.col_name.normalized().into_owned()];
+                self.column_def(cd, &names)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Constraints of `cd`, in a table whose columns are `names`
+    fn column_def(
+        &mut self,
+        cd: &mut ColumnDefinition<'_>,
+        names: &[String],
+    ) -> Result<(), ParserError> {
+        for constraint in &mut cd.constraints {
+            match &mut constraint.constraint {
+                ColumnConstraint::Default(expr) => {
+                    if let ExprKind::Id(id) = &expr.kind {
+                        // `DEFAULT id`: always a string
+                        if is_quoted(&id.0, b'"') {
+                            self.replace(expr);
+                        }
+                    } else {
+                        self.columns.clear();
+                        self.expr(expr)?;
+                    }
+                }
+                ColumnConstraint::Check(expr) | ColumnConstraint::Generated { expr, .. } => {
+                    self.columns.clear();
+                    self.columns.extend_from_slice(names);
+                    self.expr(expr)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn with(&mut self, with: &mut Option<With<'_>>) -> Result<(), ParserError> {
+        for cte in with.iter_mut().flat_map(|with| &mut with.ctes) {
+            self.select(&mut cte.select)?;
+        }
+        Ok(())
+    }
+
+    /// `select` without outer columns in scope
+    fn select(&mut self, select: &mut Select<'_>) -> Result<(), ParserError> {
+        self.with(&mut select.with)?;
+        let body = &mut select.body;
+        self.one_select(&mut body.select)?;
+        for compound in body.compounds.iter_mut().flatten() {
+            self.one_select(&mut compound.select)?;
+        }
+        Ok(())
+    }
+
+    fn one_select(&mut self, select: &mut OneSelect<'_>) -> Result<(), ParserError> {
+        match select {
+            OneSelect::Select {
+                columns,
+                from: None,
+                where_clause,
+                group_by,
+                ..
+            } => {
+                for column in columns {
+                    if let ResultColumn::Expr(expr, _) = column {
+                        self.expr(expr)?;
+                    }
+                }
+                if let Some(expr) = where_clause {
+                    self.expr(expr)?;
+                }
+                if let Some(group_by) = group_by {
+                    self.exprs(&mut group_by.exprs)?;
+                    if let Some(expr) = &mut group_by.having {
+                        self.expr(expr)?;
+                    }
+                }
+                Ok(())
+            }
+            // Columns in scope: only subqueries in FROM clause are resolved
+            OneSelect::Select {
+                from: Some(from), ..
+            } => self.from(from),
+            OneSelect::Values(rows) => {
+                for row in rows {
+                    self.exprs(row)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn from(&mut self, from: &mut FromClause<'_>) -> Result<(), ParserError> {
+        if let Some(table) = &mut from.select {
+            self.select_table(table)?;
+        }
+        for joined in from.joins.iter_mut().flatten() {
+            self.select_table(&mut joined.table)?;
+        }
+        Ok(())
+    }
+
+    fn select_table(&mut self, table: &mut SelectTable<'_>) -> Result<(), ParserError> {
+        match table {
+            SelectTable::Select(select, _) => self.select(select),
+            SelectTable::Sub(from, _) => self.from(from),
+            SelectTable::Table(..) | SelectTable::TableCall(..) => Ok(()),
+        }
+    }
+
+    fn exprs(&mut self, exprs: &mut [Expr<'_>]) -> Result<(), ParserError> {
+        exprs.iter_mut().try_for_each(|expr| self.expr(expr))
+    }
+
+    fn expr(&mut self, expr: &mut Expr<'_>) -> Result<(), ParserError> {
+        match &mut expr.kind {
+            ExprKind::Id(id) => {
+                if is_quoted(&id.0, b'"') && !self.is_column(id) {
+                    if !self.enabled {
+                        return Err(ParserError::NoSuchColumn {
+                            name: id.0.to_string(),
+                            span: expr.span,
+                        });
+                    }
+                    self.replace(expr);
+                }
+                Ok(())
+            }
+            ExprKind::Between {
+                lhs, start, end, ..
+            } => {
+                self.expr(lhs)?;
+                self.expr(start)?;
+                self.expr(end)
+            }
+            ExprKind::Binary(lhs, _, rhs) => {
+                self.expr(lhs)?;
+                self.expr(rhs)
+            }
+            ExprKind::Case {
+                base,
+                when_then_pairs,
+                else_expr,
+            } => {
+                if let Some(base) = base {
+                    self.expr(base)?;
+                }
+                for (when, then) in when_then_pairs {
+                    self.expr(when)?;
+                    self.expr(then)?;
+                }
+                if let Some(else_expr) = else_expr {
+                    self.expr(else_expr)?;
+                }
+                Ok(())
+            }
+            ExprKind::Cast { expr, .. }
+            | ExprKind::Collate(expr, _)
+            | ExprKind::IsNull(expr)
+            | ExprKind::NotNull(expr)
+            | ExprKind::Unary(_, expr)
+            | ExprKind::Raise(_, Some(expr)) => self.expr(expr),
+            ExprKind::Exists(select) | ExprKind::Subquery(select) => self.subquery(select),
+            ExprKind::FunctionCall {
+                args, filter_over, ..
+            } => {
+                if let Some(args) = args {
+                    self.exprs(args)?;
+                }
+                if let Some(filter) = filter_over
+                    .as_mut()
+                    .and_then(|tail| tail.filter_clause.as_mut())
+                {
+                    self.expr(filter)?;
+                }
+                Ok(())
+            }
+            ExprKind::InList { lhs, rhs, .. } => {
+                self.expr(lhs)?;
+                if let Some(rhs) = rhs {
+                    self.exprs(rhs)?;
+                }
+                Ok(())
+            }
+            ExprKind::InSelect { lhs, rhs, .. } => {
+                self.expr(lhs)?;
+                self.subquery(rhs)
+            }
+            ExprKind::InTable { lhs, args, .. } => {
+                self.expr(lhs)?;
+                if let Some(args) = args {
+                    self.exprs(args)?;
+                }
+                Ok(())
+            }
+            ExprKind::Like {
+                lhs, rhs, escape, ..
+            } => {
+                self.expr(lhs)?;
+                self.expr(rhs)?;
+                if let Some(escape) = escape {
+                    self.expr(escape)?;
+                }
+                Ok(())
+            }
+            ExprKind::Parenthesized(exprs) => self.exprs(exprs),
+            ExprKind::DoublyQualified(..)
+            | ExprKind::Error
+            | ExprKind::FunctionCallStar { .. }
+            | ExprKind::Literal(_)
+            | ExprKind::Name(_)
+            | ExprKind::Qualified(..)
+            | ExprKind::Raise(_, None)
+            | ExprKind::Variable(_) => Ok(()),
+        }
+    }
+
+    /// Subquery in an expression: only resolved when no column is in scope,
+    /// because it may refer to outer columns
+    fn subquery(&mut self, select: &mut Select<'_>) -> Result<(), ParserError> {
+        if self.columns.is_empty() {
+            self.select(select)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn is_column(&self, id: &Id<'_>) -> bool {
+        let name = Name::new(id.0.as_ref());
+        let name = name.normalized();
+        self.columns.iter().any(|column| *column == name)
+    }
+
+    /// Replace the double-quoted identifier `expr` with a string literal
+    fn replace(&mut self, expr: &mut Expr<'_>) {
+        let ExprKind::Id(id) = &expr.kind else {
+            return;
+        };
+        let value = unquote(&id.0, b'"').unwrap_or(Cow::Borrowed(&id.0));
+        let literal = format!("'{}'", value.replace('\'', "''"));
+        expr.kind = ExprKind::Literal(Literal::String(Cow::Owned(literal)));
+        self.fallbacks.push(expr.span);
+    }
+}
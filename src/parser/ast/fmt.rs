@@ -77,6 +77,12 @@ impl ToTokens for String {
     }
 }
 
+impl ToTokens for Cow<'_, str> {
+    fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
+        s.append(TK_ANY, Some(self.as_ref()))
+    }
+}
+
 /* FIXME: does not work, find why
 impl Display for dyn ToTokens {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -89,20 +95,20 @@ impl Display for dyn ToTokens {
 }
 */
 
-impl ToTokens for Cmd {
+impl ToTokens for Cmd<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
-            Self::Explain(stmt) => {
+            Self::Explain(stmt, _) => {
                 s.append(TK_EXPLAIN, None)?;
                 stmt.to_tokens(s)?;
             }
-            Self::ExplainQueryPlan(stmt) => {
+            Self::ExplainQueryPlan(stmt, _) => {
                 s.append(TK_EXPLAIN, None)?;
                 s.append(TK_QUERY, None)?;
                 s.append(TK_PLAN, None)?;
                 stmt.to_tokens(s)?;
             }
-            Self::Stmt(stmt) => {
+            Self::Stmt(stmt, _) => {
                 stmt.to_tokens(s)?;
             }
         }
@@ -110,13 +116,13 @@ impl ToTokens for Cmd {
     }
 }
 
-impl Display for Cmd {
+impl Display for Cmd<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         self.to_fmt(f)
     }
 }
 
-impl ToTokens for Stmt {
+impl ToTokens for Stmt<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::AlterTable(tbl_name, body) => {
@@ -134,6 +140,8 @@ impl ToTokens for Stmt {
             }
             Self::Attach { expr, db_name, key } => {
                 s.append(TK_ATTACH, None)?;
+                // so that a `DATABASE` expression is not taken for the keyword
+                s.append(TK_DATABASE, None)?;
                 expr.to_tokens(s)?;
                 s.append(TK_AS, None)?;
                 db_name.to_tokens(s)?;
@@ -342,6 +350,7 @@ impl ToTokens for Stmt {
             }
             Self::Detach(expr) => {
                 s.append(TK_DETACH, None)?;
+                s.append(TK_DATABASE, None)?;
                 expr.to_tokens(s)
             }
             Self::DropIndex {
@@ -528,7 +537,13 @@ impl ToTokens for Stmt {
     }
 }
 
-impl ToTokens for Expr {
+impl ToTokens for Expr<'_> {
+    fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
+        self.kind.to_tokens(s)
+    }
+}
+
+impl ToTokens for ExprKind<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Between {
@@ -548,7 +563,21 @@ impl ToTokens for Expr {
             }
             Self::Binary(lhs, op, rhs) => {
                 lhs.to_tokens(s)?;
-                op.to_tokens(s)?;
+                match (op, &rhs.kind) {
+                    // `a IS NOT b` would be `a IS NOT (b)`, not `a IS (NOT b)`
+                    (Operator::Is, Self::Unary(UnaryOperator::Not, _)) => {
+                        s.append(TK_IS, None)?;
+                        s.append(TK_NOT, None)?;
+                        s.append(TK_DISTINCT, None)?;
+                        s.append(TK_FROM, None)?;
+                    }
+                    (Operator::IsNot, Self::Unary(UnaryOperator::Not, _)) => {
+                        s.append(TK_IS, None)?;
+                        s.append(TK_DISTINCT, None)?;
+                        s.append(TK_FROM, None)?;
+                    }
+                    _ => op.to_tokens(s)?,
+                }
                 rhs.to_tokens(s)
             }
             Self::Case {
@@ -594,6 +623,7 @@ impl ToTokens for Expr {
                 s.append(TK_DOT, None)?;
                 col_name.to_tokens(s)
             }
+            Self::Error => Ok(()),
             Self::Exists(subquery) => {
                 s.append(TK_EXISTS, None)?;
                 s.append(TK_LP, None)?;
@@ -747,13 +777,13 @@ impl ToTokens for Expr {
     }
 }
 
-impl Display for Expr {
+impl Display for Expr<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         self.to_fmt(f)
     }
 }
 
-impl ToTokens for Literal {
+impl ToTokens for Literal<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Numeric(ref num) => s.append(TK_FLOAT, Some(num)), // TODO Validate TK_FLOAT
@@ -828,7 +858,7 @@ impl ToTokens for UnaryOperator {
     }
 }
 
-impl ToTokens for Select {
+impl ToTokens for Select<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         if let Some(ref with) = self.with {
             with.to_tokens(s)?;
@@ -846,7 +876,7 @@ impl ToTokens for Select {
     }
 }
 
-impl ToTokens for SelectBody {
+impl ToTokens for SelectBody<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.select.to_tokens(s)?;
         if let Some(ref compounds) = self.compounds {
@@ -858,7 +888,7 @@ impl ToTokens for SelectBody {
     }
 }
 
-impl ToTokens for CompoundSelect {
+impl ToTokens for CompoundSelect<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.operator.to_tokens(s)?;
         self.select.to_tokens(s)
@@ -885,7 +915,7 @@ impl Display for CompoundOperator {
     }
 }
 
-impl ToTokens for OneSelect {
+impl ToTokens for OneSelect<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Select {
@@ -935,9 +965,11 @@ impl ToTokens for OneSelect {
     }
 }
 
-impl ToTokens for FromClause {
+impl ToTokens for FromClause<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
-        self.select.as_ref().unwrap().to_tokens(s)?;
+        if let Some(ref select) = self.select {
+            select.to_tokens(s)?;
+        }
         if let Some(ref joins) = self.joins {
             for join in joins {
                 join.to_tokens(s)?;
@@ -959,7 +991,7 @@ impl ToTokens for Distinctness {
     }
 }
 
-impl ToTokens for ResultColumn {
+impl ToTokens for ResultColumn<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Expr(expr, alias) => {
@@ -979,7 +1011,7 @@ impl ToTokens for ResultColumn {
     }
 }
 
-impl ToTokens for As {
+impl ToTokens for As<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::As(ref name) => {
@@ -991,7 +1023,7 @@ impl ToTokens for As {
     }
 }
 
-impl ToTokens for JoinedSelectTable {
+impl ToTokens for JoinedSelectTable<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.operator.to_tokens(s)?;
         self.table.to_tokens(s)?;
@@ -1002,7 +1034,7 @@ impl ToTokens for JoinedSelectTable {
     }
 }
 
-impl ToTokens for SelectTable {
+impl ToTokens for SelectTable<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Table(name, alias, indexed) => {
@@ -1091,7 +1123,7 @@ impl ToTokens for JoinType {
     }
 }
 
-impl ToTokens for JoinConstraint {
+impl ToTokens for JoinConstraint<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::On(expr) => {
@@ -1108,7 +1140,7 @@ impl ToTokens for JoinConstraint {
     }
 }
 
-impl ToTokens for GroupBy {
+impl ToTokens for GroupBy<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         s.append(TK_GROUP, None)?;
         s.append(TK_BY, None)?;
@@ -1121,25 +1153,29 @@ impl ToTokens for GroupBy {
     }
 }
 
-impl ToTokens for Id {
+impl ToTokens for Id<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         double_quote(&self.0, s)
     }
 }
 
-impl ToTokens for Name {
+impl ToTokens for Name<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
-        double_quote(self.0.as_str(), s)
+        if self.quote_style() == QuoteStyle::None {
+            // a keyword accepted as a name in one place may not be in another
+            return s.append(TK_ID, Some(&self.quote_if_needed()));
+        }
+        double_quote(&self.0, s)
     }
 }
 
-impl Display for Name {
+impl Display for Name<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         self.to_fmt(f)
     }
 }
 
-impl ToTokens for QualifiedName {
+impl ToTokens for QualifiedName<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         if let Some(ref db_name) = self.db_name {
             db_name.to_tokens(s)?;
@@ -1154,7 +1190,7 @@ impl ToTokens for QualifiedName {
     }
 }
 
-impl ToTokens for AlterTableBody {
+impl ToTokens for AlterTableBody<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::RenameTo(name) => {
@@ -1182,7 +1218,7 @@ impl ToTokens for AlterTableBody {
     }
 }
 
-impl ToTokens for CreateTableBody {
+impl ToTokens for CreateTableBody<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::ColumnsAndConstraints {
@@ -1192,7 +1228,7 @@ impl ToTokens for CreateTableBody {
             } => {
                 s.append(TK_LP, None)?;
                 comma(columns.values(), s)?;
-                if let Some(constraints) = constraints {
+                if let Some(constraints) = constraints.as_ref().filter(|c| !c.is_empty()) {
                     s.append(TK_COMMA, None)?;
                     comma(constraints, s)?;
                 }
@@ -1214,7 +1250,7 @@ impl ToTokens for CreateTableBody {
     }
 }
 
-impl ToTokens for ColumnDefinition {
+impl ToTokens for ColumnDefinition<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.col_name.to_tokens(s)?;
         if let Some(ref col_type) = self.col_type {
@@ -1227,7 +1263,7 @@ impl ToTokens for ColumnDefinition {
     }
 }
 
-impl ToTokens for NamedColumnConstraint {
+impl ToTokens for NamedColumnConstraint<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         if let Some(ref name) = self.name {
             s.append(TK_CONSTRAINT, None)?;
@@ -1237,7 +1273,7 @@ impl ToTokens for NamedColumnConstraint {
     }
 }
 
-impl ToTokens for ColumnConstraint {
+impl ToTokens for ColumnConstraint<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::PrimaryKey {
@@ -1324,7 +1360,7 @@ impl ToTokens for ColumnConstraint {
     }
 }
 
-impl ToTokens for NamedTableConstraint {
+impl ToTokens for NamedTableConstraint<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         if let Some(ref name) = self.name {
             s.append(TK_CONSTRAINT, None)?;
@@ -1334,7 +1370,7 @@ impl ToTokens for NamedTableConstraint {
     }
 }
 
-impl ToTokens for TableConstraint {
+impl ToTokens for TableConstraint<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::PrimaryKey {
@@ -1424,7 +1460,7 @@ impl ToTokens for NullsOrder {
     }
 }
 
-impl ToTokens for ForeignKeyClause {
+impl ToTokens for ForeignKeyClause<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.tbl_name.to_tokens(s)?;
         if let Some(ref columns) = self.columns {
@@ -1439,7 +1475,7 @@ impl ToTokens for ForeignKeyClause {
     }
 }
 
-impl ToTokens for RefArg {
+impl ToTokens for RefArg<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::OnDelete(ref action) => {
@@ -1512,7 +1548,7 @@ impl ToTokens for InitDeferredPred {
     }
 }
 
-impl ToTokens for IndexedColumn {
+impl ToTokens for IndexedColumn<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.col_name.to_tokens(s)?;
         if let Some(ref collation_name) = self.collation_name {
@@ -1526,7 +1562,7 @@ impl ToTokens for IndexedColumn {
     }
 }
 
-impl ToTokens for Indexed {
+impl ToTokens for Indexed<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::IndexedBy(ref name) => {
@@ -1542,7 +1578,7 @@ impl ToTokens for Indexed {
     }
 }
 
-impl ToTokens for SortedColumn {
+impl ToTokens for SortedColumn<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.expr.to_tokens(s)?;
         if let Some(ref order) = self.order {
@@ -1555,7 +1591,7 @@ impl ToTokens for SortedColumn {
     }
 }
 
-impl ToTokens for Limit {
+impl ToTokens for Limit<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         s.append(TK_LIMIT, None)?;
         self.expr.to_tokens(s)?;
@@ -1567,7 +1603,7 @@ impl ToTokens for Limit {
     }
 }
 
-impl ToTokens for InsertBody {
+impl ToTokens for InsertBody<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Select(select, upsert) => {
@@ -1585,7 +1621,7 @@ impl ToTokens for InsertBody {
     }
 }
 
-impl ToTokens for Set {
+impl ToTokens for Set<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         if self.col_names.len() == 1 {
             comma(self.col_names.deref(), s)?;
@@ -1599,7 +1635,7 @@ impl ToTokens for Set {
     }
 }
 
-impl ToTokens for PragmaBody {
+impl ToTokens for PragmaBody<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Equals(value) => {
@@ -1628,7 +1664,7 @@ impl ToTokens for TriggerTime {
     }
 }
 
-impl ToTokens for TriggerEvent {
+impl ToTokens for TriggerEvent<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Delete => s.append(TK_DELETE, None),
@@ -1643,7 +1679,7 @@ impl ToTokens for TriggerEvent {
     }
 }
 
-impl ToTokens for TriggerCmd {
+impl ToTokens for TriggerCmd<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Update {
@@ -1738,7 +1774,7 @@ impl ToTokens for ResolveType {
     }
 }
 
-impl ToTokens for With {
+impl ToTokens for With<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         s.append(TK_WITH, None)?;
         if self.recursive {
@@ -1748,7 +1784,7 @@ impl ToTokens for With {
     }
 }
 
-impl ToTokens for CommonTableExpr {
+impl ToTokens for CommonTableExpr<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.tbl_name.to_tokens(s)?;
         if let Some(ref columns) = self.columns {
@@ -1773,7 +1809,7 @@ impl ToTokens for CommonTableExpr {
     }
 }
 
-impl ToTokens for Type {
+impl ToTokens for Type<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self.size {
             None => s.append(TK_ID, Some(&self.name)),
@@ -1787,7 +1823,7 @@ impl ToTokens for Type {
     }
 }
 
-impl ToTokens for TypeSize {
+impl ToTokens for TypeSize<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::MaxSize(size) => size.to_tokens(s),
@@ -1813,7 +1849,7 @@ impl ToTokens for TransactionType {
     }
 }
 
-impl ToTokens for Upsert {
+impl ToTokens for Upsert<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         s.append(TK_ON, None)?;
         s.append(TK_CONFLICT, None)?;
@@ -1828,7 +1864,7 @@ impl ToTokens for Upsert {
     }
 }
 
-impl ToTokens for UpsertIndex {
+impl ToTokens for UpsertIndex<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         s.append(TK_LP, None)?;
         comma(&self.targets, s)?;
@@ -1841,7 +1877,7 @@ impl ToTokens for UpsertIndex {
     }
 }
 
-impl ToTokens for UpsertDo {
+impl ToTokens for UpsertDo<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Set { sets, where_clause } => {
@@ -1863,7 +1899,7 @@ impl ToTokens for UpsertDo {
     }
 }
 
-impl ToTokens for FunctionTail {
+impl ToTokens for FunctionTail<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         if let Some(ref filter_clause) = self.filter_clause {
             s.append(TK_FILTER, None)?;
@@ -1880,7 +1916,7 @@ impl ToTokens for FunctionTail {
     }
 }
 
-impl ToTokens for Over {
+impl ToTokens for Over<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::Window(ref window) => window.to_tokens(s),
@@ -1889,7 +1925,7 @@ impl ToTokens for Over {
     }
 }
 
-impl ToTokens for WindowDef {
+impl ToTokens for WindowDef<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.name.to_tokens(s)?;
         s.append(TK_AS, None)?;
@@ -1897,7 +1933,7 @@ impl ToTokens for WindowDef {
     }
 }
 
-impl ToTokens for Window {
+impl ToTokens for Window<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         s.append(TK_LP, None)?;
         if let Some(ref base) = self.base {
@@ -1920,7 +1956,7 @@ impl ToTokens for Window {
     }
 }
 
-impl ToTokens for FrameClause {
+impl ToTokens for FrameClause<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         self.mode.to_tokens(s)?;
         if let Some(ref end) = self.end {
@@ -1952,7 +1988,7 @@ impl ToTokens for FrameMode {
     }
 }
 
-impl ToTokens for FrameBound {
+impl ToTokens for FrameBound<'_> {
     fn to_tokens<S: TokenStream>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
             Self::CurrentRow => {
@@ -1,10 +1,14 @@
 //! Abstract Syntax Tree
 
 pub mod check;
+mod dqs;
 pub mod fmt;
+mod owned;
+mod value;
 
+use std::borrow::Cow;
 use std::num::ParseIntError;
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 use std::str::{self, Bytes, FromStr};
 
 use fmt::{ToTokens, TokenStream};
@@ -12,8 +16,11 @@ use indexmap::{IndexMap, IndexSet};
 
 use crate::custom_err;
 use crate::dialect::TokenType::{self, *};
-use crate::dialect::{from_token, is_identifier, Token};
+use crate::dialect::{from_token, is_identifier, keyword_token, Token};
 use crate::parser::{parse::YYCODETYPE, ParserError};
+pub use dqs::Dqs;
+pub use owned::IntoOwned;
+pub use value::Value;
 
 /// `?` or `$` Prepared statement arg placeholder(s)
 #[derive(Default)]
@@ -47,16 +54,91 @@ impl TokenStream for ParameterInfo {
     }
 }
 
+/// Location of a node in the parsed input: a byte range
+///
+/// Spans are not part of the syntax: they are ignored when nodes are compared
+/// or hashed, so that the same statement parsed at different places is equal.
+#[derive(Clone, Copy, Default)]
+pub struct Span {
+    /// byte offset of the first byte
+    pub start: usize,
+    /// byte offset just past the last byte
+    pub end: usize,
+}
+
+impl Span {
+    /// Constructor
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+    /// Span from the start of `self` to the end of `other`
+    pub fn to(self, other: Self) -> Self {
+        Self::new(self.start, other.end)
+    }
+    /// `true` for a zero-length span (missing optional clause)
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+    /// Byte range, to slice the input
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+    /// Line and column (both 1-based, column is a byte offset like the scanner's)
+    /// where the span starts in `input`
+    pub fn start_position(&self, input: &[u8]) -> (u64, usize) {
+        position(input, self.start)
+    }
+    /// Line and column where the span ends in `input`
+    pub fn end_position(&self, input: &[u8]) -> (u64, usize) {
+        position(input, self.end)
+    }
+}
+
+fn position(input: &[u8], offset: usize) -> (u64, usize) {
+    let offset = offset.min(input.len());
+    let before = &input[..offset];
+    let line = memchr::memchr_iter(b'\n', before).count() as u64 + 1;
+    let column = match memchr::memrchr(b'\n', before) {
+        Some(i) => offset - i,
+        None => offset + 1,
+    };
+    (line, column)
+}
+
+impl From<&Token<'_>> for Span {
+    fn from(token: &Token<'_>) -> Self {
+        Self::new(token.0, token.2)
+    }
+}
+
+impl std::fmt::Debug for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// Spans are ignored
+impl PartialEq for Span {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+impl Eq for Span {}
+/// Spans are ignored
+impl std::hash::Hash for Span {
+    fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+}
+
 /// Statement or Explain statement
 // https://sqlite.org/syntax/sql-stmt.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Cmd {
+pub enum Cmd<'i> {
     /// `EXPLAIN` statement
-    Explain(Stmt),
+    Explain(Stmt<'i>, Span),
     /// `EXPLAIN QUERY PLAN` statement
-    ExplainQueryPlan(Stmt),
+    ExplainQueryPlan(Stmt<'i>, Span),
     /// statement
-    Stmt(Stmt),
+    Stmt(Stmt<'i>, Span),
 }
 
 pub(crate) enum ExplainKind {
@@ -67,25 +149,25 @@ pub(crate) enum ExplainKind {
 /// SQL statement
 // https://sqlite.org/syntax/sql-stmt.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Stmt {
+pub enum Stmt<'i> {
     /// `ALTER TABLE`: table name, body
-    AlterTable(QualifiedName, AlterTableBody),
+    AlterTable(QualifiedName<'i>, AlterTableBody<'i>),
     /// `ANALYSE`: object name
-    Analyze(Option<QualifiedName>),
+    Analyze(Option<QualifiedName<'i>>),
     /// `ATTACH DATABASE`
     Attach {
         /// filename
         // TODO distinction between ATTACH and ATTACH DATABASE
-        expr: Expr,
+        expr: Expr<'i>,
         /// schema name
-        db_name: Expr,
+        db_name: Expr<'i>,
         /// password
-        key: Option<Expr>,
+        key: Option<Expr<'i>>,
     },
     /// `BEGIN`: tx type, tx name
-    Begin(Option<TransactionType>, Option<Name>),
+    Begin(Option<TransactionType>, Option<Name<'i>>),
     /// `COMMIT`/`END`: tx name
-    Commit(Option<Name>), // TODO distinction between COMMIT and END
+    Commit(Option<Name<'i>>), // TODO distinction between COMMIT and END
     /// `CREATE INDEX`
     CreateIndex {
         /// `UNIQUE`
@@ -93,13 +175,13 @@ pub enum Stmt {
         /// `IF NOT EXISTS`
         if_not_exists: bool,
         /// index name
-        idx_name: QualifiedName,
+        idx_name: QualifiedName<'i>,
         /// table name
-        tbl_name: Name,
+        tbl_name: Name<'i>,
         /// indexed columns or expressions
-        columns: Vec<SortedColumn>,
+        columns: Vec<SortedColumn<'i>>,
         /// partial index
-        where_clause: Option<Expr>,
+        where_clause: Option<Expr<'i>>,
     },
     /// `CREATE TABLE`
     CreateTable {
@@ -108,9 +190,9 @@ pub enum Stmt {
         /// `IF NOT EXISTS`
         if_not_exists: bool,
         /// table name
-        tbl_name: QualifiedName,
+        tbl_name: QualifiedName<'i>,
         /// table body
-        body: CreateTableBody,
+        body: CreateTableBody<'i>,
     },
     /// `CREATE TRIGGER`
     CreateTrigger {
@@ -119,19 +201,19 @@ pub enum Stmt {
         /// `IF NOT EXISTS`
         if_not_exists: bool,
         /// trigger name
-        trigger_name: QualifiedName,
+        trigger_name: QualifiedName<'i>,
         /// `BEFORE`/`AFTER`/`INSTEAD OF`
         time: Option<TriggerTime>,
         /// `DELETE`/`INSERT`/`UPDATE`
-        event: TriggerEvent,
+        event: TriggerEvent<'i>,
         /// table name
-        tbl_name: QualifiedName,
+        tbl_name: QualifiedName<'i>,
         /// `FOR EACH ROW`
         for_each_row: bool,
         /// `WHEN`
-        when_clause: Option<Expr>,
+        when_clause: Option<Expr<'i>>,
         /// statements
-        commands: Vec<TriggerCmd>,
+        commands: Vec<TriggerCmd<'i>>,
     },
     /// `CREATE VIEW`
     CreateView {
@@ -140,283 +222,356 @@ pub enum Stmt {
         /// `IF NOT EXISTS`
         if_not_exists: bool,
         /// view name
-        view_name: QualifiedName,
+        view_name: QualifiedName<'i>,
         /// columns
-        columns: Option<Vec<IndexedColumn>>,
+        columns: Option<Vec<IndexedColumn<'i>>>,
         /// query
-        select: Box<Select>,
+        select: Box<Select<'i>>,
     },
     /// `CREATE VIRTUAL TABLE`
     CreateVirtualTable {
         /// `IF NOT EXISTS`
         if_not_exists: bool,
         /// table name
-        tbl_name: QualifiedName,
+        tbl_name: QualifiedName<'i>,
         /// module
-        module_name: Name,
+        module_name: Name<'i>,
         /// args
-        args: Option<Vec<String>>, // TODO smol str
+        args: Option<Vec<Cow<'i, str>>>,
     },
     /// `DELETE`
     Delete {
         /// CTE
-        with: Option<With>,
+        with: Option<With<'i>>,
         /// `FROM` table name
-        tbl_name: QualifiedName,
+        tbl_name: QualifiedName<'i>,
         /// `INDEXED`
-        indexed: Option<Indexed>,
+        indexed: Option<Indexed<'i>>,
         /// `WHERE` clause
-        where_clause: Option<Expr>,
+        where_clause: Option<Expr<'i>>,
         /// `RETURNING`
-        returning: Option<Vec<ResultColumn>>,
+        returning: Option<Vec<ResultColumn<'i>>>,
         /// `ORDER BY`
-        order_by: Option<Vec<SortedColumn>>,
+        order_by: Option<Vec<SortedColumn<'i>>>,
         /// `LIMIT`
-        limit: Option<Limit>,
+        limit: Option<Limit<'i>>,
     },
     /// `DETACH DATABASE`: db name
-    Detach(Expr), // TODO distinction between DETACH and DETACH DATABASE
+    Detach(Expr<'i>), // TODO distinction between DETACH and DETACH DATABASE
     /// `DROP INDEX`
     DropIndex {
         /// `IF EXISTS`
         if_exists: bool,
         /// index name
-        idx_name: QualifiedName,
+        idx_name: QualifiedName<'i>,
     },
     /// `DROP TABLE`
     DropTable {
         /// `IF EXISTS`
         if_exists: bool,
         /// table name
-        tbl_name: QualifiedName,
+        tbl_name: QualifiedName<'i>,
     },
     /// `DROP TRIGGER`
     DropTrigger {
         /// `IF EXISTS`
         if_exists: bool,
         /// trigger name
-        trigger_name: QualifiedName,
+        trigger_name: QualifiedName<'i>,
     },
     /// `DROP VIEW`
     DropView {
         /// `IF EXISTS`
         if_exists: bool,
         /// view name
-        view_name: QualifiedName,
+        view_name: QualifiedName<'i>,
     },
     /// `INSERT`
     Insert {
         /// CTE
-        with: Option<With>,
+        with: Option<With<'i>>,
         /// `OR`
         or_conflict: Option<ResolveType>, // TODO distinction between REPLACE and INSERT OR REPLACE
         /// table name
-        tbl_name: QualifiedName,
+        tbl_name: QualifiedName<'i>,
         /// `COLUMNS`
-        columns: Option<DistinctNames>,
+        columns: Option<DistinctNames<'i>>,
         /// `VALUES` or `SELECT`
-        body: InsertBody,
+        body: InsertBody<'i>,
         /// `RETURNING`
-        returning: Option<Vec<ResultColumn>>,
+        returning: Option<Vec<ResultColumn<'i>>>,
     },
     /// `PRAGMA`: pragma name, body
-    Pragma(QualifiedName, Option<PragmaBody>),
+    Pragma(QualifiedName<'i>, Option<PragmaBody<'i>>),
     /// `REINDEX`
     Reindex {
         /// collation or index or table name
-        obj_name: Option<QualifiedName>,
+        obj_name: Option<QualifiedName<'i>>,
     },
     /// `RELEASE`: savepoint name
-    Release(Name), // TODO distinction between RELEASE and RELEASE SAVEPOINT
+    Release(Name<'i>), // TODO distinction between RELEASE and RELEASE SAVEPOINT
     /// `ROLLBACK`
     Rollback {
         /// transaction name
-        tx_name: Option<Name>,
+        tx_name: Option<Name<'i>>,
         /// savepoint name
-        savepoint_name: Option<Name>, // TODO distinction between TO and TO SAVEPOINT
+        savepoint_name: Option<Name<'i>>, // TODO distinction between TO and TO SAVEPOINT
     },
     /// `SAVEPOINT`: savepoint name
-    Savepoint(Name),
+    Savepoint(Name<'i>),
     /// `SELECT`
-    Select(Box<Select>),
+    Select(Box<Select<'i>>),
     /// `UPDATE`
     Update {
         /// CTE
-        with: Option<With>,
+        with: Option<With<'i>>,
         /// `OR`
         or_conflict: Option<ResolveType>,
         /// table name
-        tbl_name: QualifiedName,
+        tbl_name: QualifiedName<'i>,
         /// `INDEXED`
-        indexed: Option<Indexed>,
+        indexed: Option<Indexed<'i>>,
         /// `SET` assignments
-        sets: Vec<Set>,
+        sets: Vec<Set<'i>>,
         /// `FROM`
-        from: Option<FromClause>,
+        from: Option<FromClause<'i>>,
         /// `WHERE` clause
-        where_clause: Option<Expr>,
+        where_clause: Option<Expr<'i>>,
         /// `RETURNING`
-        returning: Option<Vec<ResultColumn>>,
+        returning: Option<Vec<ResultColumn<'i>>>,
         /// `ORDER BY`
-        order_by: Option<Vec<SortedColumn>>,
+        order_by: Option<Vec<SortedColumn<'i>>>,
         /// `LIMIT`
-        limit: Option<Limit>,
+        limit: Option<Limit<'i>>,
     },
     /// `VACUUM`: database name, into expr
-    Vacuum(Option<Name>, Option<Expr>),
+    Vacuum(Option<Name<'i>>, Option<Expr<'i>>),
+}
+
+/// Kind of [`Stmt`], without its content
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StmtKind {
+    /// `ALTER TABLE`
+    AlterTable,
+    /// `ANALYZE`
+    Analyze,
+    /// `ATTACH DATABASE`
+    Attach,
+    /// `BEGIN`
+    Begin,
+    /// `COMMIT`/`END`
+    Commit,
+    /// `CREATE INDEX`
+    CreateIndex,
+    /// `CREATE TABLE`
+    CreateTable,
+    /// `CREATE TRIGGER`
+    CreateTrigger,
+    /// `CREATE VIEW`
+    CreateView,
+    /// `CREATE VIRTUAL TABLE`
+    CreateVirtualTable,
+    /// `DELETE`
+    Delete,
+    /// `DETACH DATABASE`
+    Detach,
+    /// `DROP INDEX`
+    DropIndex,
+    /// `DROP TABLE`
+    DropTable,
+    /// `DROP TRIGGER`
+    DropTrigger,
+    /// `DROP VIEW`
+    DropView,
+    /// `INSERT`
+    Insert,
+    /// `PRAGMA`
+    Pragma,
+    /// `REINDEX`
+    Reindex,
+    /// `RELEASE`
+    Release,
+    /// `ROLLBACK`
+    Rollback,
+    /// `SAVEPOINT`
+    Savepoint,
+    /// `SELECT`
+    Select,
+    /// `UPDATE`
+    Update,
+    /// `VACUUM`
+    Vacuum,
 }
 
 /// SQL expression
 // https://sqlite.org/syntax/expr.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Expr {
+pub struct Expr<'i> {
+    /// expression
+    pub kind: ExprKind<'i>,
+    /// location
+    pub span: Span,
+}
+
+/// SQL expression kind
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExprKind<'i> {
     /// `BETWEEN`
     Between {
         /// expression
-        lhs: Box<Expr>,
+        lhs: Box<Expr<'i>>,
         /// `NOT`
         not: bool,
         /// start
-        start: Box<Expr>,
+        start: Box<Expr<'i>>,
         /// end
-        end: Box<Expr>,
+        end: Box<Expr<'i>>,
     },
     /// binary expression
-    Binary(Box<Expr>, Operator, Box<Expr>),
+    Binary(Box<Expr<'i>>, Operator, Box<Expr<'i>>),
     /// `CASE` expression
     Case {
         /// operand
-        base: Option<Box<Expr>>,
+        base: Option<Box<Expr<'i>>>,
         /// `WHEN` condition `THEN` result
-        when_then_pairs: Vec<(Expr, Expr)>,
+        when_then_pairs: Vec<(Expr<'i>, Expr<'i>)>,
         /// `ELSE` result
-        else_expr: Option<Box<Expr>>,
+        else_expr: Option<Box<Expr<'i>>>,
     },
     /// CAST expression
     Cast {
         /// expression
-        expr: Box<Expr>,
+        expr: Box<Expr<'i>>,
         /// `AS` type name
-        type_name: Option<Type>,
+        type_name: Option<Type<'i>>,
     },
     /// `COLLATE`: expression
-    Collate(Box<Expr>, String),
+    Collate(Box<Expr<'i>>, Cow<'i, str>),
     /// schema-name.table-name.column-name
-    DoublyQualified(Name, Name, Name),
+    DoublyQualified(Name<'i>, Name<'i>, Name<'i>),
+    /// missing expression, only produced by [`Parser::next_partial`](crate::lexer::sql::Parser::next_partial)
+    Error,
     /// `EXISTS` subquery
-    Exists(Box<Select>),
+    Exists(Box<Select<'i>>),
     /// call to a built-in function
     FunctionCall {
         /// function name
-        name: Id,
+        name: Id<'i>,
         /// `DISTINCT`
         distinctness: Option<Distinctness>,
         /// arguments
-        args: Option<Vec<Expr>>,
+        args: Option<Vec<Expr<'i>>>,
         /// `ORDER BY`
-        order_by: Option<Vec<SortedColumn>>,
+        order_by: Option<Vec<SortedColumn<'i>>>,
         /// `FILTER`
-        filter_over: Option<FunctionTail>,
+        filter_over: Option<FunctionTail<'i>>,
     },
     /// Function call expression with '*' as arg
     FunctionCallStar {
         /// function name
-        name: Id,
+        name: Id<'i>,
         /// `FILTER`
-        filter_over: Option<FunctionTail>,
+        filter_over: Option<FunctionTail<'i>>,
     },
     /// Identifier
-    Id(Id),
+    Id(Id<'i>),
     /// `IN`
     InList {
         /// expression
-        lhs: Box<Expr>,
+        lhs: Box<Expr<'i>>,
         /// `NOT`
         not: bool,
         /// values
-        rhs: Option<Vec<Expr>>,
+        rhs: Option<Vec<Expr<'i>>>,
     },
     /// `IN` subselect
     InSelect {
         /// expression
-        lhs: Box<Expr>,
+        lhs: Box<Expr<'i>>,
         /// `NOT`
         not: bool,
         /// subquery
-        rhs: Box<Select>,
+        rhs: Box<Select<'i>>,
     },
     /// `IN` table name / function
     InTable {
         /// expression
-        lhs: Box<Expr>,
+        lhs: Box<Expr<'i>>,
         /// `NOT`
         not: bool,
         /// table name
-        rhs: Box<QualifiedName>,
+        rhs: Box<QualifiedName<'i>>,
         /// table function arguments
-        args: Option<Vec<Expr>>,
+        args: Option<Vec<Expr<'i>>>,
     },
     /// `IS NULL`
-    IsNull(Box<Expr>),
+    IsNull(Box<Expr<'i>>),
     /// `LIKE`
     Like {
         /// expression
-        lhs: Box<Expr>,
+        lhs: Box<Expr<'i>>,
         /// `NOT`
         not: bool,
         /// operator
         op: LikeOperator,
         /// pattern
-        rhs: Box<Expr>,
+        rhs: Box<Expr<'i>>,
         /// `ESCAPE` char
-        escape: Option<Box<Expr>>,
+        escape: Option<Box<Expr<'i>>>,
     },
     /// Literal expression
-    Literal(Literal),
+    Literal(Literal<'i>),
     /// Name
-    Name(Name),
+    Name(Name<'i>),
     /// `NOT NULL` or `NOTNULL`
-    NotNull(Box<Expr>),
+    NotNull(Box<Expr<'i>>),
     /// Parenthesized subexpression
-    Parenthesized(Vec<Expr>),
+    Parenthesized(Vec<Expr<'i>>),
     /// Qualified name
-    Qualified(Name, Name),
+    Qualified(Name<'i>, Name<'i>),
     /// `RAISE` function call
-    Raise(ResolveType, Option<Box<Expr>>),
+    Raise(ResolveType, Option<Box<Expr<'i>>>),
     /// Subquery expression
-    Subquery(Box<Select>),
+    Subquery(Box<Select<'i>>),
     /// Unary expression
-    Unary(UnaryOperator, Box<Expr>),
+    Unary(UnaryOperator, Box<Expr<'i>>),
     /// Parameters
-    Variable(String),
+    Variable(Cow<'i, str>),
 }
 
-impl Expr {
+impl<'i> Expr<'i> {
     /// Constructor
-    pub fn parenthesized(x: Self) -> Self {
+    pub fn new(kind: ExprKind<'i>, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl<'i> ExprKind<'i> {
+    /// Constructor
+    pub fn parenthesized(x: Expr<'i>) -> Self {
         Self::Parenthesized(vec![x])
     }
     /// Constructor
-    pub fn id(xt: YYCODETYPE, x: Token) -> Self {
+    pub fn id(xt: YYCODETYPE, x: Token<'i>) -> Self {
         Self::Id(Id::from_token(xt, x))
     }
     /// Constructor
-    pub fn collate(x: Self, ct: YYCODETYPE, c: Token) -> Self {
+    pub fn collate(x: Expr<'i>, ct: YYCODETYPE, c: Token<'i>) -> Self {
         Self::Collate(Box::new(x), from_token(ct, c))
     }
     /// Constructor
-    pub fn cast(x: Self, type_name: Option<Type>) -> Self {
+    pub fn cast(x: Expr<'i>, type_name: Option<Type<'i>>) -> Self {
         Self::Cast {
             expr: Box::new(x),
             type_name,
         }
     }
     /// Constructor
-    pub fn binary(left: Self, op: YYCODETYPE, right: Self) -> Self {
+    pub fn binary(left: Expr<'i>, op: YYCODETYPE, right: Expr<'i>) -> Self {
         Self::Binary(Box::new(left), Operator::from(op), Box::new(right))
     }
     /// Constructor
-    pub fn ptr(left: Self, op: Token, right: Self) -> Self {
+    pub fn ptr(left: Expr<'i>, op: Token, right: Expr<'i>) -> Self {
         let mut ptr = Operator::ArrowRight;
         if op.1 == b"->>" {
             ptr = Operator::ArrowRightShift;
@@ -424,7 +579,13 @@ impl Expr {
         Self::Binary(Box::new(left), ptr, Box::new(right))
     }
     /// Constructor
-    pub fn like(lhs: Self, not: bool, op: LikeOperator, rhs: Self, escape: Option<Self>) -> Self {
+    pub fn like(
+        lhs: Expr<'i>,
+        not: bool,
+        op: LikeOperator,
+        rhs: Expr<'i>,
+        escape: Option<Expr<'i>>,
+    ) -> Self {
         Self::Like {
             lhs: Box::new(lhs),
             not,
@@ -434,7 +595,7 @@ impl Expr {
         }
     }
     /// Constructor
-    pub fn not_null(x: Self, op: YYCODETYPE) -> Self {
+    pub fn not_null(x: Expr<'i>, op: YYCODETYPE) -> Self {
         if op == TK_ISNULL as YYCODETYPE {
             Self::IsNull(Box::new(x))
         } else if op == TK_NOTNULL as YYCODETYPE {
@@ -444,11 +605,11 @@ impl Expr {
         }
     }
     /// Constructor
-    pub fn unary(op: UnaryOperator, x: Self) -> Self {
+    pub fn unary(op: UnaryOperator, x: Expr<'i>) -> Self {
         Self::Unary(op, Box::new(x))
     }
     /// Constructor
-    pub fn between(lhs: Self, not: bool, start: Self, end: Self) -> Self {
+    pub fn between(lhs: Expr<'i>, not: bool, start: Expr<'i>, end: Expr<'i>) -> Self {
         Self::Between {
             lhs: Box::new(lhs),
             not,
@@ -457,7 +618,7 @@ impl Expr {
         }
     }
     /// Constructor
-    pub fn in_list(lhs: Self, not: bool, rhs: Option<Vec<Self>>) -> Self {
+    pub fn in_list(lhs: Expr<'i>, not: bool, rhs: Option<Vec<Expr<'i>>>) -> Self {
         Self::InList {
             lhs: Box::new(lhs),
             not,
@@ -465,7 +626,7 @@ impl Expr {
         }
     }
     /// Constructor
-    pub fn in_select(lhs: Self, not: bool, rhs: Select) -> Self {
+    pub fn in_select(lhs: Expr<'i>, not: bool, rhs: Select<'i>) -> Self {
         Self::InSelect {
             lhs: Box::new(lhs),
             not,
@@ -473,7 +634,12 @@ impl Expr {
         }
     }
     /// Constructor
-    pub fn in_table(lhs: Self, not: bool, rhs: QualifiedName, args: Option<Vec<Self>>) -> Self {
+    pub fn in_table(
+        lhs: Expr<'i>,
+        not: bool,
+        rhs: QualifiedName<'i>,
+        args: Option<Vec<Expr<'i>>>,
+    ) -> Self {
         Self::InTable {
             lhs: Box::new(lhs),
             not,
@@ -482,24 +648,22 @@ impl Expr {
         }
     }
     /// Constructor
-    pub fn sub_query(query: Select) -> Self {
+    pub fn sub_query(query: Select<'i>) -> Self {
         Self::Subquery(Box::new(query))
     }
 }
 
 /// SQL literal
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Literal {
-    /// Number
-    Numeric(String),
-    /// String
-    // TODO Check that string is already quoted and correctly escaped
-    String(String),
-    /// BLOB
-    // TODO Check that string is valid (only hexa)
-    Blob(String),
+pub enum Literal<'i> {
+    /// Number, as written (see [`Literal::value`])
+    Numeric(Cow<'i, str>),
+    /// String, with its quotes (see [`Literal::value`])
+    String(Cow<'i, str>),
+    /// BLOB, hexadecimal digits only (see [`Literal::value`])
+    Blob(Cow<'i, str>),
     /// Keyword
-    Keyword(String),
+    Keyword(Cow<'i, str>),
     /// `NULL`
     Null,
     /// `CURRENT_DATE`
@@ -510,17 +674,27 @@ pub enum Literal {
     CurrentTimestamp,
 }
 
-impl Literal {
+impl<'i> Literal<'i> {
     /// Constructor
+    ///
+    /// # Panics
+    ///
+    /// When `token` is not `CURRENT_DATE`, `CURRENT_TIME` or
+    /// `CURRENT_TIMESTAMP`: see [`try_from_ctime_kw`](Self::try_from_ctime_kw).
     pub fn from_ctime_kw(token: Token) -> Self {
+        Self::try_from_ctime_kw(token).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Constructor, failing when `token` is not `CURRENT_DATE`,
+    /// `CURRENT_TIME` or `CURRENT_TIMESTAMP`
+    pub fn try_from_ctime_kw(token: Token) -> Result<Self, ParserError> {
         if b"CURRENT_DATE".eq_ignore_ascii_case(token.1) {
-            Self::CurrentDate
+            Ok(Self::CurrentDate)
         } else if b"CURRENT_TIME".eq_ignore_ascii_case(token.1) {
-            Self::CurrentTime
+            Ok(Self::CurrentTime)
         } else if b"CURRENT_TIMESTAMP".eq_ignore_ascii_case(token.1) {
-            Self::CurrentTimestamp
+            Ok(Self::CurrentTimestamp)
         } else {
-            unreachable!()
+            Err(unexpected_token(token))
         }
     }
 }
@@ -540,23 +714,42 @@ pub enum LikeOperator {
 
 impl LikeOperator {
     /// Constructor
+    ///
+    /// # Panics
+    ///
+    /// When `token` is not `MATCH`, `LIKE`, `GLOB` or `REGEXP`:
+    /// see [`try_from_token`](Self::try_from_token).
     pub fn from_token(token_type: YYCODETYPE, token: Token) -> Self {
+        Self::try_from_token(token_type, token).unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// Constructor, failing when `token` is not `MATCH`, `LIKE`, `GLOB` or
+    /// `REGEXP`
+    pub fn try_from_token(token_type: YYCODETYPE, token: Token) -> Result<Self, ParserError> {
         if token_type == TK_MATCH as YYCODETYPE {
-            return Self::Match;
+            return Ok(Self::Match);
         } else if token_type == TK_LIKE_KW as YYCODETYPE {
-            let token = token.1;
-            if b"LIKE".eq_ignore_ascii_case(token) {
-                return Self::Like;
-            } else if b"GLOB".eq_ignore_ascii_case(token) {
-                return Self::Glob;
-            } else if b"REGEXP".eq_ignore_ascii_case(token) {
-                return Self::Regexp;
+            let text = token.1;
+            if b"LIKE".eq_ignore_ascii_case(text) {
+                return Ok(Self::Like);
+            } else if b"GLOB".eq_ignore_ascii_case(text) {
+                return Ok(Self::Glob);
+            } else if b"REGEXP".eq_ignore_ascii_case(text) {
+                return Ok(Self::Regexp);
             }
         }
-        unreachable!()
+        Err(unexpected_token(token))
     }
 }
 
+/// Error for a token whose text does not match its type, like an empty
+/// token inserted by [`Parser::next_partial`](crate::lexer::sql::Parser::next_partial)
+fn unexpected_token(token: Token) -> ParserError {
+    custom_err!(
+        "near \"{}\": syntax error",
+        String::from_utf8_lossy(token.1)
+    )
+}
+
 /// SQL operators
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Operator {
@@ -663,28 +856,28 @@ impl From<YYCODETYPE> for UnaryOperator {
 // https://sqlite.org/lang_select.html
 // https://sqlite.org/syntax/factored-select-stmt.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Select {
+pub struct Select<'i> {
     /// CTE
-    pub with: Option<With>,
+    pub with: Option<With<'i>>,
     /// body
-    pub body: SelectBody,
+    pub body: SelectBody<'i>,
     /// `ORDER BY`
-    pub order_by: Option<Vec<SortedColumn>>, // ORDER BY term does not match any column in the result set
+    pub order_by: Option<Vec<SortedColumn<'i>>>, // ORDER BY term does not match any column in the result set
     /// `LIMIT`
-    pub limit: Option<Limit>,
+    pub limit: Option<Limit<'i>>,
 }
 
 /// `SELECT` body
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct SelectBody {
+pub struct SelectBody<'i> {
     /// first select
-    pub select: OneSelect,
+    pub select: OneSelect<'i>,
     /// compounds
-    pub compounds: Option<Vec<CompoundSelect>>,
+    pub compounds: Option<Vec<CompoundSelect<'i>>>,
 }
 
-impl SelectBody {
-    pub(crate) fn push(&mut self, cs: CompoundSelect) -> Result<(), ParserError> {
+impl<'i> SelectBody<'i> {
+    pub(crate) fn push(&mut self, cs: CompoundSelect<'i>) -> Result<(), ParserError> {
         use crate::ast::check::ColumnCount;
         if let ColumnCount::Fixed(n) = self.select.column_count() {
             if let ColumnCount::Fixed(m) = cs.select.column_count() {
@@ -707,11 +900,11 @@ impl SelectBody {
 
 /// Compound select
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct CompoundSelect {
+pub struct CompoundSelect<'i> {
     /// operator
     pub operator: CompoundOperator,
     /// select
-    pub select: OneSelect,
+    pub select: OneSelect<'i>,
 }
 
 /// Compound operators
@@ -731,37 +924,38 @@ pub enum CompoundOperator {
 /// `SELECT` core
 // https://sqlite.org/syntax/select-core.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum OneSelect {
+#[allow(clippy::large_enum_variant)]
+pub enum OneSelect<'i> {
     /// `SELECT`
     Select {
         /// `DISTINCT`
         distinctness: Option<Distinctness>,
         /// columns
-        columns: Vec<ResultColumn>,
+        columns: Vec<ResultColumn<'i>>,
         /// `FROM` clause
-        from: Option<FromClause>,
+        from: Option<FromClause<'i>>,
         /// `WHERE` clause
-        where_clause: Option<Expr>,
+        where_clause: Option<Expr<'i>>,
         /// `GROUP BY`
-        group_by: Option<GroupBy>,
+        group_by: Option<GroupBy<'i>>,
         /// `WINDOW` definition
-        window_clause: Option<Vec<WindowDef>>,
+        window_clause: Option<Vec<WindowDef<'i>>>,
     },
     /// `VALUES`
-    Values(Vec<Vec<Expr>>),
+    Values(Vec<Vec<Expr<'i>>>),
 }
 
 /// `SELECT` ... `FROM` clause
 // https://sqlite.org/syntax/join-clause.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct FromClause {
+pub struct FromClause<'i> {
     /// table
-    pub select: Option<Box<SelectTable>>, // FIXME mandatory
+    pub select: Option<Box<SelectTable<'i>>>, // FIXME mandatory
     /// `JOIN`ed tabled
-    pub joins: Option<Vec<JoinedSelectTable>>,
+    pub joins: Option<Vec<JoinedSelectTable<'i>>>,
     op: Option<JoinOperator>, // FIXME transient
 }
-impl FromClause {
+impl<'i> FromClause<'i> {
     pub(crate) fn empty() -> Self {
         Self {
             select: None,
@@ -772,8 +966,8 @@ impl FromClause {
 
     pub(crate) fn push(
         &mut self,
-        table: SelectTable,
-        jc: Option<JoinConstraint>,
+        table: SelectTable<'i>,
+        jc: Option<JoinConstraint<'i>>,
     ) -> Result<(), ParserError> {
         let op = self.op.take();
         if let Some(op) = op {
@@ -820,48 +1014,49 @@ pub enum Distinctness {
 /// `SELECT` or `RETURNING` result column
 // https://sqlite.org/syntax/result-column.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum ResultColumn {
+pub enum ResultColumn<'i> {
     /// expression
-    Expr(Expr, Option<As>),
+    Expr(Expr<'i>, Option<As<'i>>),
     /// `*`
     Star,
     /// table name.`*`
-    TableStar(Name),
+    TableStar(Name<'i>),
 }
 
 /// Alias
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum As {
+pub enum As<'i> {
     /// `AS`
-    As(Name),
+    As(Name<'i>),
     /// no `AS`
-    Elided(Name), // FIXME Ids
+    Elided(Name<'i>), // FIXME Ids
 }
 
 /// `JOIN` clause
 // https://sqlite.org/syntax/join-clause.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct JoinedSelectTable {
+pub struct JoinedSelectTable<'i> {
     /// operator
     pub operator: JoinOperator,
     /// table
-    pub table: SelectTable,
+    pub table: SelectTable<'i>,
     /// constraint
-    pub constraint: Option<JoinConstraint>,
+    pub constraint: Option<JoinConstraint<'i>>,
 }
 
 /// Table or subquery
 // https://sqlite.org/syntax/table-or-subquery.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum SelectTable {
+#[allow(clippy::large_enum_variant)]
+pub enum SelectTable<'i> {
     /// table
-    Table(QualifiedName, Option<As>, Option<Indexed>),
+    Table(QualifiedName<'i>, Option<As<'i>>, Option<Indexed<'i>>),
     /// table function call
-    TableCall(QualifiedName, Option<Vec<Expr>>, Option<As>),
+    TableCall(QualifiedName<'i>, Option<Vec<Expr<'i>>>, Option<As<'i>>),
     /// `SELECT` subquery
-    Select(Select, Option<As>),
+    Select(Select<'i>, Option<As<'i>>),
     /// subquery
-    Sub(FromClause, Option<As>),
+    Sub(FromClause<'i>, Option<As<'i>>),
 }
 
 /// Join operators
@@ -877,13 +1072,13 @@ pub enum JoinOperator {
 impl JoinOperator {
     pub(crate) fn from(
         token: Token,
-        n1: Option<Name>,
-        n2: Option<Name>,
+        n1: Option<Name<'_>>,
+        n2: Option<Name<'_>>,
     ) -> Result<Self, ParserError> {
         Ok({
             let mut jt = JoinType::try_from(token.1)?;
             for n in [&n1, &n2].into_iter().flatten() {
-                jt |= JoinType::try_from(n.0.as_ref())?;
+                jt |= JoinType::try_from(n.0.as_bytes())?;
             }
             if (jt & (JoinType::INNER | JoinType::OUTER)) == (JoinType::INNER | JoinType::OUTER)
                 || (jt & (JoinType::OUTER | JoinType::LEFT | JoinType::RIGHT)) == JoinType::OUTER
@@ -954,80 +1149,155 @@ impl TryFrom<&[u8]> for JoinType {
 
 /// `JOIN` constraint
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum JoinConstraint {
+pub enum JoinConstraint<'i> {
     /// `ON`
-    On(Expr),
+    On(Expr<'i>),
     /// `USING`: col names
-    Using(DistinctNames),
+    Using(DistinctNames<'i>),
 }
 
 /// `GROUP BY`
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct GroupBy {
+pub struct GroupBy<'i> {
     /// expressions
-    pub exprs: Vec<Expr>,
+    pub exprs: Vec<Expr<'i>>,
     /// `HAVING`
-    pub having: Option<Expr>, // HAVING clause on a non-aggregate query
+    pub having: Option<Expr<'i>>, // HAVING clause on a non-aggregate query
 }
 
 /// identifier or one of several keywords or `INDEXED`
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Id(pub String);
+pub struct Id<'i>(pub Cow<'i, str>, pub Span);
 
-impl Id {
+impl<'i> Id<'i> {
     /// Constructor
-    pub fn from_token(ty: YYCODETYPE, token: Token) -> Self {
-        Self(from_token(ty, token))
+    pub fn from_token(ty: YYCODETYPE, token: Token<'i>) -> Self {
+        Self(from_token(ty, token), Span::from(&token))
     }
 }
 
 // TODO ids (identifier or string)
 
 /// identifier or string or `CROSS` or `FULL` or `INNER` or `LEFT` or `NATURAL` or `OUTER` or `RIGHT`.
+///
+/// The name is kept as written, with its quotes if any: see
+/// [`quote_style`](Name::quote_style) and [`unquoted`](Name::unquoted).
 #[derive(Clone, Debug, Eq)]
-pub struct Name(pub String); // TODO distinction between Name and "Name"/[Name]/`Name`
+pub struct Name<'i>(pub Cow<'i, str>, pub Span);
+
+/// How a [`Name`] is quoted
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum QuoteStyle {
+    /// Not quoted: `x`
+    None,
+    /// `"x"`
+    Double,
+    /// `'x'`
+    Single,
+    /// `` `x` ``
+    Backtick,
+    /// `[x]`
+    Bracket,
+}
+
+impl QuoteStyle {
+    /// Opening quote character
+    fn quote(self) -> Option<u8> {
+        match self {
+            Self::None => None,
+            Self::Double => Some(b'"'),
+            Self::Single => Some(b'\''),
+            Self::Backtick => Some(b'`'),
+            Self::Bracket => Some(b'['),
+        }
+    }
+}
 
-impl Name {
+impl<'i> Name<'i> {
+    /// Constructor, without location
+    pub fn new(name: impl Into<Cow<'i, str>>) -> Self {
+        Self(name.into(), Span::default())
+    }
     /// Constructor
-    pub fn from_token(ty: YYCODETYPE, token: Token) -> Self {
-        Self(from_token(ty, token))
+    pub fn from_token(ty: YYCODETYPE, token: Token<'i>) -> Self {
+        Self(from_token(ty, token), Span::from(&token))
     }
-
-    fn as_bytes(&self) -> QuotedIterator<'_> {
-        if self.0.is_empty() {
-            return QuotedIterator(self.0.bytes(), 0);
+    /// How this name is quoted.
+    /// A name which is not correctly quoted (like `"x`) is taken as is:
+    /// [`QuoteStyle::None`].
+    pub fn quote_style(&self) -> QuoteStyle {
+        let style = match self.0.as_bytes().first() {
+            Some(b'"') => QuoteStyle::Double,
+            Some(b'\'') => QuoteStyle::Single,
+            Some(b'`') => QuoteStyle::Backtick,
+            Some(b'[') => QuoteStyle::Bracket,
+            _ => return QuoteStyle::None,
+        };
+        match style.quote() {
+            Some(quote) if value::is_quoted(&self.0, quote) => style,
+            _ => QuoteStyle::None,
         }
-        let bytes = self.0.as_bytes();
-        let mut quote = bytes[0];
-        if quote != b'"' && quote != b'`' && quote != b'\'' && quote != b'[' {
-            return QuotedIterator(self.0.bytes(), 0);
-        } else if quote == b'[' {
-            quote = b']';
+    }
+    /// Name without its quotes, with doubled quotes unescaped
+    pub fn unquoted(&self) -> Cow<'_, str> {
+        self.quote_style()
+            .quote()
+            .and_then(|quote| value::unquote(&self.0, quote))
+            .unwrap_or(Cow::Borrowed(&self.0))
+    }
+    /// Name without its quotes and in lower case, like SQLite compares
+    /// names: only ASCII letters are folded.
+    pub fn normalized(&self) -> Cow<'_, str> {
+        let name = self.unquoted();
+        if name.bytes().any(|b| b.is_ascii_uppercase()) {
+            Cow::Owned(name.to_ascii_lowercase())
+        } else {
+            name
         }
-        debug_assert!(bytes.len() > 1);
-        debug_assert_eq!(quote, bytes[bytes.len() - 1]);
-        let sub = &self.0.as_str()[1..bytes.len() - 1];
-        if quote == b']' {
-            return QuotedIterator(sub.bytes(), 0); // no escape
+    }
+    /// Name unquoted when it is a valid identifier which is not a keyword,
+    /// else double-quoted.
+    ///
+    /// ```rust
+    /// use sqlite3_parser::ast::Name;
+    ///
+    /// assert_eq!(Name::new("[x]").quote_if_needed(), "x");
+    /// assert_eq!(Name::new("`order`").quote_if_needed(), "\"order\"");
+    /// assert_eq!(Name::new("'a \"b\"'").quote_if_needed(), "\"a \"\"b\"\"\"");
+    /// ```
+    pub fn quote_if_needed(&self) -> Cow<'_, str> {
+        let name = self.unquoted();
+        if is_identifier(&name) && keyword_token(name.as_bytes()).is_none() {
+            return name;
+        }
+        Cow::Owned(format!("\"{}\"", name.replace('"', "\"\"")))
+    }
+
+    fn as_bytes(&self) -> QuotedIterator<'_> {
+        match self.quote_style() {
+            QuoteStyle::None => QuotedIterator(self.0.bytes(), 0),
+            style => {
+                let sub = &self.0[1..self.0.len() - 1];
+                match style {
+                    QuoteStyle::Bracket => QuotedIterator(sub.bytes(), 0), // no escape
+                    _ => QuotedIterator(sub.bytes(), self.0.as_bytes()[0]),
+                }
+            }
         }
-        QuotedIterator(sub.bytes(), quote)
     }
 }
 
+/// Bytes of a quoted name, with doubled quotes unescaped
 struct QuotedIterator<'s>(Bytes<'s>, u8);
 impl Iterator for QuotedIterator<'_> {
     type Item = u8;
 
     fn next(&mut self) -> Option<u8> {
-        match self.0.next() {
-            x @ Some(b) => {
-                if b == self.1 && self.0.next() != Some(self.1) {
-                    panic!("Malformed string literal: {:?}", self.0);
-                }
-                x
-            }
-            x => x,
+        let b = self.0.next()?;
+        if self.1 != 0 && b == self.1 {
+            self.0.next(); // escaped quote
         }
+        Some(b)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -1054,26 +1324,26 @@ fn eq_ignore_case_and_quote(mut it: QuotedIterator<'_>, mut other: QuotedIterato
 }
 
 /// Ignore case and quote
-impl std::hash::Hash for Name {
+impl std::hash::Hash for Name<'_> {
     fn hash<H: std::hash::Hasher>(&self, hasher: &mut H) {
         self.as_bytes()
             .for_each(|b| hasher.write_u8(b.to_ascii_lowercase()));
     }
 }
 /// Ignore case and quote
-impl PartialEq for Name {
+impl PartialEq for Name<'_> {
     fn eq(&self, other: &Self) -> bool {
         eq_ignore_case_and_quote(self.as_bytes(), other.as_bytes())
     }
 }
 /// Ignore case and quote
-impl PartialEq<str> for Name {
+impl PartialEq<str> for Name<'_> {
     fn eq(&self, other: &str) -> bool {
         eq_ignore_case_and_quote(self.as_bytes(), QuotedIterator(other.bytes(), 0u8))
     }
 }
 /// Ignore case and quote
-impl PartialEq<&str> for Name {
+impl PartialEq<&str> for Name<'_> {
     fn eq(&self, other: &&str) -> bool {
         eq_ignore_case_and_quote(self.as_bytes(), QuotedIterator(other.bytes(), 0u8))
     }
@@ -1081,18 +1351,24 @@ impl PartialEq<&str> for Name {
 
 /// Qualified name
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct QualifiedName {
+pub struct QualifiedName<'i> {
     /// schema
-    pub db_name: Option<Name>,
+    pub db_name: Option<Name<'i>>,
     /// object name
-    pub name: Name,
+    pub name: Name<'i>,
     /// alias
-    pub alias: Option<Name>, // FIXME restrict alias usage (fullname vs xfullname)
+    pub alias: Option<Name<'i>>, // FIXME restrict alias usage (fullname vs xfullname)
 }
 
-impl QualifiedName {
+impl<'i> QualifiedName<'i> {
+    /// Location, from the schema name to the alias
+    pub fn span(&self) -> Span {
+        let start = self.db_name.as_ref().unwrap_or(&self.name).1;
+        let end = self.alias.as_ref().unwrap_or(&self.name).1;
+        start.to(end)
+    }
     /// Constructor
-    pub fn single(name: Name) -> Self {
+    pub fn single(name: Name<'i>) -> Self {
         Self {
             db_name: None,
             name,
@@ -1100,7 +1376,7 @@ impl QualifiedName {
         }
     }
     /// Constructor
-    pub fn fullname(db_name: Name, name: Name) -> Self {
+    pub fn fullname(db_name: Name<'i>, name: Name<'i>) -> Self {
         Self {
             db_name: Some(db_name),
             name,
@@ -1108,7 +1384,7 @@ impl QualifiedName {
         }
     }
     /// Constructor
-    pub fn xfullname(db_name: Name, name: Name, alias: Name) -> Self {
+    pub fn xfullname(db_name: Name<'i>, name: Name<'i>, alias: Name<'i>) -> Self {
         Self {
             db_name: Some(db_name),
             name,
@@ -1116,7 +1392,7 @@ impl QualifiedName {
         }
     }
     /// Constructor
-    pub fn alias(name: Name, alias: Name) -> Self {
+    pub fn alias(name: Name<'i>, alias: Name<'i>) -> Self {
         Self {
             db_name: None,
             name,
@@ -1127,34 +1403,38 @@ impl QualifiedName {
 
 /// Ordered set of distinct column names
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct DistinctNames(IndexSet<Name>);
+pub struct DistinctNames<'i>(IndexSet<Name<'i>>);
 
-impl DistinctNames {
+impl<'i> DistinctNames<'i> {
     /// Initialize
-    pub fn new(name: Name) -> Self {
+    pub fn new(name: Name<'i>) -> Self {
         let mut dn = Self(IndexSet::new());
         dn.0.insert(name);
         dn
     }
     /// Single column name
-    pub fn single(name: Name) -> Self {
+    pub fn single(name: Name<'i>) -> Self {
         let mut dn = Self(IndexSet::with_capacity(1));
         dn.0.insert(name);
         dn
     }
     /// Push a distinct name or fail
-    pub fn insert(&mut self, name: Name) -> Result<(), ParserError> {
-        if self.0.contains(&name) {
-            return Err(custom_err!("column \"{}\" specified more than once", name));
+    pub fn insert(&mut self, name: Name<'i>) -> Result<(), ParserError> {
+        if let Some(previous) = self.0.get(&name) {
+            return Err(ParserError::Duplicate {
+                message: format!("column \"{name}\" specified more than once"),
+                span: name.1,
+                previous: previous.1,
+            });
         }
         self.0.insert(name);
         Ok(())
     }
 }
-impl Deref for DistinctNames {
-    type Target = IndexSet<Name>;
+impl<'i> Deref for DistinctNames<'i> {
+    type Target = IndexSet<Name<'i>>;
 
-    fn deref(&self) -> &IndexSet<Name> {
+    fn deref(&self) -> &IndexSet<Name<'i>> {
         &self.0
     }
 }
@@ -1162,45 +1442,45 @@ impl Deref for DistinctNames {
 /// `ALTER TABLE` body
 // https://sqlite.org/lang_altertable.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum AlterTableBody {
+pub enum AlterTableBody<'i> {
     /// `RENAME TO`: new table name
-    RenameTo(Name),
+    RenameTo(Name<'i>),
     /// `ADD COLUMN`
-    AddColumn(ColumnDefinition), // TODO distinction between ADD and ADD COLUMN
+    AddColumn(ColumnDefinition<'i>), // TODO distinction between ADD and ADD COLUMN
     /// `RENAME COLUMN`
     RenameColumn {
         /// old name
-        old: Name,
+        old: Name<'i>,
         /// new name
-        new: Name,
+        new: Name<'i>,
     },
     /// `DROP COLUMN`
-    DropColumn(Name), // TODO distinction between DROP and DROP COLUMN
+    DropColumn(Name<'i>), // TODO distinction between DROP and DROP COLUMN
 }
 
 /// `CREATE TABLE` body
 // https://sqlite.org/lang_createtable.html
 // https://sqlite.org/syntax/create-table-stmt.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum CreateTableBody {
+pub enum CreateTableBody<'i> {
     /// columns and constraints
     ColumnsAndConstraints {
         /// table column definitions
-        columns: IndexMap<Name, ColumnDefinition>,
+        columns: IndexMap<Name<'i>, ColumnDefinition<'i>>,
         /// table constraints
-        constraints: Option<Vec<NamedTableConstraint>>,
+        constraints: Option<Vec<NamedTableConstraint<'i>>>,
         /// table options
         options: TableOptions,
     },
     /// `AS` select
-    AsSelect(Box<Select>),
+    AsSelect(Box<Select<'i>>),
 }
 
-impl CreateTableBody {
+impl<'i> CreateTableBody<'i> {
     /// Constructor
     pub fn columns_and_constraints(
-        columns: IndexMap<Name, ColumnDefinition>,
-        constraints: Option<Vec<NamedTableConstraint>>,
+        columns: IndexMap<Name<'i>, ColumnDefinition<'i>>,
+        constraints: Option<Vec<NamedTableConstraint<'i>>>,
         options: TableOptions,
     ) -> Result<Self, ParserError> {
         Ok(Self::ColumnsAndConstraints {
@@ -1214,22 +1494,29 @@ impl CreateTableBody {
 /// Table column definition
 // https://sqlite.org/syntax/column-def.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct ColumnDefinition {
+pub struct ColumnDefinition<'i> {
     /// column name
-    pub col_name: Name,
+    pub col_name: Name<'i>,
     /// column type
-    pub col_type: Option<Type>,
+    pub col_type: Option<Type<'i>>,
     /// column constraints
-    pub constraints: Vec<NamedColumnConstraint>,
+    pub constraints: Vec<NamedColumnConstraint<'i>>,
 }
 
-impl ColumnDefinition {
+impl<'i> ColumnDefinition<'i> {
     /// Constructor
-    pub fn add_column(columns: &mut IndexMap<Name, Self>, mut cd: Self) -> Result<(), ParserError> {
+    pub fn add_column(
+        columns: &mut IndexMap<Name<'i>, Self>,
+        mut cd: Self,
+    ) -> Result<(), ParserError> {
         let col_name = &cd.col_name;
-        if columns.contains_key(col_name) {
+        if let Some((previous, _)) = columns.get_key_value(col_name) {
             // TODO unquote
-            return Err(custom_err!("duplicate column name: {}", col_name));
+            return Err(ParserError::Duplicate {
+                message: format!("duplicate column name: {col_name}"),
+                span: col_name.1,
+                previous: previous.1,
+            });
         }
         // https://github.com/sqlite/sqlite/blob/e452bf40a14aca57fd9047b330dff282f3e4bbcc/src/build.c#L1511-L1514
         if let Some(ref mut col_type) = cd.col_type {
@@ -1255,7 +1542,7 @@ impl ColumnDefinition {
             if truncate {
                 // str_split_whitespace_remainder
                 let new_type: Vec<&str> = split.collect();
-                col_type.name = new_type.join(" ");
+                col_type.name = Cow::Owned(new_type.join(" "));
             }
         }
         for constraint in &cd.constraints {
@@ -1285,17 +1572,17 @@ impl ColumnDefinition {
 /// Named column constraint
 // https://sqlite.org/syntax/column-constraint.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct NamedColumnConstraint {
+pub struct NamedColumnConstraint<'i> {
     /// constraint name
-    pub name: Option<Name>,
+    pub name: Option<Name<'i>>,
     /// constraint
-    pub constraint: ColumnConstraint,
+    pub constraint: ColumnConstraint<'i>,
 }
 
 /// Column constraint
 // https://sqlite.org/syntax/column-constraint.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum ColumnConstraint {
+pub enum ColumnConstraint<'i> {
     /// `PRIMARY KEY`
     PrimaryKey {
         /// `ASC` / `DESC`
@@ -1315,50 +1602,50 @@ pub enum ColumnConstraint {
     /// `UNIQUE`
     Unique(Option<ResolveType>),
     /// `CHECK`
-    Check(Expr),
+    Check(Expr<'i>),
     /// `DEFAULT`
-    Default(Expr),
+    Default(Expr<'i>),
     /// `DEFERRABLE`
     Defer(DeferSubclause), // FIXME
     /// `COLLATE`
     Collate {
         /// collation name
-        collation_name: Name, // FIXME Ids
+        collation_name: Name<'i>, // FIXME Ids
     },
     /// `REFERENCES` foreign-key clause
     ForeignKey {
         /// clause
-        clause: ForeignKeyClause,
+        clause: ForeignKeyClause<'i>,
         /// `DEFERRABLE`
         deref_clause: Option<DeferSubclause>,
     },
     /// `GENERATED`
     Generated {
         /// expression
-        expr: Expr,
+        expr: Expr<'i>,
         /// `STORED` / `VIRTUAL`
-        typ: Option<Id>,
+        typ: Option<Id<'i>>,
     },
 }
 
 /// Named table constraint
 // https://sqlite.org/syntax/table-constraint.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct NamedTableConstraint {
+pub struct NamedTableConstraint<'i> {
     /// constraint name
-    pub name: Option<Name>,
+    pub name: Option<Name<'i>>,
     /// constraint
-    pub constraint: TableConstraint,
+    pub constraint: TableConstraint<'i>,
 }
 
 /// Table constraint
 // https://sqlite.org/syntax/table-constraint.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum TableConstraint {
+pub enum TableConstraint<'i> {
     /// `PRIMARY KEY`
     PrimaryKey {
         /// columns
-        columns: Vec<SortedColumn>,
+        columns: Vec<SortedColumn<'i>>,
         /// `AUTOINCREMENT`
         auto_increment: bool,
         /// `ON CONFLICT` clause
@@ -1367,18 +1654,18 @@ pub enum TableConstraint {
     /// `UNIQUE`
     Unique {
         /// columns
-        columns: Vec<SortedColumn>,
+        columns: Vec<SortedColumn<'i>>,
         /// `ON CONFLICT` clause
         conflict_clause: Option<ResolveType>,
     },
     /// `CHECK`
-    Check(Expr),
+    Check(Expr<'i>),
     /// `FOREIGN KEY`
     ForeignKey {
         /// columns
-        columns: Vec<IndexedColumn>,
+        columns: Vec<IndexedColumn<'i>>,
         /// `REFERENCES`
-        clause: ForeignKeyClause,
+        clause: ForeignKeyClause<'i>,
         /// `DEFERRABLE`
         deref_clause: Option<DeferSubclause>,
     },
@@ -1418,18 +1705,18 @@ pub enum NullsOrder {
 /// `REFERENCES` clause
 // https://sqlite.org/syntax/foreign-key-clause.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct ForeignKeyClause {
+pub struct ForeignKeyClause<'i> {
     /// foreign table name
-    pub tbl_name: Name,
+    pub tbl_name: Name<'i>,
     /// foreign table columns
-    pub columns: Option<Vec<IndexedColumn>>,
+    pub columns: Option<Vec<IndexedColumn<'i>>>,
     /// referential action(s) / deferrable option(s)
-    pub args: Vec<RefArg>,
+    pub args: Vec<RefArg<'i>>,
 }
 
 /// foreign-key reference args
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum RefArg {
+pub enum RefArg<'i> {
     /// `ON DELETE`
     OnDelete(RefAct),
     /// `ON INSERT`
@@ -1437,7 +1724,7 @@ pub enum RefArg {
     /// `ON UPDATE`
     OnUpdate(RefAct),
     /// `MATCH`
-    Match(Name),
+    Match(Name<'i>),
 }
 
 /// foreign-key reference actions
@@ -1476,29 +1763,29 @@ pub enum InitDeferredPred {
 /// Indexed column
 // https://sqlite.org/syntax/indexed-column.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct IndexedColumn {
+pub struct IndexedColumn<'i> {
     /// column name
-    pub col_name: Name,
+    pub col_name: Name<'i>,
     /// `COLLATE`
-    pub collation_name: Option<Name>, // FIXME Ids
+    pub collation_name: Option<Name<'i>>, // FIXME Ids
     /// `ORDER BY`
     pub order: Option<SortOrder>,
 }
 
 /// `INDEXED BY` / `NOT INDEXED`
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Indexed {
+pub enum Indexed<'i> {
     /// `INDEXED BY`: idx name
-    IndexedBy(Name),
+    IndexedBy(Name<'i>),
     /// `NOT INDEXED`
     NotIndexed,
 }
 
 /// Sorted column
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct SortedColumn {
+pub struct SortedColumn<'i> {
     /// expression
-    pub expr: Expr,
+    pub expr: Expr<'i>,
     /// `ASC` / `DESC`
     pub order: Option<SortOrder>,
     /// `NULLS FIRST` / `NULLS LAST`
@@ -1507,46 +1794,47 @@ pub struct SortedColumn {
 
 /// `LIMIT`
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Limit {
+pub struct Limit<'i> {
     /// count
-    pub expr: Expr,
+    pub expr: Expr<'i>,
     /// `OFFSET`
-    pub offset: Option<Expr>, // TODO distinction between LIMIT offset, count and LIMIT count OFFSET offset
+    pub offset: Option<Expr<'i>>, // TODO distinction between LIMIT offset, count and LIMIT count OFFSET offset
 }
 
 /// `INSERT` body
 // https://sqlite.org/lang_insert.html
 // https://sqlite.org/syntax/insert-stmt.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum InsertBody {
+#[allow(clippy::large_enum_variant)]
+pub enum InsertBody<'i> {
     /// `SELECT` or `VALUES`
-    Select(Box<Select>, Option<Upsert>),
+    Select(Box<Select<'i>>, Option<Upsert<'i>>),
     /// `DEFAULT VALUES`
     DefaultValues,
 }
 
 /// `UPDATE ... SET`
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Set {
+pub struct Set<'i> {
     /// column name(s)
-    pub col_names: DistinctNames,
+    pub col_names: DistinctNames<'i>,
     /// expression
-    pub expr: Expr,
+    pub expr: Expr<'i>,
 }
 
 /// `PRAGMA` body
 // https://sqlite.org/syntax/pragma-stmt.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum PragmaBody {
+pub enum PragmaBody<'i> {
     /// `=`
-    Equals(PragmaValue),
+    Equals(PragmaValue<'i>),
     /// function call
-    Call(PragmaValue),
+    Call(PragmaValue<'i>),
 }
 
 /// `PRAGMA` value
 // https://sqlite.org/syntax/pragma-value.html
-pub type PragmaValue = Expr; // TODO
+pub type PragmaValue<'i> = Expr<'i>; // TODO
 
 /// `CREATE TRIGGER` time
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -1561,7 +1849,7 @@ pub enum TriggerTime {
 
 /// `CREATE TRIGGER` event
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum TriggerEvent {
+pub enum TriggerEvent<'i> {
     /// `DELETE`
     Delete,
     /// `INSERT`
@@ -1569,51 +1857,52 @@ pub enum TriggerEvent {
     /// `UPDATE`
     Update,
     /// `UPDATE OF`: col names
-    UpdateOf(DistinctNames),
+    UpdateOf(DistinctNames<'i>),
 }
 
 /// `CREATE TRIGGER` command
 // https://sqlite.org/lang_createtrigger.html
 // https://sqlite.org/syntax/create-trigger-stmt.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum TriggerCmd {
+#[allow(clippy::large_enum_variant)]
+pub enum TriggerCmd<'i> {
     /// `UPDATE`
     Update {
         /// `OR`
         or_conflict: Option<ResolveType>,
         /// table name
-        tbl_name: Name,
+        tbl_name: Name<'i>,
         /// `SET` assignments
-        sets: Vec<Set>,
+        sets: Vec<Set<'i>>,
         /// `FROM`
-        from: Option<FromClause>,
+        from: Option<FromClause<'i>>,
         /// `WHERE` clause
-        where_clause: Option<Expr>,
+        where_clause: Option<Expr<'i>>,
     },
     /// `INSERT`
     Insert {
         /// `OR`
         or_conflict: Option<ResolveType>,
         /// table name
-        tbl_name: Name,
+        tbl_name: Name<'i>,
         /// `COLUMNS`
-        col_names: Option<DistinctNames>,
+        col_names: Option<DistinctNames<'i>>,
         /// `SELECT` or `VALUES`
-        select: Select,
+        select: Select<'i>,
         /// `ON CONLICT` clause
-        upsert: Option<Upsert>,
+        upsert: Option<Upsert<'i>>,
         /// `RETURNING`
-        returning: Option<Vec<ResultColumn>>,
+        returning: Option<Vec<ResultColumn<'i>>>,
     },
     /// `DELETE`
     Delete {
         /// table name
-        tbl_name: Name,
+        tbl_name: Name<'i>,
         /// `WHERE` clause
-        where_clause: Option<Expr>,
+        where_clause: Option<Expr<'i>>,
     },
     /// `SELECT`
-    Select(Select),
+    Select(Select<'i>),
 }
 
 /// Conflict resolution types
@@ -1635,11 +1924,11 @@ pub enum ResolveType {
 // https://sqlite.org/lang_with.html
 // https://sqlite.org/syntax/with-clause.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct With {
+pub struct With<'i> {
     /// `RECURSIVE`
     pub recursive: bool,
     /// CTEs
-    pub ctes: Vec<CommonTableExpr>,
+    pub ctes: Vec<CommonTableExpr<'i>>,
 }
 
 /// CTE materialization
@@ -1656,22 +1945,26 @@ pub enum Materialized {
 /// CTE
 // https://sqlite.org/syntax/common-table-expression.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct CommonTableExpr {
+pub struct CommonTableExpr<'i> {
     /// table name
-    pub tbl_name: Name,
+    pub tbl_name: Name<'i>,
     /// table columns
-    pub columns: Option<Vec<IndexedColumn>>, // check no duplicate
+    pub columns: Option<Vec<IndexedColumn<'i>>>, // check no duplicate
     /// `MATERIALIZED`
     pub materialized: Materialized,
     /// query
-    pub select: Select,
+    pub select: Select<'i>,
 }
 
-impl CommonTableExpr {
+impl<'i> CommonTableExpr<'i> {
     /// Constructor
     pub fn add_cte(ctes: &mut Vec<Self>, cte: Self) -> Result<(), ParserError> {
-        if ctes.iter().any(|c| c.tbl_name == cte.tbl_name) {
-            return Err(custom_err!("duplicate WITH table name: {}", cte.tbl_name));
+        if let Some(previous) = ctes.iter().find(|c| c.tbl_name == cte.tbl_name) {
+            return Err(ParserError::Duplicate {
+                message: format!("duplicate WITH table name: {}", cte.tbl_name),
+                span: cte.tbl_name.1,
+                previous: previous.tbl_name.1,
+            });
         }
         ctes.push(cte);
         Ok(())
@@ -1681,21 +1974,21 @@ impl CommonTableExpr {
 /// Column type
 // https://sqlite.org/syntax/type-name.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Type {
+pub struct Type<'i> {
     /// type name
-    pub name: String, // TODO Validate: Ids+
+    pub name: Cow<'i, str>, // TODO Validate: Ids+
     /// type size
-    pub size: Option<TypeSize>,
+    pub size: Option<TypeSize<'i>>,
 }
 
 /// Column type size limit(s)
 // https://sqlite.org/syntax/type-name.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum TypeSize {
+pub enum TypeSize<'i> {
     /// maximum size
-    MaxSize(Box<Expr>),
+    MaxSize(Box<Expr<'i>>),
     /// precision
-    TypeSize(Box<Expr>, Box<Expr>),
+    TypeSize(Box<Expr<'i>>, Box<Expr<'i>>),
 }
 
 /// Transaction types
@@ -1713,33 +2006,33 @@ pub enum TransactionType {
 // https://sqlite.org/lang_upsert.html
 // https://sqlite.org/syntax/upsert-clause.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Upsert {
+pub struct Upsert<'i> {
     /// conflict targets
-    pub index: Option<UpsertIndex>,
+    pub index: Option<UpsertIndex<'i>>,
     /// `DO` clause
-    pub do_clause: UpsertDo,
+    pub do_clause: UpsertDo<'i>,
     /// next upsert
-    pub next: Option<Box<Upsert>>,
+    pub next: Option<Box<Upsert<'i>>>,
 }
 
 /// Upsert conflict targets
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct UpsertIndex {
+pub struct UpsertIndex<'i> {
     /// columns
-    pub targets: Vec<SortedColumn>,
+    pub targets: Vec<SortedColumn<'i>>,
     /// `WHERE` clause
-    pub where_clause: Option<Expr>,
+    pub where_clause: Option<Expr<'i>>,
 }
 
 /// Upsert `DO` action
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum UpsertDo {
+pub enum UpsertDo<'i> {
     /// `SET`
     Set {
         /// assignments
-        sets: Vec<Set>,
+        sets: Vec<Set<'i>>,
         /// `WHERE` clause
-        where_clause: Option<Expr>,
+        where_clause: Option<Expr<'i>>,
     },
     /// `NOTHING`
     Nothing,
@@ -1747,56 +2040,57 @@ pub enum UpsertDo {
 
 /// Function call tail
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct FunctionTail {
+pub struct FunctionTail<'i> {
     /// `FILTER` clause
-    pub filter_clause: Option<Box<Expr>>,
+    pub filter_clause: Option<Box<Expr<'i>>>,
     /// `OVER` clause
-    pub over_clause: Option<Box<Over>>,
+    pub over_clause: Option<Box<Over<'i>>>,
 }
 
 /// Function call `OVER` clause
 // https://sqlite.org/syntax/over-clause.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Over {
+#[allow(clippy::large_enum_variant)]
+pub enum Over<'i> {
     /// Window definition
-    Window(Window),
+    Window(Window<'i>),
     /// Window name
-    Name(Name),
+    Name(Name<'i>),
 }
 
 /// `OVER` window definition
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct WindowDef {
+pub struct WindowDef<'i> {
     /// window name
-    pub name: Name,
+    pub name: Name<'i>,
     /// window definition
-    pub window: Window,
+    pub window: Window<'i>,
 }
 
 /// Window definition
 // https://sqlite.org/syntax/window-defn.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Window {
+pub struct Window<'i> {
     /// base window name
-    pub base: Option<Name>,
+    pub base: Option<Name<'i>>,
     /// `PARTITION BY`
-    pub partition_by: Option<Vec<Expr>>,
+    pub partition_by: Option<Vec<Expr<'i>>>,
     /// `ORDER BY`
-    pub order_by: Option<Vec<SortedColumn>>,
+    pub order_by: Option<Vec<SortedColumn<'i>>>,
     /// frame spec
-    pub frame_clause: Option<FrameClause>,
+    pub frame_clause: Option<FrameClause<'i>>,
 }
 
 /// Frame specification
 // https://sqlite.org/syntax/frame-spec.html
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct FrameClause {
+pub struct FrameClause<'i> {
     /// unit
     pub mode: FrameMode,
     /// start bound
-    pub start: FrameBound,
+    pub start: FrameBound<'i>,
     /// end bound
-    pub end: Option<FrameBound>,
+    pub end: Option<FrameBound<'i>>,
     /// `EXCLUDE`
     pub exclude: Option<FrameExclude>,
 }
@@ -1814,13 +2108,13 @@ pub enum FrameMode {
 
 /// Frame bounds
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum FrameBound {
+pub enum FrameBound<'i> {
     /// `CURRENT ROW`
     CurrentRow,
     /// `FOLLOWING`
-    Following(Expr),
+    Following(Expr<'i>),
     /// `PRECEDING`
-    Preceding(Expr),
+    Preceding(Expr<'i>),
     /// `UNBOUNDED FOLLOWING`
     UnboundedFollowing,
     /// `UNBOUNDED PRECEDING`
@@ -1842,7 +2136,8 @@ pub enum FrameExclude {
 
 #[cfg(test)]
 mod test {
-    use super::Name;
+    use super::{Literal, Name, QuoteStyle};
+    use std::borrow::Cow;
 
     #[test]
     fn test_dequote() {
@@ -1854,7 +2149,134 @@ mod test {
         assert_eq!(name("[x]"), "x");
     }
 
-    fn name(s: &'static str) -> Name {
-        Name(s.to_owned())
+    #[test]
+    fn name_quote_style() {
+        assert_eq!(name("x").quote_style(), QuoteStyle::None);
+        assert_eq!(name(r#""x""#).quote_style(), QuoteStyle::Double);
+        assert_eq!(name("'x'").quote_style(), QuoteStyle::Single);
+        assert_eq!(name("`x`").quote_style(), QuoteStyle::Backtick);
+        assert_eq!(name("[x]").quote_style(), QuoteStyle::Bracket);
+        // malformed
+        for malformed in [r#"""#, r#""x"#, r#""x"y""#, "[x]]", "`x", ""] {
+            let n = name(malformed);
+            assert_eq!(n.quote_style(), QuoteStyle::None, "{malformed}");
+            assert_eq!(n.unquoted(), malformed);
+            assert_eq!(n, name(malformed));
+        }
+        assert_ne!(name(r#""x"#), name("x"));
+    }
+
+    #[test]
+    fn name_unquoted_and_normalized() {
+        assert_eq!(name(r#""Foo""Bar""#).unquoted(), r#"Foo"Bar"#);
+        assert_eq!(name("'it''s'").unquoted(), "it's");
+        assert_eq!(name("[a\"b]").unquoted(), "a\"b");
+        assert_eq!(name(r#""Foo""Bar""#).normalized(), r#"foo"bar"#);
+        assert_eq!(name("ÉtÉ").normalized(), "ÉtÉ");
+        assert_eq!(name("[MixedCase]").normalized(), "mixedcase");
+        assert!(matches!(name("lower").normalized(), Cow::Borrowed("lower")));
+    }
+
+    #[test]
+    fn name_quote_if_needed() {
+        assert_eq!(name("x").quote_if_needed(), "x");
+        assert_eq!(name("_x$1").quote_if_needed(), "_x$1");
+        assert_eq!(name(r#""x""#).quote_if_needed(), "x");
+        assert_eq!(name("select").quote_if_needed(), r#""select""#);
+        assert_eq!(name("[1x]").quote_if_needed(), r#""1x""#);
+        assert_eq!(name("`a b`").quote_if_needed(), r#""a b""#);
+        assert_eq!(name("'a\"b'").quote_if_needed(), r#""a""b""#);
+        assert_eq!(name("\"\"").quote_if_needed(), r#""""#);
+    }
+
+    #[test]
+    fn literal_values() {
+        fn value(l: Literal<'_>) -> Result<Option<String>, String> {
+            l.value()
+                .map(|v| v.map(|v| format!("{v:?}")))
+                .map_err(|e| e.to_string())
+        }
+        fn numeric(n: &str) -> Result<Option<String>, String> {
+            value(Literal::Numeric(n.into()))
+        }
+        fn negated(n: &str) -> Result<Option<String>, String> {
+            Literal::Numeric(n.into())
+                .negated_value()
+                .map(|v| v.map(|v| format!("{v:?}")))
+                .map_err(|e| e.to_string())
+        }
+        let ok = |s: &str| Ok(Some(s.to_owned()));
+        assert_eq!(numeric("0"), ok("Integer(0)"));
+        assert_eq!(numeric("007"), ok("Integer(7)"));
+        assert_eq!(numeric("1_000_000"), ok("Integer(1000000)"));
+        assert_eq!(
+            numeric("9223372036854775807"),
+            ok("Integer(9223372036854775807)")
+        );
+        assert_eq!(
+            numeric("9223372036854775808"),
+            ok("Real(9.223372036854776e18)")
+        );
+        assert_eq!(numeric("99999999999999999999999"), ok("Real(1e23)"));
+        assert_eq!(
+            negated("9223372036854775808"),
+            ok("Integer(-9223372036854775808)")
+        );
+        assert_eq!(
+            negated("9223372036854775809"),
+            ok("Real(-9.223372036854776e18)")
+        );
+        assert_eq!(numeric("0x0"), ok("Integer(0)"));
+        assert_eq!(numeric("0XfF"), ok("Integer(255)"));
+        assert_eq!(
+            numeric("0x00007FFFFFFFFFFFFFFF"),
+            ok("Integer(9223372036854775807)")
+        );
+        assert_eq!(
+            numeric("0x8000000000000000"),
+            ok("Integer(-9223372036854775808)")
+        );
+        assert_eq!(negated("0xFFFF_FFFF_FFFF_FFFF"), ok("Integer(1)"));
+        assert_eq!(
+            negated("0x8000000000000000"),
+            Err("hex literal too big: -0x8000000000000000".to_owned())
+        );
+        assert_eq!(
+            numeric("0x10000000000000000"),
+            Err("hex literal too big: 0x10000000000000000".to_owned())
+        );
+        assert_eq!(numeric("1.5"), ok("Real(1.5)"));
+        assert_eq!(numeric(".5e-1"), ok("Real(0.05)"));
+        assert_eq!(numeric("1."), ok("Real(1.0)"));
+        assert_eq!(numeric("1_0.2_5E1_0"), ok("Real(102500000000.0)"));
+        assert_eq!(numeric("1e400"), ok("Real(inf)"));
+        assert_eq!(negated("1e400"), ok("Real(-inf)"));
+        assert!(numeric("inf").is_err());
+        assert!(numeric("0x").is_err());
+        assert!(numeric("").is_err());
+
+        assert_eq!(value(Literal::String("''".into())), ok(r#"Text("")"#));
+        assert_eq!(
+            value(Literal::String("'a''b'''".into())),
+            ok(r#"Text("a'b'")"#)
+        );
+        assert_eq!(value(Literal::String("'é'".into())), ok(r#"Text("é")"#));
+        assert!(value(Literal::String("'a'b'".into())).is_err());
+        assert!(value(Literal::String("'a".into())).is_err());
+        assert!(value(Literal::String("a".into())).is_err());
+
+        assert_eq!(value(Literal::Blob("".into())), ok("Blob([])"));
+        assert_eq!(value(Literal::Blob("00fF".into())), ok("Blob([0, 255])"));
+        assert!(value(Literal::Blob("0".into())).is_err());
+        assert!(value(Literal::Blob("0g".into())).is_err());
+
+        assert_eq!(value(Literal::Null), ok("Null"));
+        assert_eq!(value(Literal::CurrentDate), Ok(None));
+        assert_eq!(value(Literal::Keyword("ON".into())), Ok(None));
+        assert_eq!(Literal::Null.negated_value(), Ok(None));
+    }
+
+    fn name(s: &'static str) -> Name<'static> {
+        Name::new(s)
     }
 }
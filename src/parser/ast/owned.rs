@@ -0,0 +1,590 @@
+//! Conversion of AST nodes borrowing the input into `'static` nodes
+use std::borrow::Cow;
+
+use indexmap::{IndexMap, IndexSet};
+
+use super::*;
+
+/// Detach an AST node from the input it borrows from
+pub trait IntoOwned {
+    /// `'static` version of `Self`
+    type Owned: 'static;
+    /// Copy borrowed strings to make the node `'static`
+    fn into_owned(self) -> Self::Owned;
+}
+
+impl IntoOwned for Cow<'_, str> {
+    type Owned = Cow<'static, str>;
+    fn into_owned(self) -> Cow<'static, str> {
+        Cow::Owned(Cow::into_owned(self))
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Option<T> {
+    type Owned = Option<T::Owned>;
+    fn into_owned(self) -> Self::Owned {
+        self.map(IntoOwned::into_owned)
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Box<T> {
+    type Owned = Box<T::Owned>;
+    fn into_owned(self) -> Self::Owned {
+        Box::new(IntoOwned::into_owned(*self))
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for Vec<T> {
+    type Owned = Vec<T::Owned>;
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter().map(IntoOwned::into_owned).collect()
+    }
+}
+
+impl<A: IntoOwned, B: IntoOwned> IntoOwned for (A, B) {
+    type Owned = (A::Owned, B::Owned);
+    fn into_owned(self) -> Self::Owned {
+        (IntoOwned::into_owned(self.0), IntoOwned::into_owned(self.1))
+    }
+}
+
+impl IntoOwned for IndexSet<Name<'_>> {
+    type Owned = IndexSet<Name<'static>>;
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter().map(IntoOwned::into_owned).collect()
+    }
+}
+
+impl IntoOwned for IndexMap<Name<'_>, ColumnDefinition<'_>> {
+    type Owned = IndexMap<Name<'static>, ColumnDefinition<'static>>;
+    fn into_owned(self) -> Self::Owned {
+        self.into_iter().map(IntoOwned::into_owned).collect()
+    }
+}
+
+/// Nodes without any reference to the input
+macro_rules! static_into_owned {
+    ($($ty:ty),+ $(,)?) => {
+        $(impl IntoOwned for $ty {
+            type Owned = Self;
+            fn into_owned(self) -> Self::Owned {
+                self
+            }
+        })+
+    };
+}
+
+static_into_owned!(
+    bool,
+    CompoundOperator,
+    DeferSubclause,
+    Distinctness,
+    FrameExclude,
+    FrameMode,
+    InitDeferredPred,
+    JoinOperator,
+    LikeOperator,
+    Materialized,
+    NullsOrder,
+    Operator,
+    RefAct,
+    ResolveType,
+    SortOrder,
+    Span,
+    TableOptions,
+    TransactionType,
+    TriggerTime,
+    UnaryOperator,
+);
+
+/// Structs: all fields are converted
+macro_rules! struct_into_owned {
+    ($name:ident { $($field:ident),+ $(,)? }) => {
+        impl IntoOwned for $name<'_> {
+            type Owned = $name<'static>;
+            fn into_owned(self) -> Self::Owned {
+                $name {
+                    $($field: IntoOwned::into_owned(self.$field),)+
+                }
+            }
+        }
+    };
+    ($name:ident($($field:tt),+)) => {
+        impl IntoOwned for $name<'_> {
+            type Owned = $name<'static>;
+            fn into_owned(self) -> Self::Owned {
+                $name($(IntoOwned::into_owned(self.$field)),+)
+            }
+        }
+    };
+}
+
+/// Enums: all variant fields are converted
+macro_rules! enum_into_owned {
+    ($name:ident {
+        $($variant:ident $(($($arg:ident),+))? $({ $($field:ident),+ })?),+ $(,)?
+    }) => {
+        impl IntoOwned for $name<'_> {
+            type Owned = $name<'static>;
+            fn into_owned(self) -> Self::Owned {
+                match self {
+                    $($name::$variant $(($($arg),+))? $({ $($field),+ })? => $name::$variant
+                        $(($(IntoOwned::into_owned($arg)),+))?
+                        $({ $($field: IntoOwned::into_owned($field)),+ })?,)+
+                }
+            }
+        }
+    };
+}
+
+enum_into_owned!(Cmd {
+    Explain(stmt, span),
+    ExplainQueryPlan(stmt, span),
+    Stmt(stmt, span),
+});
+enum_into_owned!(Stmt {
+    AlterTable(name, body),
+    Analyze(name),
+    Attach { expr, db_name, key },
+    Begin(tx_type, name),
+    Commit(name),
+    CreateIndex {
+        unique,
+        if_not_exists,
+        idx_name,
+        tbl_name,
+        columns,
+        where_clause
+    },
+    CreateTable {
+        temporary,
+        if_not_exists,
+        tbl_name,
+        body
+    },
+    CreateTrigger {
+        temporary,
+        if_not_exists,
+        trigger_name,
+        time,
+        event,
+        tbl_name,
+        for_each_row,
+        when_clause,
+        commands
+    },
+    CreateView {
+        temporary,
+        if_not_exists,
+        view_name,
+        columns,
+        select
+    },
+    CreateVirtualTable {
+        if_not_exists,
+        tbl_name,
+        module_name,
+        args
+    },
+    Delete {
+        with,
+        tbl_name,
+        indexed,
+        where_clause,
+        returning,
+        order_by,
+        limit
+    },
+    Detach(expr),
+    DropIndex { if_exists, idx_name },
+    DropTable { if_exists, tbl_name },
+    DropTrigger {
+        if_exists,
+        trigger_name
+    },
+    DropView { if_exists, view_name },
+    Insert {
+        with,
+        or_conflict,
+        tbl_name,
+        columns,
+        body,
+        returning
+    },
+    Pragma(name, body),
+    Reindex { obj_name },
+    Release(name),
+    Rollback {
+        tx_name,
+        savepoint_name
+    },
+    Savepoint(name),
+    Select(select),
+    Update {
+        with,
+        or_conflict,
+        tbl_name,
+        indexed,
+        sets,
+        from,
+        where_clause,
+        returning,
+        order_by,
+        limit
+    },
+    Vacuum(name, expr),
+});
+struct_into_owned!(Expr { kind, span });
+enum_into_owned!(ExprKind {
+    Between {
+        lhs,
+        not,
+        start,
+        end
+    },
+    Binary(lhs, op, rhs),
+    Case {
+        base,
+        when_then_pairs,
+        else_expr
+    },
+    Cast { expr, type_name },
+    Collate(expr, collation),
+    DoublyQualified(db_name, tbl_name, col_name),
+    Error,
+    Exists(select),
+    FunctionCall {
+        name,
+        distinctness,
+        args,
+        order_by,
+        filter_over
+    },
+    FunctionCallStar { name, filter_over },
+    Id(id),
+    InList { lhs, not, rhs },
+    InSelect { lhs, not, rhs },
+    InTable {
+        lhs,
+        not,
+        rhs,
+        args
+    },
+    IsNull(expr),
+    Like {
+        lhs,
+        not,
+        op,
+        rhs,
+        escape
+    },
+    Literal(literal),
+    Name(name),
+    NotNull(expr),
+    Parenthesized(exprs),
+    Qualified(tbl_name, col_name),
+    Raise(resolve_type, expr),
+    Subquery(select),
+    Unary(op, expr),
+    Variable(name),
+});
+
+impl IntoOwned for Literal<'_> {
+    type Owned = Literal<'static>;
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            Literal::Numeric(s) => Literal::Numeric(IntoOwned::into_owned(s)),
+            Literal::String(s) => Literal::String(IntoOwned::into_owned(s)),
+            Literal::Blob(s) => Literal::Blob(IntoOwned::into_owned(s)),
+            Literal::Keyword(s) => Literal::Keyword(IntoOwned::into_owned(s)),
+            Literal::Null => Literal::Null,
+            Literal::CurrentDate => Literal::CurrentDate,
+            Literal::CurrentTime => Literal::CurrentTime,
+            Literal::CurrentTimestamp => Literal::CurrentTimestamp,
+        }
+    }
+}
+
+struct_into_owned!(Select {
+    with,
+    body,
+    order_by,
+    limit
+});
+struct_into_owned!(SelectBody { select, compounds });
+struct_into_owned!(CompoundSelect { operator, select });
+enum_into_owned!(OneSelect {
+    Select {
+        distinctness,
+        columns,
+        from,
+        where_clause,
+        group_by,
+        window_clause
+    },
+    Values(values),
+});
+struct_into_owned!(FromClause { select, joins, op });
+impl IntoOwned for ResultColumn<'_> {
+    type Owned = ResultColumn<'static>;
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            ResultColumn::Expr(expr, alias) => {
+                ResultColumn::Expr(IntoOwned::into_owned(expr), IntoOwned::into_owned(alias))
+            }
+            ResultColumn::Star => ResultColumn::Star,
+            ResultColumn::TableStar(name) => ResultColumn::TableStar(IntoOwned::into_owned(name)),
+        }
+    }
+}
+enum_into_owned!(As {
+    As(name),
+    Elided(name),
+});
+struct_into_owned!(JoinedSelectTable {
+    operator,
+    table,
+    constraint
+});
+enum_into_owned!(SelectTable {
+    Table(name, alias, indexed),
+    TableCall(name, args, alias),
+    Select(select, alias),
+    Sub(from, alias),
+});
+enum_into_owned!(JoinConstraint {
+    On(expr),
+    Using(names),
+});
+struct_into_owned!(GroupBy { exprs, having });
+struct_into_owned!(Id(0, 1));
+struct_into_owned!(Name(0, 1));
+struct_into_owned!(QualifiedName {
+    db_name,
+    name,
+    alias
+});
+struct_into_owned!(DistinctNames(0));
+enum_into_owned!(AlterTableBody {
+    RenameTo(name),
+    AddColumn(column),
+    RenameColumn { old, new },
+    DropColumn(name),
+});
+enum_into_owned!(CreateTableBody {
+    ColumnsAndConstraints {
+        columns,
+        constraints,
+        options
+    },
+    AsSelect(select),
+});
+struct_into_owned!(ColumnDefinition {
+    col_name,
+    col_type,
+    constraints
+});
+struct_into_owned!(NamedColumnConstraint { name, constraint });
+impl IntoOwned for ColumnConstraint<'_> {
+    type Owned = ColumnConstraint<'static>;
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            ColumnConstraint::PrimaryKey {
+                order,
+                conflict_clause,
+                auto_increment,
+            } => ColumnConstraint::PrimaryKey {
+                order,
+                conflict_clause,
+                auto_increment,
+            },
+            ColumnConstraint::NotNull {
+                nullable,
+                conflict_clause,
+            } => ColumnConstraint::NotNull {
+                nullable,
+                conflict_clause,
+            },
+            ColumnConstraint::Unique(conflict_clause) => ColumnConstraint::Unique(conflict_clause),
+            ColumnConstraint::Check(expr) => ColumnConstraint::Check(IntoOwned::into_owned(expr)),
+            ColumnConstraint::Default(expr) => {
+                ColumnConstraint::Default(IntoOwned::into_owned(expr))
+            }
+            ColumnConstraint::Defer(defer) => ColumnConstraint::Defer(defer),
+            ColumnConstraint::Collate { collation_name } => ColumnConstraint::Collate {
+                collation_name: IntoOwned::into_owned(collation_name),
+            },
+            ColumnConstraint::ForeignKey {
+                clause,
+                deref_clause,
+            } => ColumnConstraint::ForeignKey {
+                clause: IntoOwned::into_owned(clause),
+                deref_clause,
+            },
+            ColumnConstraint::Generated { expr, typ } => ColumnConstraint::Generated {
+                expr: IntoOwned::into_owned(expr),
+                typ: IntoOwned::into_owned(typ),
+            },
+        }
+    }
+}
+struct_into_owned!(NamedTableConstraint { name, constraint });
+enum_into_owned!(TableConstraint {
+    PrimaryKey {
+        columns,
+        auto_increment,
+        conflict_clause
+    },
+    Unique {
+        columns,
+        conflict_clause
+    },
+    Check(expr),
+    ForeignKey {
+        columns,
+        clause,
+        deref_clause
+    },
+});
+struct_into_owned!(ForeignKeyClause {
+    tbl_name,
+    columns,
+    args
+});
+enum_into_owned!(RefArg {
+    OnDelete(act),
+    OnInsert(act),
+    OnUpdate(act),
+    Match(name),
+});
+struct_into_owned!(IndexedColumn {
+    col_name,
+    collation_name,
+    order
+});
+impl IntoOwned for Indexed<'_> {
+    type Owned = Indexed<'static>;
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            Indexed::IndexedBy(name) => Indexed::IndexedBy(IntoOwned::into_owned(name)),
+            Indexed::NotIndexed => Indexed::NotIndexed,
+        }
+    }
+}
+struct_into_owned!(SortedColumn { expr, order, nulls });
+struct_into_owned!(Limit { expr, offset });
+impl IntoOwned for InsertBody<'_> {
+    type Owned = InsertBody<'static>;
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            InsertBody::Select(select, upsert) => {
+                InsertBody::Select(IntoOwned::into_owned(select), IntoOwned::into_owned(upsert))
+            }
+            InsertBody::DefaultValues => InsertBody::DefaultValues,
+        }
+    }
+}
+struct_into_owned!(Set { col_names, expr });
+enum_into_owned!(PragmaBody {
+    Equals(value),
+    Call(value),
+});
+impl IntoOwned for TriggerEvent<'_> {
+    type Owned = TriggerEvent<'static>;
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            TriggerEvent::Delete => TriggerEvent::Delete,
+            TriggerEvent::Insert => TriggerEvent::Insert,
+            TriggerEvent::Update => TriggerEvent::Update,
+            TriggerEvent::UpdateOf(names) => TriggerEvent::UpdateOf(IntoOwned::into_owned(names)),
+        }
+    }
+}
+enum_into_owned!(TriggerCmd {
+    Update {
+        or_conflict,
+        tbl_name,
+        sets,
+        from,
+        where_clause
+    },
+    Insert {
+        or_conflict,
+        tbl_name,
+        col_names,
+        select,
+        upsert,
+        returning
+    },
+    Delete {
+        tbl_name,
+        where_clause
+    },
+    Select(select),
+});
+struct_into_owned!(With { recursive, ctes });
+struct_into_owned!(CommonTableExpr {
+    tbl_name,
+    columns,
+    materialized,
+    select
+});
+struct_into_owned!(Type { name, size });
+enum_into_owned!(TypeSize {
+    MaxSize(size),
+    TypeSize(precision, scale),
+});
+struct_into_owned!(Upsert {
+    index,
+    do_clause,
+    next
+});
+struct_into_owned!(UpsertIndex {
+    targets,
+    where_clause
+});
+impl IntoOwned for UpsertDo<'_> {
+    type Owned = UpsertDo<'static>;
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            UpsertDo::Set { sets, where_clause } => UpsertDo::Set {
+                sets: IntoOwned::into_owned(sets),
+                where_clause: IntoOwned::into_owned(where_clause),
+            },
+            UpsertDo::Nothing => UpsertDo::Nothing,
+        }
+    }
+}
+struct_into_owned!(FunctionTail {
+    filter_clause,
+    over_clause
+});
+enum_into_owned!(Over {
+    Window(window),
+    Name(name),
+});
+struct_into_owned!(WindowDef { name, window });
+struct_into_owned!(Window {
+    base,
+    partition_by,
+    order_by,
+    frame_clause
+});
+struct_into_owned!(FrameClause {
+    mode,
+    start,
+    end,
+    exclude
+});
+impl IntoOwned for FrameBound<'_> {
+    type Owned = FrameBound<'static>;
+    fn into_owned(self) -> Self::Owned {
+        match self {
+            FrameBound::CurrentRow => FrameBound::CurrentRow,
+            FrameBound::Following(expr) => FrameBound::Following(IntoOwned::into_owned(expr)),
+            FrameBound::Preceding(expr) => FrameBound::Preceding(IntoOwned::into_owned(expr)),
+            FrameBound::UnboundedFollowing => FrameBound::UnboundedFollowing,
+            FrameBound::UnboundedPreceding => FrameBound::UnboundedPreceding,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Decoded values of literals, with SQLite semantics
+use std::borrow::Cow;
+
+use super::Literal;
+use crate::custom_err;
+use crate::parser::ParserError;
+
+/// Value of a constant literal
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    /// `NULL`
+    Null,
+    /// 64-bit signed integer
+    Integer(i64),
+    /// 64-bit floating point number
+    Real(f64),
+    /// String, unescaped
+    Text(Cow<'a, str>),
+    /// BLOB, decoded
+    Blob(Vec<u8>),
+}
+
+impl Literal<'_> {
+    /// Decoded value, like SQLite computes it:
+    /// - an integer which does not fit in an `i64` becomes an `f64`,
+    ///   except an hexadecimal one which is an error (`hex literal too big`),
+    /// - a float is converted like `sqlite3AtoF` does,
+    /// - `_` digit separators are ignored,
+    /// - quotes are removed from a string and doubled quotes unescaped,
+    /// - a BLOB is decoded from hexadecimal.
+    ///
+    /// Return `None` for keywords and `CURRENT_*`, which are not constants.
+    ///
+    /// ```rust
+    /// use sqlite3_parser::ast::{Literal, Value};
+    ///
+    /// let n = Literal::Numeric("1_000".into());
+    /// assert_eq!(n.value().unwrap(), Some(Value::Integer(1000)));
+    /// let n = Literal::Numeric("0xFFFFFFFFFFFFFFFF".into());
+    /// assert_eq!(n.value().unwrap(), Some(Value::Integer(-1)));
+    /// let n = Literal::Numeric("9223372036854775808".into());
+    /// assert_eq!(n.value().unwrap(), Some(Value::Real(9223372036854775808.0)));
+    /// assert!(Literal::Numeric("0x1_0000_0000_0000_0000".into()).value().is_err());
+    ///
+    /// let s = Literal::String("'it''s'".into());
+    /// assert_eq!(s.value().unwrap(), Some(Value::Text("it's".into())));
+    /// let b = Literal::Blob("CAFE".into());
+    /// assert_eq!(b.value().unwrap(), Some(Value::Blob(vec![0xCA, 0xFE])));
+    /// ```
+    pub fn value(&self) -> Result<Option<Value<'_>>, ParserError> {
+        Ok(Some(match self {
+            Self::Numeric(n) => numeric(n, false)?,
+            Self::String(s) => match unquote(s, b'\'') {
+                Some(s) => Value::Text(s),
+                None => return Err(custom_err!("malformed string literal: {}", s)),
+            },
+            Self::Blob(b) => Value::Blob(blob(b)?),
+            Self::Null => Value::Null,
+            Self::Keyword(_) | Self::CurrentDate | Self::CurrentTime | Self::CurrentTimestamp => {
+                return Ok(None)
+            }
+        }))
+    }
+    /// Decoded value of the literal preceded by `-`, like
+    /// [`value`](Self::value): `-9223372036854775808` is `i64::MIN` while
+    /// `-0x8000000000000000` is too big.
+    ///
+    /// Return `None` for non-numeric literals.
+    pub fn negated_value(&self) -> Result<Option<Value<'_>>, ParserError> {
+        match self {
+            Self::Numeric(n) => numeric(n, true).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+// https://sqlite.org/src/file?name=src/expr.c: codeInteger, codeReal
+fn numeric(text: &str, negative: bool) -> Result<Value<'static>, ParserError> {
+    let digits = if text.contains('_') {
+        Cow::Owned(text.replace('_', ""))
+    } else {
+        Cow::Borrowed(text)
+    };
+    if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(custom_err!("malformed hex literal: {}", text));
+        }
+        let hex = hex.trim_start_matches('0');
+        return match u64::from_str_radix(if hex.is_empty() { "0" } else { hex }, 16) {
+            // the hexadecimal notation is used for bit patterns
+            Ok(n) if !(negative && n as i64 == i64::MIN) => {
+                let n = n as i64;
+                Ok(Value::Integer(if negative { n.wrapping_neg() } else { n }))
+            }
+            _ => Err(custom_err!(
+                "hex literal too big: {}{}",
+                if negative { "-" } else { "" },
+                text
+            )),
+        };
+    }
+    if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+        match digits.parse::<u64>() {
+            Ok(n) if n <= i64::MAX as u64 => {
+                let n = n as i64;
+                return Ok(Value::Integer(if negative { -n } else { n }));
+            }
+            Ok(n) if negative && n == i64::MIN.unsigned_abs() => {
+                return Ok(Value::Integer(i64::MIN));
+            }
+            _ => {} // too big: real
+        }
+    }
+    // `f64::from_str` also accepts `inf` or `NaN`
+    if digits
+        .bytes()
+        .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-'))
+    {
+        if let Ok(f) = digits.parse::<f64>() {
+            return Ok(Value::Real(if negative { -f } else { f }));
+        }
+    }
+    Err(custom_err!("malformed number: {}", text))
+}
+
+fn blob(hex: &str) -> Result<Vec<u8>, ParserError> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(custom_err!("malformed blob literal: X'{}'", hex));
+    }
+    Ok(hex
+        .as_bytes()
+        .chunks_exact(2)
+        .map(|pair| (hex_digit(pair[0]) << 4) | hex_digit(pair[1]))
+        .collect())
+}
+
+fn hex_digit(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        _ => b - b'A' + 10,
+    }
+}
+
+/// Whether `text` is enclosed by `quote`s (or brackets), with only doubled
+/// `quote`s inside (no `]` inside brackets).
+pub(crate) fn is_quoted(text: &str, quote: u8) -> bool {
+    let close = if quote == b'[' { b']' } else { quote };
+    let bytes = text.as_bytes();
+    if bytes.len() < 2 || bytes[0] != quote || bytes[bytes.len() - 1] != close {
+        return false;
+    }
+    let inner = &bytes[1..bytes.len() - 1];
+    if quote == b'[' {
+        return !inner.contains(&b']'); // no escape
+    }
+    let mut i = 0;
+    while i < inner.len() {
+        if inner[i] == quote {
+            if inner.get(i + 1) != Some(&quote) {
+                return false;
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// `text` without its enclosing `quote`s and with doubled `quote`s
+/// unescaped, or `None` when it is not correctly quoted.
+pub(crate) fn unquote(text: &str, quote: u8) -> Option<Cow<'_, str>> {
+    if !is_quoted(text, quote) {
+        return None;
+    }
+    let inner = &text[1..text.len() - 1];
+    let quote = quote as char;
+    Some(if quote != '[' && inner.contains(quote) {
+        Cow::Owned(inner.replace(&format!("{quote}{quote}"), &quote.to_string()))
+    } else {
+        Cow::Borrowed(inner)
+    })
+}
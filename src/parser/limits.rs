@@ -0,0 +1,52 @@
+//! Limits enforced while parsing, like SQLite's compile-time limits
+//! (<https://sqlite.org/limits.html>)
+
+/// Parse limits, with SQLite's defaults.
+///
+/// A statement exceeding one of these limits is rejected with the same
+/// message as SQLite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParserLimits {
+    /// Maximum length of a statement or fragment in bytes
+    /// (`SQLITE_MAX_SQL_LENGTH`)
+    pub max_sql_length: usize,
+    /// Maximum depth of an expression tree (`SQLITE_MAX_EXPR_DEPTH`).
+    ///
+    /// Unlike SQLite, parentheses and `SELECT`s (the statement itself
+    /// included) also add a level, so that nested subqueries are limited too.
+    /// So do each table of a `FROM` clause (parenthesized joins included)
+    /// and each `ON CONFLICT` clause.
+    ///
+    /// Deeper trees are rejected before being built, so that neither the
+    /// parser nor the recursive formatting or drop of the tree can overflow
+    /// the stack.
+    ///
+    /// Like in SQLite, `0` means no limit.
+    pub max_expr_depth: usize,
+    /// Maximum number of SELECTs in a compound SELECT
+    /// (`SQLITE_MAX_COMPOUND_SELECT`)
+    pub max_compound_select: usize,
+    /// Maximum number of columns of a table or result set, of assignments
+    /// in an `UPDATE` and of terms in an `ORDER BY` or `GROUP BY` clause
+    /// (`SQLITE_MAX_COLUMN`)
+    pub max_column: usize,
+    /// Maximum number of arguments of a function call
+    /// (`SQLITE_MAX_FUNCTION_ARG`)
+    pub max_function_arg: usize,
+    /// Maximum number of a `?NNN` variable and maximum number of variables
+    /// in a statement (`SQLITE_MAX_VARIABLE_NUMBER`)
+    pub max_variable_number: usize,
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        Self {
+            max_sql_length: 1_000_000_000,
+            max_expr_depth: 1000,
+            max_compound_select: 500,
+            max_column: 2000,
+            max_function_arg: 127,
+            max_variable_number: 32766,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! SQLite parser
 
 pub mod ast;
+mod limits;
 pub mod parse {
     #![expect(unused_braces)]
     #![expect(clippy::if_same_then_else)]
@@ -8,38 +9,156 @@ pub mod parse {
     #![expect(clippy::needless_return)]
     #![expect(clippy::upper_case_acronyms)]
     #![expect(clippy::manual_range_patterns)]
+    #![expect(clippy::large_enum_variant)]
 
     include!(concat!(env!("OUT_DIR"), "/parse.rs"));
 }
 
-use crate::dialect::Token;
-use ast::{Cmd, ExplainKind, Name, Stmt};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::dialect::{from_bytes, Token, TokenType};
+use ast::{
+    Cmd, ColumnDefinition, ExplainKind, Expr, Name, QualifiedName, SortedColumn, Span, Stmt, Type,
+};
+pub use limits::ParserLimits;
 
 /// Parser error
 #[derive(Debug, PartialEq)]
 pub enum ParserError {
     /// Syntax error
-    SyntaxError(String),
+    SyntaxError {
+        /// type of the offending token
+        token_type: TokenType,
+        /// text of the offending token
+        found: String,
+        /// tokens which would have been accepted instead
+        expected: Vec<TokenType>,
+    },
     /// Unexpected EOF
     UnexpectedEof,
     /// Custom error
     Custom(String),
+    /// Name defined more than once
+    Duplicate {
+        /// error message
+        message: String,
+        /// location of the duplicate name
+        span: Span,
+        /// location of the previous definition
+        previous: Span,
+    },
+    /// Double-quoted identifier which is not a column, while the
+    /// double-quoted string literals fallback is disabled
+    NoSuchColumn {
+        /// double-quoted identifier
+        name: String,
+        /// location of the identifier
+        span: Span,
+    },
+    /// Syntax newer than the target SQLite version
+    Unsupported {
+        /// feature, like `RETURNING`
+        feature: &'static str,
+        /// first SQLite version supporting the feature,
+        /// like `SQLITE_VERSION_NUMBER`
+        version: u32,
+        /// location of the offending node or token
+        span: Span,
+    },
 }
 
 impl std::fmt::Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Self::SyntaxError(s) => {
-                write!(f, "near \"{s}\": syntax error")
+            Self::SyntaxError {
+                found, expected, ..
+            } => {
+                write!(f, "near \"{found}\": syntax error")?;
+                if let [token_type] = expected.as_slice() {
+                    write!(f, ", expected {}", ExpectedToken(*token_type))
+                } else if let [first, others @ ..] = expected.as_slice() {
+                    write!(f, ", expected one of: {}", ExpectedToken(*first))?;
+                    for token_type in others {
+                        write!(f, ", {}", ExpectedToken(*token_type))?;
+                    }
+                    Ok(())
+                } else {
+                    Ok(())
+                }
             }
             Self::UnexpectedEof => f.write_str("unexpected end of input"),
-            Self::Custom(s) => f.write_str(s),
+            Self::Custom(s) | Self::Duplicate { message: s, .. } => f.write_str(s),
+            Self::NoSuchColumn { name, .. } => write!(
+                f,
+                "no such column: {name} - should this be a string literal in single-quotes?"
+            ),
+            Self::Unsupported {
+                feature, version, ..
+            } => write!(
+                f,
+                "{feature} requires SQLite {}.{}.{}",
+                version / 1_000_000,
+                version / 1000 % 1000,
+                version % 1000
+            ),
         }
     }
 }
 
 impl std::error::Error for ParserError {}
 
+/// Token type, as shown in the list of expected tokens
+struct ExpectedToken(TokenType);
+
+impl std::fmt::Display for ExpectedToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use TokenType::*;
+        match self.0 {
+            TK_EOF => f.write_str("end of input"),
+            TK_ID => f.write_str("identifier"),
+            TK_STRING => f.write_str("string"),
+            TK_INTEGER => f.write_str("integer"),
+            TK_FLOAT => f.write_str("float"),
+            TK_BLOB => f.write_str("blob"),
+            TK_VARIABLE => f.write_str("variable"),
+            TK_JOIN_KW => f.write_str("join operator"),
+            TK_CTIME_KW => f.write_str("CURRENT_TIME"),
+            TK_LIKE_KW => f.write_str("LIKE"),
+            TK_PTR => f.write_str("'->'"),
+            TK_ANY => f.write_str("any token"),
+            token_type => match token_type.as_str() {
+                Some(s) if s.starts_with(|c: char| c.is_ascii_alphabetic()) => f.write_str(s),
+                Some(s) => write!(f, "'{s}'"),
+                None => write!(f, "{token_type:?}"),
+            },
+        }
+    }
+}
+
+/// Expected terminals, without the fragment markers which are not part of
+/// the input
+pub(crate) fn expected_tokens(expected: Vec<parse::YYCODETYPE>) -> Vec<TokenType> {
+    use TokenType::{TK_FRAGMENT_EXPR, TK_FRAGMENT_QUALIFIED_NAME};
+    expected
+        .into_iter()
+        .filter_map(|t| TokenType::try_from(t).ok())
+        .filter(|t| !(TK_FRAGMENT_EXPR..=TK_FRAGMENT_QUALIFIED_NAME).contains(t))
+        .collect()
+}
+
+impl ParserError {
+    /// Location of the offending name, when it is not the current token
+    pub(crate) fn span(&self) -> Option<Span> {
+        match self {
+            Self::Duplicate { span, .. }
+            | Self::NoSuchColumn { span, .. }
+            | Self::Unsupported { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+}
+
 /// Custom error constructor
 #[macro_export]
 macro_rules! custom_err {
@@ -54,46 +173,111 @@ macro_rules! custom_err {
     };
 }
 
+/// Fragment parsed on its own, instead of a command
+pub enum Fragment<'input> {
+    /// Expression, from `Parser::parse_expr`
+    Expr(Expr<'input>),
+    /// Type name, from `Parser::parse_type`
+    Type(Type<'input>),
+    /// Column definition, from `Parser::parse_column_def`
+    ColumnDef(ColumnDefinition<'input>),
+    /// Sorted columns, from `Parser::parse_sorted_columns`
+    SortedColumns(Vec<SortedColumn<'input>>),
+    /// Qualified name, from `Parser::parse_qualified_name`
+    QualifiedName(QualifiedName<'input>),
+}
+
 /// Parser context
 pub struct Context<'input> {
     input: &'input [u8],
+    offset: usize, // of `input` in the whole text
     explain: Option<ExplainKind>,
-    stmt: Option<Stmt>,
-    constraint_name: Option<Name>,      // transient
+    stmt: Option<Stmt<'input>>,
+    fragment: Option<Fragment<'input>>,
+    span: Option<Span>, // from EXPLAIN to the end of the statement
+    constraint_name: Option<Name<'input>>, // transient
     module_arg: Option<(usize, usize)>, // Complete text of a module argument
-    module_args: Option<Vec<String>>,   // CREATE VIRTUAL TABLE args
+    module_args: Option<Vec<Cow<'input, str>>>, // CREATE VIRTUAL TABLE args
+    limits: ParserLimits,
+    target_version: u32, // like SQLITE_VERSION_NUMBER
+    variables: usize,    // number of the last variable
+    variable_names: HashSet<&'input [u8]>,
+    placeholders: Vec<usize>, // offsets of the identifiers inserted by the tolerant parser
     done: bool,
     error: Option<ParserError>,
 }
 
 impl<'input> Context<'input> {
-    pub fn new(input: &'input [u8]) -> Self {
+    pub fn new(input: &'input [u8], offset: usize) -> Self {
         Context {
             input,
+            offset,
             explain: None,
             stmt: None,
+            fragment: None,
+            span: None,
             constraint_name: None,
             module_arg: None,
             module_args: None,
+            limits: ParserLimits::default(),
+            target_version: u32::MAX,
+            variables: 0,
+            variable_names: HashSet::new(),
+            placeholders: Vec::new(),
             done: false,
             error: None,
         }
     }
 
+    /// Limits checked by the grammar actions
+    pub fn limits(&self) -> &ParserLimits {
+        &self.limits
+    }
+    pub fn set_limits(&mut self, limits: ParserLimits) {
+        self.limits = limits;
+    }
+    pub fn set_target_version(&mut self, version: u32) {
+        self.target_version = version;
+    }
+
+    /// Check that `feature`, at `span`, is supported by the target SQLite
+    /// version: it was introduced in `version`
+    pub fn require(
+        &self,
+        feature: &'static str,
+        version: u32,
+        span: Span,
+    ) -> Result<(), ParserError> {
+        if self.target_version < version {
+            return Err(ParserError::Unsupported {
+                feature,
+                version,
+                span,
+            });
+        }
+        Ok(())
+    }
+
     /// Consume parsed command
-    pub fn cmd(&mut self) -> Option<Cmd> {
+    pub fn cmd(&mut self) -> Option<Cmd<'input>> {
         if let Some(stmt) = self.stmt.take() {
+            let span = self.span.take().unwrap_or_default();
             match self.explain.take() {
-                Some(ExplainKind::Explain) => Some(Cmd::Explain(stmt)),
-                Some(ExplainKind::QueryPlan) => Some(Cmd::ExplainQueryPlan(stmt)),
-                None => Some(Cmd::Stmt(stmt)),
+                Some(ExplainKind::Explain) => Some(Cmd::Explain(stmt, span)),
+                Some(ExplainKind::QueryPlan) => Some(Cmd::ExplainQueryPlan(stmt, span)),
+                None => Some(Cmd::Stmt(stmt, span)),
             }
         } else {
             None
         }
     }
 
-    fn constraint_name(&mut self) -> Option<Name> {
+    fn explain(&mut self, kind: ExplainKind, span: Span) {
+        self.explain = Some(kind);
+        self.span = Some(span);
+    }
+
+    fn constraint_name(&mut self) -> Option<Name<'input>> {
         self.constraint_name.take()
     }
     fn no_constraint_name(&self) -> bool {
@@ -113,18 +297,62 @@ impl<'input> Context<'input> {
     }
     fn add_module_arg(&mut self) {
         if let Some((start, end)) = self.module_arg.take() {
+            let (start, end) = (start - self.offset, end - self.offset);
             if let Ok(arg) = std::str::from_utf8(&self.input[start..end]) {
-                self.module_args.get_or_insert(vec![]).push(arg.to_owned());
+                self.module_args
+                    .get_or_insert(vec![])
+                    .push(Cow::Borrowed(arg));
             } // FIXME error handling
         }
     }
-    fn module_args(&mut self) -> Option<Vec<String>> {
+    fn module_args(&mut self) -> Option<Vec<Cow<'input, str>>> {
         self.add_module_arg();
         self.module_args.take()
     }
 
+    /// Number the variable `name` (without the `?` prefix) like SQLite
+    fn variable(&mut self, name: &'input [u8]) -> Result<(), ParserError> {
+        let max = self.limits.max_variable_number;
+        if name.is_empty() {
+            self.variables += 1;
+        } else if name.iter().all(u8::is_ascii_digit) {
+            match from_bytes(name).parse::<usize>() {
+                Ok(n) if (1..=max).contains(&n) => self.variables = self.variables.max(n),
+                _ => {
+                    return Err(custom_err!(
+                        "variable number must be between ?1 and ?{}",
+                        max
+                    ))
+                }
+            }
+        } else if self.variable_names.insert(name) {
+            self.variables += 1;
+        }
+        if self.variables > max {
+            return Err(custom_err!("too many SQL variables"));
+        }
+        Ok(())
+    }
+
+    /// Check the number of arguments of the function `name`
+    fn function_args(&self, name: &Token, args: Option<&Vec<Expr>>) -> Result<(), ParserError> {
+        if args.map_or(0, Vec::len) > self.limits.max_function_arg {
+            return Err(custom_err!(
+                "too many arguments on function {}",
+                from_bytes(name.1)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Consume the fragment parsed instead of a command
+    pub fn fragment(&mut self) -> Option<Fragment<'input>> {
+        self.fragment.take()
+    }
+
     /// This routine is called after a single SQL statement has been parsed.
-    fn sqlite3_finish_coding(&mut self) {
+    fn sqlite3_finish_coding(&mut self, span: Span) {
+        self.span = Some(self.span.map_or(span, |explain| explain.to(span)));
         self.done = true;
     }
 
@@ -137,6 +365,19 @@ impl<'input> Context<'input> {
         self.error.is_none()
     }
 
+    /// Mark the empty identifier fed at `offset` as missing from the input
+    pub fn insert_placeholder(&mut self, offset: usize) {
+        self.placeholders.push(offset);
+    }
+    /// Offsets of the identifiers missing from the input
+    pub fn placeholders(&self) -> &[usize] {
+        &self.placeholders
+    }
+    /// Whether `token` is an identifier missing from the input
+    fn is_placeholder(&self, token: &Token<'_>) -> bool {
+        token.1.is_empty() && self.placeholders.contains(&token.0)
+    }
+
     /// Consume error generated by parser
     pub fn error(&mut self) -> Option<ParserError> {
         self.error.take()
@@ -145,9 +386,14 @@ impl<'input> Context<'input> {
     pub fn reset(&mut self) {
         self.explain = None;
         self.stmt = None;
+        self.fragment = None;
+        self.span = None;
         self.constraint_name = None;
         self.module_arg = None;
         self.module_args = None;
+        self.variables = 0;
+        self.variable_names.clear();
+        self.placeholders.clear();
         self.done = false;
         self.error = None;
     }
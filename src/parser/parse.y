@@ -37,12 +37,20 @@
 // This code runs whenever there is a syntax error
 //
 %syntax_error {
-  if TokenType::TK_EOF as YYCODETYPE == yymajor {
+  // the `;` inserted at the end of input is empty
+  if TokenType::TK_EOF as YYCODETYPE == yymajor
+    || (TokenType::TK_SEMI as YYCODETYPE == yymajor && yyminor.1.is_empty()) {
     error!(target: TARGET, "incomplete input");
     self.ctx.error = Some(ParserError::UnexpectedEof);
   } else {
     error!(target: TARGET, "near \"{:?}\": syntax error", yyminor);
-    self.ctx.error = Some(ParserError::SyntaxError(from_bytes(yyminor.1)));
+    let token_type = TokenType::try_from(yymajor).unwrap_or(TokenType::TK_ID);
+    let expected = expected_tokens(self.yy_expected_on_error());
+    self.ctx.error = Some(ParserError::SyntaxError {
+      token_type,
+      found: from_bytes(yyminor.1),
+      expected,
+    });
   }
 }
 
@@ -56,15 +64,53 @@
 %include {
 use crate::custom_err;
 use crate::parser::ast::*;
-use crate::parser::{Context, ParserError};
+use crate::parser::{expected_tokens, Context, Fragment, ParserError};
 use crate::dialect::{from_bytes, from_token, Token, TokenType};
 use indexmap::IndexMap;
 use log::error;
+use std::borrow::Cow;
 
 #[expect(non_camel_case_types)]
 type sqlite3ParserError = crate::parser::ParserError;
+
+// `RIGHT` and `FULL` joins require SQLite 3.39.0
+fn require_join(ctx: &Context, op: &JoinOperator, span: Span) -> Result<(), ParserError> {
+  match op {
+    JoinOperator::TypedJoin(Some(jt)) if jt.contains(JoinType::LEFT | JoinType::RIGHT) => {
+      ctx.require("FULL JOIN", 3_039_000, span)
+    }
+    JoinOperator::TypedJoin(Some(jt)) if jt.contains(JoinType::RIGHT) => {
+      ctx.require("RIGHT JOIN", 3_039_000, span)
+    }
+    _ => Ok(()),
+  }
+}
+
+// Upsert requires SQLite 3.24.0, and a second `ON CONFLICT` clause 3.35.0
+fn require_upsert(ctx: &Context, span: Span, next: Option<Span>) -> Result<(), ParserError> {
+  ctx.require("upsert", 3_024_000, span)?;
+  match next {
+    Some(next) => ctx.require("multiple ON CONFLICT clauses", 3_035_000, next),
+    None => Ok(()),
+  }
+}
+
+// End of `DO UPDATE SET ... [WHERE ...]`
+fn do_update_end(sets: &[Set], where_clause: Option<&Expr>) -> Span {
+  where_clause.or(sets.last().map(|set| &set.expr)).map_or(Span::default(), |expr| expr.span)
+}
 } // end %include
 
+// Nonterminals whose nesting is limited by `ParserLimits::max_expr_depth`
+// (`term` too, for literals): all those whose trees can be nested
+%nested expr select term seltablist upsert.
+
+// This code runs instead of a reduction nesting too many expressions
+//
+%stack_overflow {
+  custom_err!("Expression tree is too large (maximum depth {})", self.yymaxdepth)
+}
+
 // Input is a single SQL command
 input ::= cmdlist.
 cmdlist ::= cmdlist ecmd.
@@ -73,16 +119,16 @@ ecmd ::= SEMI.
 ecmd ::= cmdx SEMI.
 %ifndef SQLITE_OMIT_EXPLAIN
 ecmd ::= explain cmdx SEMI.       {NEVER-REDUCE}
-explain ::= EXPLAIN.              { self.ctx.explain = Some(ExplainKind::Explain); }
-explain ::= EXPLAIN QUERY PLAN.   { self.ctx.explain = Some(ExplainKind::QueryPlan); }
+explain ::= EXPLAIN.              { self.ctx.explain(ExplainKind::Explain, yyspan); }
+explain ::= EXPLAIN QUERY PLAN.   { self.ctx.explain(ExplainKind::QueryPlan, yyspan); }
 %endif  SQLITE_OMIT_EXPLAIN
-cmdx ::= cmd.           { self.ctx.sqlite3_finish_coding(); }
+cmdx ::= cmd.           { self.ctx.sqlite3_finish_coding(yyspan); }
 
 ///////////////////// Begin and end transactions. ////////////////////////////
 //
 
 cmd ::= BEGIN transtype(Y) trans_opt(X).  {self.ctx.stmt = Some(Stmt::Begin(Y, X));}
-%type trans_opt {Option<Name>}
+%type trans_opt "Option<Name<'i>>"
 trans_opt(A) ::= .               {A = None;}
 trans_opt(A) ::= TRANSACTION.    {A = None;}
 trans_opt(A) ::= TRANSACTION nm(X). {A = Some(X);}
@@ -109,7 +155,13 @@ cmd ::= ROLLBACK trans_opt(Y) TO savepoint_opt nm(X). {
 ///////////////////// The CREATE TABLE statement ////////////////////////////
 //
 cmd ::= createkw temp(T) TABLE ifnotexists(E) fullname(Y) create_table_args(X). {
-  self.ctx.stmt = Some(Stmt::CreateTable{ temporary: T, if_not_exists: E, tbl_name: Y, body: X });
+  let (y, x) = (Y, X);
+  if let CreateTableBody::ColumnsAndConstraints{ ref columns, .. } = x {
+    if columns.len() > self.ctx.limits.max_column {
+      return Err(custom_err!("too many columns on {}", y.name));
+    }
+  }
+  self.ctx.stmt = Some(Stmt::CreateTable{ temporary: T, if_not_exists: E, tbl_name: y, body: x });
 }
 createkw(A) ::= CREATE(A).
 
@@ -122,7 +174,7 @@ temp(A) ::= TEMP.  {A = true;}
 %endif  SQLITE_OMIT_TEMPDB
 temp(A) ::= .      {A = false;}
 
-%type create_table_args {CreateTableBody}
+%type create_table_args "CreateTableBody<'i>"
 create_table_args(A) ::= LP columnlist(C) conslist_opt(X) RP table_option_set(F). {
   A = CreateTableBody::columns_and_constraints(C, X, F)?;
 }
@@ -145,12 +197,13 @@ table_option(A) ::= WITHOUT nm(X). {
 table_option(A) ::= nm(X). {
   let option = X;
   if option == "strict" {
+    self.ctx.require("STRICT", 3_037_000, option.1)?;
     A = TableOptions::STRICT;
   }else{
     return Err(custom_err!("unknown table option: {}", option));
   }
 }
-%type columnlist {IndexMap<Name,ColumnDefinition>}
+%type columnlist "IndexMap<Name<'i>,ColumnDefinition<'i>>"
 columnlist(A) ::= columnlist(A) COMMA columnname(X) carglist(Y). {
   let col = X;
   let cd = ColumnDefinition{ col_name: col.0, col_type: col.1, constraints: Y };
@@ -163,7 +216,7 @@ columnlist(A) ::= columnname(X) carglist(Y). {
   ColumnDefinition::add_column(&mut map, cd)?;
   A = map;
 }
-%type columnname {(Name, Option<Type>)}
+%type columnname "(Name<'i>, Option<Type<'i>>)"
 columnname(A) ::= nm(X) typetoken(Y). {A = (X, Y);}
 
 // Declare some tokens early in order to influence their values, to
@@ -246,7 +299,7 @@ columnname(A) ::= nm(X) typetoken(Y). {A = (X, Y);}
 
 // The name of a column or table can be any of the following:
 //
-%type nm {Name}
+%type nm "Name<'i>"
 nm(A) ::= idj(X). { A = Name::from_token(@X, X); }
 nm(A) ::= STRING(X). { A = Name::from_token(@X, X); }
 
@@ -254,7 +307,7 @@ nm(A) ::= STRING(X). { A = Name::from_token(@X, X); }
 // as can be found after the column name in a CREATE TABLE statement.
 // Multiple tokens are concatenated to form the value of the typetoken.
 //
-%type typetoken {Option<Type>}
+%type typetoken "Option<Type<'i>>"
 typetoken(A) ::= .   {A = None;}
 typetoken(A) ::= typename(X). {A = Some(Type{ name: X, size: None });}
 typetoken(A) ::= typename(X) LP signed(Y) RP. {
@@ -263,10 +316,10 @@ typetoken(A) ::= typename(X) LP signed(Y) RP. {
 typetoken(A) ::= typename(X) LP signed(Y) COMMA signed(Z) RP. {
   A = Some(Type{ name: X, size: Some(TypeSize::TypeSize(Box::new(Y), Box::new(Z))) });
 }
-%type typename {String}
+%type typename "Cow<'i, str>"
 typename(A) ::= ids(X). {A=from_token(@X, X);}
-typename(A) ::= typename(A) ids(Y). {let ids=from_token(@Y, Y); A.push(' '); A.push_str(&ids);}
-%type signed {Expr}
+typename(A) ::= typename(A) ids(Y). {let ids=from_token(@Y, Y); let a = A.to_mut(); a.push(' '); a.push_str(&ids);}
+%type signed "Expr<'i>"
 signed ::= plus_num.
 signed ::= minus_num.
 
@@ -287,34 +340,42 @@ signed ::= minus_num.
 // "carglist" is a list of additional constraints that come after the
 // column name and column type in a CREATE TABLE statement.
 //
-%type carglist {Vec<NamedColumnConstraint>}
+%type carglist "Vec<NamedColumnConstraint<'i>>"
 carglist(A) ::= carglist(A) ccons(X). {if self.ctx.no_constraint_name() { let cc = X; A.push(cc); }}
 carglist(A) ::= .                     {A = vec![];}
-%type ccons {NamedColumnConstraint}
+%type ccons "NamedColumnConstraint<'i>"
 ccons ::= CONSTRAINT nm(X).           { self.ctx.constraint_name = Some(X);}
 ccons(A) ::= DEFAULT term(X). {
   let name = self.ctx.constraint_name();
   let constraint = ColumnConstraint::Default(X);
   A = NamedColumnConstraint{ name, constraint };
 }
-ccons(A) ::= DEFAULT LP expr(X) RP. {
+ccons(A) ::= DEFAULT LP(L) expr(X) RP(R). {
   let name = self.ctx.constraint_name();
-  let constraint = ColumnConstraint::Default(Expr::parenthesized(X));
+  let (l, r) = (L, R);
+  let span = Span::from(&l).to(Span::from(&r));
+  let constraint = ColumnConstraint::Default(Expr::new(ExprKind::parenthesized(X), span));
   A = NamedColumnConstraint{ name, constraint };
 }
-ccons(A) ::= DEFAULT PLUS term(X). {
+ccons(A) ::= DEFAULT PLUS(S) term(X). {
   let name = self.ctx.constraint_name();
-  let constraint = ColumnConstraint::Default(Expr::Unary(UnaryOperator::Positive, Box::new(X)));
+  let x = X;
+  let span = Span::from(&S).to(x.span);
+  let constraint = ColumnConstraint::Default(Expr::new(ExprKind::Unary(UnaryOperator::Positive, Box::new(x)), span));
   A = NamedColumnConstraint{ name, constraint };
 }
-ccons(A) ::= DEFAULT MINUS term(X).      {
+ccons(A) ::= DEFAULT MINUS(S) term(X).      {
   let name = self.ctx.constraint_name();
-  let constraint = ColumnConstraint::Default(Expr::Unary(UnaryOperator::Negative, Box::new(X)));
+  let x = X;
+  let span = Span::from(&S).to(x.span);
+  let constraint = ColumnConstraint::Default(Expr::new(ExprKind::Unary(UnaryOperator::Negative, Box::new(x)), span));
   A = NamedColumnConstraint{ name, constraint };
 }
 ccons(A) ::= DEFAULT id(X).       {
   let name = self.ctx.constraint_name();
-  let constraint = ColumnConstraint::Default(Expr::id(@X, X));
+  let (xt, x) = (@X, X);
+  let kind = if self.ctx.is_placeholder(&x) { ExprKind::Error } else { ExprKind::id(xt, x) };
+  let constraint = ColumnConstraint::Default(Expr::new(kind, Span::from(&x)));
   A = NamedColumnConstraint{ name, constraint };
 }
 
@@ -371,7 +432,7 @@ ccons(A) ::= AS generated(X). {
   let constraint = X;
   A = NamedColumnConstraint{ name, constraint };
 }
-%type generated {ColumnConstraint}
+%type generated "ColumnConstraint<'i>"
 generated(X) ::= LP expr(E) RP. {
   X = ColumnConstraint::Generated{ expr: E, typ: None };
 }
@@ -389,10 +450,10 @@ autoinc(X) ::= AUTOINCR.  {X = true;}
 // or immediate and which determine what action to take if a ref-integ
 // check fails.
 //
-%type refargs {Vec<RefArg>}
+%type refargs "Vec<RefArg<'i>>"
 refargs(A) ::= .                  { A = vec![]; /* EV: R-19803-45884 */}
 refargs(A) ::= refargs(A) refarg(Y). { let ra = Y; A.push(ra); }
-%type refarg {RefArg}
+%type refarg "RefArg<'i>"
 refarg(A) ::= MATCH nm(X).              { A = RefArg::Match(X); }
 refarg(A) ::= ON INSERT refact(X).      { A = RefArg::OnInsert(X); }
 refarg(A) ::= ON DELETE refact(X).   { A = RefArg::OnDelete(X); }
@@ -411,15 +472,19 @@ init_deferred_pred_opt(A) ::= .                       {A = None;}
 init_deferred_pred_opt(A) ::= INITIALLY DEFERRED.     {A = Some(InitDeferredPred::InitiallyDeferred);}
 init_deferred_pred_opt(A) ::= INITIALLY IMMEDIATE.    {A = Some(InitDeferredPred::InitiallyImmediate);}
 
-%type conslist_opt {Option<Vec<NamedTableConstraint>>}
+%type conslist_opt "Option<Vec<NamedTableConstraint<'i>>>"
 conslist_opt(A) ::= .                         {A = None;}
-conslist_opt(A) ::= COMMA conslist(X).        {A = Some(X);}
-%type conslist {Vec<NamedTableConstraint>}
+conslist_opt(A) ::= COMMA conslist(X).        {
+  let x = X;
+  // empty when there are only dangling names, like `CONSTRAINT c`
+  A = if x.is_empty() { None } else { Some(x) };
+}
+%type conslist "Vec<NamedTableConstraint<'i>>"
 conslist(A) ::= conslist(A) tconscomma tcons(X). {if self.ctx.no_constraint_name() { let tc = X; A.push(tc); }}
 conslist(A) ::= tcons(X).                        {if self.ctx.no_constraint_name() { let tc = X; A = vec![tc]; } else { A = vec![]; }}
 tconscomma ::= COMMA.            { self.ctx.constraint_name = None;} // TODO Validate: useful ?
 tconscomma ::= .
-%type tcons {NamedTableConstraint}
+%type tcons "NamedTableConstraint<'i>"
 tcons ::= CONSTRAINT nm(X).      { self.ctx.constraint_name = Some(X)}
 tcons(A) ::= PRIMARY KEY LP sortlist(X) autoinc(I) RP onconf(R). {
   let name = self.ctx.constraint_name();
@@ -489,9 +554,9 @@ cmd ::= select(X).  {
   self.ctx.stmt = Some(Stmt::Select(Box::new(X)));
 }
 
-%type select {Select}
-%type selectnowith {SelectBody}
-%type oneselect {OneSelect}
+%type select "Select<'i>"
+%type selectnowith "SelectBody<'i>"
+%type oneselect "OneSelect<'i>"
 
 %include {
 }
@@ -515,6 +580,9 @@ selectnowith(A) ::= oneselect(X). {
 selectnowith(A) ::= selectnowith(A) multiselect_op(Y) oneselect(Z).  {
   let cs = CompoundSelect{ operator: Y, select: Z };
   A.push(cs)?;
+  if A.compounds.as_ref().map_or(0, Vec::len) >= self.ctx.limits.max_compound_select {
+    return Err(custom_err!("too many terms in compound SELECT"));
+  }
 }
 %type multiselect_op {CompoundOperator}
 multiselect_op(A) ::= UNION.             {A = CompoundOperator::Union;}
@@ -525,13 +593,21 @@ multiselect_op(A) ::= INTERSECT.         {A = CompoundOperator::Intersect;}
 
 oneselect(A) ::= SELECT distinct(D) selcollist(W) from(X) where_opt(Y)
                  groupby_opt(P). {
-  A = OneSelect::Select{ distinctness: D, columns: W, from: X, where_clause: Y,
+  let w = W;
+  if w.len() > self.ctx.limits.max_column {
+    return Err(custom_err!("too many columns in result set"));
+  }
+  A = OneSelect::Select{ distinctness: D, columns: w, from: X, where_clause: Y,
                          group_by: P, window_clause: None };
     }
 %ifndef SQLITE_OMIT_WINDOWFUNC
 oneselect(A) ::= SELECT distinct(D) selcollist(W) from(X) where_opt(Y)
                  groupby_opt(P) window_clause(R). {
-  A = OneSelect::Select{ distinctness: D, columns: W, from: X, where_clause: Y,
+  let w = W;
+  if w.len() > self.ctx.limits.max_column {
+    return Err(custom_err!("too many columns in result set"));
+  }
+  A = OneSelect::Select{ distinctness: D, columns: w, from: X, where_clause: Y,
                          group_by: P, window_clause: Some(R) };
 }
 %endif
@@ -539,7 +615,7 @@ oneselect(A) ::= SELECT distinct(D) selcollist(W) from(X) where_opt(Y)
 
 oneselect(A) ::= values(X). { A = OneSelect::Values(X); }
 
-%type values {Vec<Vec<Expr>>}
+%type values "Vec<Vec<Expr<'i>>>"
 values(A) ::= VALUES LP nexprlist(X) RP. {
   A = vec![X];
 }
@@ -561,8 +637,8 @@ distinct(A) ::= .           {A = None;}
 // "SELECT * FROM ..." is encoded as a special expression with an
 // opcode of TK_ASTERISK.
 //
-%type selcollist {Vec<ResultColumn>}
-%type sclp {Vec<ResultColumn>}
+%type selcollist "Vec<ResultColumn<'i>>"
+%type sclp "Vec<ResultColumn<'i>>"
 sclp(A) ::= selcollist(A) COMMA.
 sclp(A) ::= .                                {A = Vec::<ResultColumn>::new();}
 selcollist(A) ::= sclp(A) expr(X) as(Y).     {
@@ -581,15 +657,15 @@ selcollist(A) ::= sclp(A) nm(X) DOT STAR. {
 // An option "AS <id>" phrase that can follow one of the expressions that
 // define the result set, or one of the tables in the FROM clause.
 //
-%type as {Option<As>}
+%type as "Option<As<'i>>"
 as(X) ::= AS nm(Y).    {X = Some(As::As(Y));}
 as(X) ::= ids(Y).      {X = Some(As::Elided(Name::from_token(@Y, Y)));}
 as(X) ::= .            {X = None;}
 
 
-%type seltablist {FromClause}
-%type stl_prefix {FromClause}
-%type from {Option<FromClause>}
+%type seltablist "FromClause<'i>"
+%type stl_prefix "FromClause<'i>"
+%type from "Option<FromClause<'i>>"
 
 // A complete FROM clause.
 //
@@ -633,7 +709,7 @@ seltablist(A) ::= stl_prefix(A) fullname(Y) LP exprlist(E) RP as(Z)
   }
 %endif  SQLITE_OMIT_SUBQUERY
 
-%type fullname {QualifiedName}
+%type fullname "QualifiedName<'i>"
 fullname(A) ::= nm(X).  {
   A = QualifiedName::single(X);
 }
@@ -641,7 +717,7 @@ fullname(A) ::= nm(X) DOT nm(Y). {
   A = QualifiedName::fullname(X, Y);
 }
 
-%type xfullname {QualifiedName}
+%type xfullname "QualifiedName<'i>"
 xfullname(A) ::= nm(X).
    {A = QualifiedName::single(X); /*A-overwrites-X*/}
 xfullname(A) ::= nm(X) DOT nm(Y).
@@ -656,12 +732,21 @@ xfullname(A) ::= nm(X) AS nm(Z). {
 %type joinop {JoinOperator}
 joinop(X) ::= COMMA.              { X = JoinOperator::Comma; }
 joinop(X) ::= JOIN.              { X = JoinOperator::TypedJoin(None); }
-joinop(X) ::= JOIN_KW(A) JOIN.
-                  {X = JoinOperator::from(A, None, None)?;  /*X-overwrites-A*/}
-joinop(X) ::= JOIN_KW(A) nm(B) JOIN.
-                  {X = JoinOperator::from(A, Some(B), None)?; /*X-overwrites-A*/}
-joinop(X) ::= JOIN_KW(A) nm(B) nm(C) JOIN.
-                  {X = JoinOperator::from(A, Some(B), Some(C))?;/*X-overwrites-A*/}
+joinop(X) ::= JOIN_KW(A) JOIN. {
+  let op = JoinOperator::from(A, None, None)?;
+  require_join(&self.ctx, &op, yyspan)?;
+  X = op; /*X-overwrites-A*/
+}
+joinop(X) ::= JOIN_KW(A) nm(B) JOIN. {
+  let op = JoinOperator::from(A, Some(B), None)?;
+  require_join(&self.ctx, &op, yyspan)?;
+  X = op; /*X-overwrites-A*/
+}
+joinop(X) ::= JOIN_KW(A) nm(B) nm(C) JOIN. {
+  let op = JoinOperator::from(A, Some(B), Some(C))?;
+  require_join(&self.ctx, &op, yyspan)?;
+  X = op; /*X-overwrites-A*/
+}
 
 // There is a parsing ambiguity in an upsert statement that uses a
 // SELECT on the RHS of a the INSERT:
@@ -680,7 +765,7 @@ joinop(X) ::= JOIN_KW(A) nm(B) nm(C) JOIN.
 // The [AND] and [OR] precedence marks in the rules for on_using cause the
 // ON in this context to always be interpreted as belonging to the JOIN.
 //
-%type on_using {Option<JoinConstraint>}
+%type on_using "Option<JoinConstraint<'i>>"
 on_using(N) ::= ON expr(E).            {N = Some(JoinConstraint::On(E));}
 on_using(N) ::= USING LP idlist(L) RP. {N = Some(JoinConstraint::Using(L));}
 on_using(N) ::= .                 [OR] {N = None;}
@@ -695,21 +780,27 @@ on_using(N) ::= .                 [OR] {N = None;}
 // normally illegal. The sqlite3SrcListIndexedBy() function
 // recognizes and interprets this as a special case.
 //
-%type indexed_opt {Option<Indexed>}
+%type indexed_opt "Option<Indexed<'i>>"
 indexed_opt(A) ::= .                 {A = None;}
 indexed_opt(A) ::= INDEXED BY nm(X). {A = Some(Indexed::IndexedBy(X));}
 indexed_opt(A) ::= NOT INDEXED.      {A = Some(Indexed::NotIndexed);}
 
-%type orderby_opt {Option<Vec<SortedColumn>>}
+%type orderby_opt "Option<Vec<SortedColumn<'i>>>"
 
 // the sortlist non-terminal stores a list of expression where each
 // expression is optionally followed by ASC or DESC to indicate the
 // sort order.
 //
-%type sortlist {Vec<SortedColumn>}
+%type sortlist "Vec<SortedColumn<'i>>"
 
 orderby_opt(A) ::= .                          {A = None;}
-orderby_opt(A) ::= ORDER BY sortlist(X).      {A = Some(X);}
+orderby_opt(A) ::= ORDER BY sortlist(X).      {
+  let x = X;
+  if x.len() > self.ctx.limits.max_column {
+    return Err(custom_err!("too many terms in ORDER BY clause"));
+  }
+  A = Some(x);
+}
 sortlist(A) ::= sortlist(A) COMMA expr(Y) sortorder(Z) nulls(X). {
   let sc = SortedColumn { expr: Y, order: Z, nulls: X };
   A.push(sc);
@@ -725,19 +816,31 @@ sortorder(A) ::= DESC.          {A = Some(SortOrder::Desc);}
 sortorder(A) ::= .              {A = None;}
 
 %type nulls {Option<NullsOrder>}
-nulls(A) ::= NULLS FIRST.       {A = Some(NullsOrder::First);}
-nulls(A) ::= NULLS LAST.        {A = Some(NullsOrder::Last);}
+nulls(A) ::= NULLS FIRST.       {
+  self.ctx.require("NULLS FIRST", 3_030_000, yyspan)?;
+  A = Some(NullsOrder::First);
+}
+nulls(A) ::= NULLS LAST.        {
+  self.ctx.require("NULLS LAST", 3_030_000, yyspan)?;
+  A = Some(NullsOrder::Last);
+}
 nulls(A) ::= .                  {A = None;}
 
-%type groupby_opt {Option<GroupBy>}
+%type groupby_opt "Option<GroupBy<'i>>"
 groupby_opt(A) ::= .                      {A = None;}
-groupby_opt(A) ::= GROUP BY nexprlist(X) having_opt(Y). {A = Some(GroupBy{ exprs: X, having: Y });}
+groupby_opt(A) ::= GROUP BY nexprlist(X) having_opt(Y). {
+  let x = X;
+  if x.len() > self.ctx.limits.max_column {
+    return Err(custom_err!("too many terms in GROUP BY clause"));
+  }
+  A = Some(GroupBy{ exprs: x, having: Y });
+}
 
-%type having_opt {Option<Expr>}
+%type having_opt "Option<Expr<'i>>"
 having_opt(A) ::= .                {A = None;}
 having_opt(A) ::= HAVING expr(X).  {A = Some(X);}
 
-%type limit_opt {Option<Limit>}
+%type limit_opt "Option<Limit<'i>>"
 
 // The destructor for limit_opt will never fire in the current grammar.
 // The limit_opt non-terminal only occurs at the end of a single production
@@ -772,29 +875,35 @@ cmd ::= with(C) DELETE FROM xfullname(X) indexed_opt(I) where_opt_ret(W). {
 }
 %endif
 
-%type where_opt {Option<Expr>}
-%type where_opt_ret {(Option<Expr>, Option<Vec<ResultColumn>>)}
+%type where_opt "Option<Expr<'i>>"
+%type where_opt_ret "(Option<Expr<'i>>, Option<Vec<ResultColumn<'i>>>)"
 
 where_opt(A) ::= .                    {A = None;}
 where_opt(A) ::= WHERE expr(X).       {A = Some(X);}
 where_opt_ret(A) ::= .                                      {A = (None, None);}
 where_opt_ret(A) ::= WHERE expr(X).                         {A = (Some(X), None);}
-where_opt_ret(A) ::= RETURNING selcollist(X).
-       {A = (None, Some(X));}
-where_opt_ret(A) ::= WHERE expr(X) RETURNING selcollist(Y).
-       {A = (Some(X), Some(Y));}
+where_opt_ret(A) ::= RETURNING(R) selcollist(X). {
+  let span = Span::from(&R);
+  self.ctx.require("RETURNING", 3_035_000, span)?;
+  A = (None, Some(X));
+}
+where_opt_ret(A) ::= WHERE expr(X) RETURNING(R) selcollist(Y). {
+  let span = Span::from(&R);
+  self.ctx.require("RETURNING", 3_035_000, span)?;
+  A = (Some(X), Some(Y));
+}
 
 ////////////////////////// The UPDATE command ////////////////////////////////
 //
 %if SQLITE_ENABLE_UPDATE_DELETE_LIMIT || SQLITE_UDL_CAPABLE_PARSER
-cmd ::= with(C) UPDATE orconf(R) xfullname(X) indexed_opt(I) SET setlist(Y) from(F)
+cmd ::= with(C) UPDATE orconf(R) xfullname(X) indexed_opt(I) SET setlist(Y) update_from(F)
         where_opt_ret(W) orderby_opt(O) limit_opt(L).  {
   let (where_clause, returning) = W;
   self.ctx.stmt = Some(Stmt::Update { with: C, or_conflict: R, tbl_name: X, indexed: I, sets: Y, from: F,
                                       where_clause, returning, order_by: O, limit: L });
 }
 %else
-cmd ::= with(C) UPDATE orconf(R) xfullname(X) indexed_opt(I) SET setlist(Y) from(F)
+cmd ::= with(C) UPDATE orconf(R) xfullname(X) indexed_opt(I) SET setlist(Y) update_from(F)
         where_opt_ret(W). {
   let (where_clause, returning) = W;
   self.ctx.stmt = Some(Stmt::Update { with: C, or_conflict: R, tbl_name: X, indexed: I, sets: Y, from: F,
@@ -802,17 +911,29 @@ cmd ::= with(C) UPDATE orconf(R) xfullname(X) indexed_opt(I) SET setlist(Y) from
 }
 %endif
 
+%type update_from "Option<FromClause<'i>>"
+update_from(A) ::= .                     {A = None;}
+update_from(A) ::= FROM(F) seltablist(X). {
+  let span = Span::from(&F);
+  self.ctx.require("UPDATE FROM", 3_033_000, span)?;
+  A = Some(X);
+}
 
-
-%type setlist {Vec<Set>}
+%type setlist "Vec<Set<'i>>"
 
 setlist(A) ::= setlist(A) COMMA nm(X) EQ expr(Y). {
   let s = Set{ col_names: DistinctNames::single(X), expr: Y };
   A.push(s);
+  if A.len() > self.ctx.limits.max_column {
+    return Err(custom_err!("too many columns in set list"));
+  }
 }
 setlist(A) ::= setlist(A) COMMA LP idlist(X) RP EQ expr(Y). {
   let s = Set{ col_names: X, expr: Y };
   A.push(s);
+  if A.len() > self.ctx.limits.max_column {
+    return Err(custom_err!("too many columns in set list"));
+  }
 }
 setlist(A) ::= nm(X) EQ expr(Y). {
   A = vec![Set{ col_names: DistinctNames::single(X), expr: Y }];
@@ -825,7 +946,7 @@ setlist(A) ::= LP idlist(X) RP EQ expr(Y). {
 //
 cmd ::= with(W) insert_cmd(R) INTO xfullname(X) idlist_opt(F) select(S)
         upsert(U). {
-  let (upsert, returning) = U;
+  let (upsert, returning, _) = U;
   let body = InsertBody::Select(Box::new(S), upsert);
   self.ctx.stmt = Some(Stmt::Insert{ with: W, or_conflict: R, tbl_name: X, columns: F,
                                      body, returning });
@@ -837,41 +958,61 @@ cmd ::= with(W) insert_cmd(R) INTO xfullname(X) idlist_opt(F) DEFAULT VALUES ret
                                      body, returning: Y });
 }
 
-%type upsert {(Option<Upsert>, Option<Vec<ResultColumn>>)}
+%type upsert "(Option<Upsert<'i>>, Option<Vec<ResultColumn<'i>>>, Span)"
 
 // Because upsert only occurs at the tip end of the INSERT rule for cmd,
 // there is never a case where the value of the upsert pointer will not
 // be destroyed by the cmd action.  So comment-out the destructor to
 // avoid unreachable code.
 //%destructor upsert {sqlite3UpsertDelete(pParse->db,$$);}
-upsert(A) ::= . { A = (None, None); }
-upsert(A) ::= RETURNING selcollist(X).  { A = (None, Some(X)); }
-upsert(A) ::= ON CONFLICT LP sortlist(T) RP where_opt(TW)
+// The span is the one of the first `ON CONFLICT` clause.
+upsert(A) ::= . { A = (None, None, Span::default()); }
+upsert(A) ::= RETURNING(R) selcollist(X).  {
+  let span = Span::from(&R);
+  self.ctx.require("RETURNING", 3_035_000, span)?;
+  A = (None, Some(X), Span::default());
+}
+upsert(A) ::= ON(O) CONFLICT LP sortlist(T) RP where_opt(TW)
               DO UPDATE SET setlist(Z) where_opt(W) upsert(N).
               { let index = UpsertIndex{ targets: T, where_clause: TW };
-                let do_clause = UpsertDo::Set{ sets: Z, where_clause: W };
-                let (next, returning) = N;
-                A = (Some(Upsert{ index: Some(index), do_clause, next: next.map(Box::new) }), returning);}
-upsert(A) ::= ON CONFLICT LP sortlist(T) RP where_opt(TW) DO NOTHING upsert(N).
+                let (sets, where_clause) = (Z, W);
+                let span = Span::from(&O).to(do_update_end(&sets, where_clause.as_ref()));
+                let do_clause = UpsertDo::Set{ sets, where_clause };
+                let (next, returning, next_span) = N;
+                require_upsert(&self.ctx, span, next.is_some().then_some(next_span))?;
+                A = (Some(Upsert{ index: Some(index), do_clause, next: next.map(Box::new) }), returning, span);}
+upsert(A) ::= ON(O) CONFLICT LP sortlist(T) RP where_opt(TW) DO NOTHING(E) upsert(N).
               { let index = UpsertIndex{ targets: T, where_clause: TW };
-                let (next, returning) = N;
-                A = (Some(Upsert{ index: Some(index), do_clause: UpsertDo::Nothing, next: next.map(Box::new) }), returning); }
-upsert(A) ::= ON CONFLICT DO NOTHING returning(R).
-              { A = (Some(Upsert{ index: None, do_clause: UpsertDo::Nothing, next: None }), R); }
-upsert(A) ::= ON CONFLICT DO UPDATE SET setlist(Z) where_opt(W) returning(R).
-              { let do_clause = UpsertDo::Set{ sets: Z, where_clause: W };
-                A = (Some(Upsert{ index: None, do_clause, next: None }), R);}
-
-%type returning {Option<Vec<ResultColumn>>}
-returning(A) ::= RETURNING selcollist(X).  {A = Some(X);}
+                let span = Span::from(&O).to(Span::from(&E));
+                let (next, returning, next_span) = N;
+                require_upsert(&self.ctx, span, next.is_some().then_some(next_span))?;
+                A = (Some(Upsert{ index: Some(index), do_clause: UpsertDo::Nothing, next: next.map(Box::new) }), returning, span); }
+upsert(A) ::= ON(O) CONFLICT DO NOTHING(E) returning(R).
+              { let span = Span::from(&O).to(Span::from(&E));
+                require_upsert(&self.ctx, span, None)?;
+                A = (Some(Upsert{ index: None, do_clause: UpsertDo::Nothing, next: None }), R, span); }
+upsert(A) ::= ON(O) CONFLICT DO UPDATE SET setlist(Z) where_opt(W) returning(R).
+              { let (sets, where_clause) = (Z, W);
+                let span = Span::from(&O).to(do_update_end(&sets, where_clause.as_ref()));
+                require_upsert(&self.ctx, span, None)?;
+                self.ctx.require("ON CONFLICT DO UPDATE without a conflict target", 3_035_000, span)?;
+                let do_clause = UpsertDo::Set{ sets, where_clause };
+                A = (Some(Upsert{ index: None, do_clause, next: None }), R, span);}
+
+%type returning "Option<Vec<ResultColumn<'i>>>"
+returning(A) ::= RETURNING(R) selcollist(X).  {
+  let span = Span::from(&R);
+  self.ctx.require("RETURNING", 3_035_000, span)?;
+  A = Some(X);
+}
 returning(A) ::= . {A = None;}
 
 %type insert_cmd {Option<ResolveType>}
 insert_cmd(A) ::= INSERT orconf(R).   {A = R;}
 insert_cmd(A) ::= REPLACE.            {A = Some(ResolveType::Replace);}
 
-%type idlist_opt {Option<DistinctNames>}
-%type idlist {DistinctNames}
+%type idlist_opt "Option<DistinctNames<'i>>"
+%type idlist "DistinctNames<'i>"
 idlist_opt(A) ::= .                       {A = None;}
 idlist_opt(A) ::= LP idlist(X) RP.    {A = Some(X);}
 idlist(A) ::= idlist(A) COMMA nm(Y).
@@ -882,97 +1023,115 @@ idlist(A) ::= nm(Y).
 /////////////////////////// Expression Processing /////////////////////////////
 //
 
-%type expr {Expr}
-%type term {Expr}
+%type expr "Expr<'i>"
+%type term "Expr<'i>"
 
 %include {
 }
 
 expr(A) ::= term(A).
-expr(A) ::= LP expr(X) RP. {A = Expr::parenthesized(X);}
-expr(A) ::= idj(X).          {A= Expr::id(@X, X); /*A-overwrites-X*/}
+expr(A) ::= LP expr(X) RP. {A = Expr::new(ExprKind::parenthesized(X), yyspan);}
+expr(A) ::= idj(X).          {
+  let (xt, x) = (@X, X);
+  let kind = if self.ctx.is_placeholder(&x) { ExprKind::Error } else { ExprKind::id(xt, x) };
+  A = Expr::new(kind, yyspan); /*A-overwrites-X*/
+}
 expr(A) ::= nm(X) DOT nm(Y). {
-  A = Expr::Qualified(X, Y); /*A-overwrites-X*/
+  A = Expr::new(ExprKind::Qualified(X, Y), yyspan); /*A-overwrites-X*/
 }
 expr(A) ::= nm(X) DOT nm(Y) DOT nm(Z). {
-  A = Expr::DoublyQualified(X, Y, Z); /*A-overwrites-X*/
+  A = Expr::new(ExprKind::DoublyQualified(X, Y, Z), yyspan); /*A-overwrites-X*/
 }
-term(A) ::= NULL. {A=Expr::Literal(Literal::Null);}
-term(A) ::= BLOB(X). {A=Expr::Literal(Literal::Blob(X.unwrap())); /*A-overwrites-X*/}
-term(A) ::= STRING(X).          {A=Expr::Literal(Literal::String(X.unwrap())); /*A-overwrites-X*/}
+term(A) ::= NULL. {A = Expr::new(ExprKind::Literal(Literal::Null), yyspan);}
+term(A) ::= BLOB(X). {A = Expr::new(ExprKind::Literal(Literal::Blob(from_token(@X, X))), yyspan); /*A-overwrites-X*/}
+term(A) ::= STRING(X).          {A = Expr::new(ExprKind::Literal(Literal::String(from_token(@X, X))), yyspan); /*A-overwrites-X*/}
 term(A) ::= FLOAT|INTEGER(X). {
-  A = Expr::Literal(Literal::Numeric(X.unwrap())); /*A-overwrites-X*/
+  A = Expr::new(ExprKind::Literal(Literal::Numeric(from_token(@X, X))), yyspan); /*A-overwrites-X*/
 }
 expr(A) ::= VARIABLE(X).     {
-  A = Expr::Variable(X.unwrap()); /*A-overwrites-X*/
+  let x = X;
+  self.ctx.variable(x.1)?;
+  A = Expr::new(ExprKind::Variable(from_token(@X, x)), yyspan);
 }
 expr(A) ::= expr(X) COLLATE ids(C). {
-  A = Expr::collate(X, @C, C); /*A-overwrites-X*/
+  A = Expr::new(ExprKind::collate(X, @C, C), yyspan); /*A-overwrites-X*/
 }
 %ifndef SQLITE_OMIT_CAST
 expr(A) ::= CAST LP expr(E) AS typetoken(T) RP. {
-  A = Expr::cast(E, T);
+  A = Expr::new(ExprKind::cast(E, T), yyspan);
 }
 %endif  SQLITE_OMIT_CAST
 
 expr(A) ::= idj(X) LP distinct(D) exprlist(Y) RP. {
-  A = Expr::FunctionCall{ name: Id::from_token(@X, X), distinctness: D, args: Y, order_by: None, filter_over: None }; /*A-overwrites-X*/
+  let (x, y) = (X, Y);
+  self.ctx.function_args(&x, y.as_ref())?;
+  A = Expr::new(ExprKind::FunctionCall{ name: Id::from_token(@X, x), distinctness: D, args: y, order_by: None, filter_over: None }, yyspan);
 }
-expr(A) ::= idj(X) LP distinct(D) exprlist(Y) ORDER BY sortlist(O) RP. {
-  A = Expr::FunctionCall{ name: Id::from_token(@X, X), distinctness: D, args: Y, order_by: Some(O), filter_over: None }; /*A-overwrites-X*/
+expr(A) ::= idj(X) LP distinct(D) exprlist(Y) ORDER(B) BY sortlist(O) RP. {
+  let span = Span::from(&B);
+  self.ctx.require("ORDER BY in aggregate function arguments", 3_044_000, span)?;
+  let (x, y) = (X, Y);
+  self.ctx.function_args(&x, y.as_ref())?;
+  A = Expr::new(ExprKind::FunctionCall{ name: Id::from_token(@X, x), distinctness: D, args: y, order_by: Some(O), filter_over: None }, yyspan);
 }
 expr(A) ::= idj(X) LP STAR RP. {
-  A = Expr::FunctionCallStar{ name: Id::from_token(@X, X), filter_over: None }; /*A-overwrites-X*/
+  A = Expr::new(ExprKind::FunctionCallStar{ name: Id::from_token(@X, X), filter_over: None }, yyspan); /*A-overwrites-X*/
 }
 
 %ifndef SQLITE_OMIT_WINDOWFUNC
 expr(A) ::= idj(X) LP distinct(D) exprlist(Y) RP filter_over(Z). {
-  A = Expr::FunctionCall{ name: Id::from_token(@X, X), distinctness: D, args: Y, order_by: None, filter_over: Some(Z) }; /*A-overwrites-X*/
+  let (x, y) = (X, Y);
+  self.ctx.function_args(&x, y.as_ref())?;
+  A = Expr::new(ExprKind::FunctionCall{ name: Id::from_token(@X, x), distinctness: D, args: y, order_by: None, filter_over: Some(Z) }, yyspan);
 }
-expr(A) ::= idj(X) LP distinct(D) exprlist(Y) ORDER BY sortlist(O) RP filter_over(Z). {
-  A = Expr::FunctionCall{ name: Id::from_token(@X, X), distinctness: D, args: Y, order_by: Some(O), filter_over: Some(Z) }; /*A-overwrites-X*/
+expr(A) ::= idj(X) LP distinct(D) exprlist(Y) ORDER(B) BY sortlist(O) RP filter_over(Z). {
+  let span = Span::from(&B);
+  self.ctx.require("ORDER BY in aggregate function arguments", 3_044_000, span)?;
+  let (x, y) = (X, Y);
+  self.ctx.function_args(&x, y.as_ref())?;
+  A = Expr::new(ExprKind::FunctionCall{ name: Id::from_token(@X, x), distinctness: D, args: y, order_by: Some(O), filter_over: Some(Z) }, yyspan);
 }
 expr(A) ::= idj(X) LP STAR RP filter_over(Z). {
-  A = Expr::FunctionCallStar{ name: Id::from_token(@X, X), filter_over: Some(Z) }; /*A-overwrites-X*/
+  A = Expr::new(ExprKind::FunctionCallStar{ name: Id::from_token(@X, X), filter_over: Some(Z) }, yyspan); /*A-overwrites-X*/
 }
 %endif
 
 term(A) ::= CTIME_KW(OP). {
-  A = Expr::Literal(Literal::from_ctime_kw(OP));
+  A = Expr::new(ExprKind::Literal(Literal::try_from_ctime_kw(OP)?), yyspan);
 }
 
 expr(A) ::= LP nexprlist(X) COMMA expr(Y) RP. {
   let mut x = X;
   x.push(Y);
-  A = Expr::Parenthesized(x);
+  A = Expr::new(ExprKind::Parenthesized(x), yyspan);
 }
 
-expr(A) ::= expr(X) AND(OP) expr(Y).    {A=Expr::binary(X,@OP,Y); /*A-overwrites-X*/}
-expr(A) ::= expr(X) OR(OP) expr(Y).     {A=Expr::binary(X,@OP,Y); /*A-overwrites-X*/}
+expr(A) ::= expr(X) AND(OP) expr(Y).    {A = Expr::new(ExprKind::binary(X,@OP,Y), yyspan); /*A-overwrites-X*/}
+expr(A) ::= expr(X) OR(OP) expr(Y).     {A = Expr::new(ExprKind::binary(X,@OP,Y), yyspan); /*A-overwrites-X*/}
 expr(A) ::= expr(X) LT|GT|GE|LE(OP) expr(Y).
-                                        {A=Expr::binary(X,@OP,Y); /*A-overwrites-X*/}
-expr(A) ::= expr(X) EQ|NE(OP) expr(Y).  {A=Expr::binary(X,@OP,Y); /*A-overwrites-X*/}
+                                        {A = Expr::new(ExprKind::binary(X,@OP,Y), yyspan); /*A-overwrites-X*/}
+expr(A) ::= expr(X) EQ|NE(OP) expr(Y).  {A = Expr::new(ExprKind::binary(X,@OP,Y), yyspan); /*A-overwrites-X*/}
 expr(A) ::= expr(X) BITAND|BITOR|LSHIFT|RSHIFT(OP) expr(Y).
-                                        {A=Expr::binary(X,@OP,Y); /*A-overwrites-X*/}
+                                        {A = Expr::new(ExprKind::binary(X,@OP,Y), yyspan); /*A-overwrites-X*/}
 expr(A) ::= expr(X) PLUS|MINUS(OP) expr(Y).
-                                        {A=Expr::binary(X,@OP,Y); /*A-overwrites-X*/}
+                                        {A = Expr::new(ExprKind::binary(X,@OP,Y), yyspan); /*A-overwrites-X*/}
 expr(A) ::= expr(X) STAR|SLASH|REM(OP) expr(Y).
-                                        {A=Expr::binary(X,@OP,Y); /*A-overwrites-X*/}
-expr(A) ::= expr(X) CONCAT(OP) expr(Y). {A=Expr::binary(X,@OP,Y); /*A-overwrites-X*/}
+                                        {A = Expr::new(ExprKind::binary(X,@OP,Y), yyspan); /*A-overwrites-X*/}
+expr(A) ::= expr(X) CONCAT(OP) expr(Y). {A = Expr::new(ExprKind::binary(X,@OP,Y), yyspan); /*A-overwrites-X*/}
 %type likeop {(bool, LikeOperator)}
-likeop(A) ::= LIKE_KW|MATCH(X). {A=(false, LikeOperator::from_token(@X, X)); /*A-overwrite-X*/}
-likeop(A) ::= NOT LIKE_KW|MATCH(X). {A=(true, LikeOperator::from_token(@X, X)); /*A-overwrite-X*/}
+likeop(A) ::= LIKE_KW|MATCH(X). {A=(false, LikeOperator::try_from_token(@X, X)?); /*A-overwrite-X*/}
+likeop(A) ::= NOT LIKE_KW|MATCH(X). {A=(true, LikeOperator::try_from_token(@X, X)?); /*A-overwrite-X*/}
 expr(A) ::= expr(X) likeop(OP) expr(Y).  [LIKE_KW]  {
   let op = OP;
-  A=Expr::like(X,op.0,op.1,Y,None); /*A-overwrites-X*/
+  A = Expr::new(ExprKind::like(X,op.0,op.1,Y,None), yyspan); /*A-overwrites-X*/
 }
 expr(A) ::= expr(X) likeop(OP) expr(Y) ESCAPE expr(E).  [LIKE_KW]  {
   let op = OP;
-  A=Expr::like(X,op.0,op.1,Y,Some(E)); /*A-overwrites-X*/
+  A = Expr::new(ExprKind::like(X,op.0,op.1,Y,Some(E)), yyspan); /*A-overwrites-X*/
 }
 
-expr(A) ::= expr(X) ISNULL|NOTNULL(E).   {A = Expr::not_null(X, @E); /*A-overwrites-X*/}
-expr(A) ::= expr(X) NOT NULL.    {A = Expr::not_null(X, TokenType::TK_NOTNULL as YYCODETYPE); /*A-overwrites-X*/}
+expr(A) ::= expr(X) ISNULL|NOTNULL(E).   {A = Expr::new(ExprKind::not_null(X, @E), yyspan); /*A-overwrites-X*/}
+expr(A) ::= expr(X) NOT NULL.    {A = Expr::new(ExprKind::not_null(X, TokenType::TK_NOTNULL as YYCODETYPE), yyspan); /*A-overwrites-X*/}
 
 %include {
 }
@@ -984,62 +1143,67 @@ expr(A) ::= expr(X) NOT NULL.    {A = Expr::not_null(X, TokenType::TK_NOTNULL as
 // is any other expression, code as TK_IS or TK_ISNOT.
 //
 expr(A) ::= expr(X) IS(OP) expr(Y).     {
-  A = Expr::binary(X, @OP, Y); /*A-overwrites-X*/
+  A = Expr::new(ExprKind::binary(X, @OP, Y), yyspan); /*A-overwrites-X*/
 }
 expr(A) ::= expr(X) IS NOT expr(Y). {
-  A = Expr::binary(X, TokenType::TK_NOT as YYCODETYPE, Y); /*A-overwrites-X*/
+  A = Expr::new(ExprKind::binary(X, TokenType::TK_NOT as YYCODETYPE, Y), yyspan); /*A-overwrites-X*/
 }
 expr(A) ::= expr(X) IS NOT DISTINCT FROM expr(Y).     {
-  A = Expr::binary(X, TokenType::TK_IS as YYCODETYPE, Y); /*A-overwrites-X*/
+  self.ctx.require("IS NOT DISTINCT FROM", 3_039_000, yyspan)?;
+  A = Expr::new(ExprKind::binary(X, TokenType::TK_IS as YYCODETYPE, Y), yyspan); /*A-overwrites-X*/
 }
 expr(A) ::= expr(X) IS DISTINCT FROM expr(Y). {
-  A = Expr::binary(X, TokenType::TK_NOT as YYCODETYPE, Y); /*A-overwrites-X*/
+  self.ctx.require("IS DISTINCT FROM", 3_039_000, yyspan)?;
+  A = Expr::new(ExprKind::binary(X, TokenType::TK_NOT as YYCODETYPE, Y), yyspan); /*A-overwrites-X*/
 }
 
 expr(A) ::= NOT(B) expr(X).
-              {A = Expr::unary(UnaryOperator::from(@B), X);/*A-overwrites-B*/}
+              {A = Expr::new(ExprKind::unary(UnaryOperator::from(@B), X), yyspan);/*A-overwrites-B*/}
 expr(A) ::= BITNOT(B) expr(X).
-              {A = Expr::unary(UnaryOperator::from(@B), X);/*A-overwrites-B*/}
+              {A = Expr::new(ExprKind::unary(UnaryOperator::from(@B), X), yyspan);/*A-overwrites-B*/}
 expr(A) ::= PLUS|MINUS(B) expr(X). [BITNOT] {
-  A = Expr::unary(UnaryOperator::from(@B), X);/*A-overwrites-B*/
+  A = Expr::new(ExprKind::unary(UnaryOperator::from(@B), X), yyspan);/*A-overwrites-B*/
 }
 
 expr(A) ::= expr(B) PTR(C) expr(D). {
-  A = Expr::ptr(B, C, D);
+  let c = C;
+  let feature = if c.1 == b"->" { "-> operator" } else { "->> operator" };
+  self.ctx.require(feature, 3_038_000, Span::from(&c))?;
+  A = Expr::new(ExprKind::ptr(B, c, D), yyspan);
 }
 
 %type between_op {bool}
 between_op(A) ::= BETWEEN.     {A = false;}
 between_op(A) ::= NOT BETWEEN. {A = true;}
 expr(A) ::= expr(B) between_op(N) expr(X) AND expr(Y). [BETWEEN] {
-  A = Expr::between(B, N, X, Y);/*A-overwrites-B*/
+  A = Expr::new(ExprKind::between(B, N, X, Y), yyspan);/*A-overwrites-B*/
 }
 %ifndef SQLITE_OMIT_SUBQUERY
   %type in_op {bool}
   in_op(A) ::= IN.      {A = false;}
   in_op(A) ::= NOT IN.  {A = true;}
   expr(A) ::= expr(X) in_op(N) LP exprlist(Y) RP. [IN] {
-    A = Expr::in_list(X, N, Y);/*A-overwrites-X*/
+    A = Expr::new(ExprKind::in_list(X, N, Y), yyspan);/*A-overwrites-X*/
   }
   expr(A) ::= LP select(X) RP. {
-    A = Expr::sub_query(X);
+    A = Expr::new(ExprKind::sub_query(X), yyspan);
   }
   expr(A) ::= expr(X) in_op(N) LP select(Y) RP.  [IN] {
-    A = Expr::in_select(X, N, Y);/*A-overwrites-X*/
+    A = Expr::new(ExprKind::in_select(X, N, Y), yyspan);/*A-overwrites-X*/
   }
   expr(A) ::= expr(X) in_op(N) fullname(Y) paren_exprlist(E). [IN] {
-    A = Expr::in_table(X, N, Y, E);/*A-overwrites-X*/
+    A = Expr::new(ExprKind::in_table(X, N, Y, E), yyspan);/*A-overwrites-X*/
   }
   expr(A) ::= EXISTS LP select(Y) RP. {
-    A = Expr::Exists(Box::new(Y));
+    A = Expr::new(ExprKind::Exists(Box::new(Y)), yyspan);
   }
 %endif SQLITE_OMIT_SUBQUERY
 
 /* CASE expressions */
 expr(A) ::= CASE case_operand(X) case_exprlist(Y) case_else(Z) END. {
-  A = Expr::Case{ base: X.map(Box::new), when_then_pairs: Y, else_expr: Z.map(Box::new)};
+  A = Expr::new(ExprKind::Case{ base: X.map(Box::new), when_then_pairs: Y, else_expr: Z.map(Box::new)}, yyspan);
 }
-%type case_exprlist {Vec<(Expr, Expr)>}
+%type case_exprlist "Vec<(Expr<'i>, Expr<'i>)>"
 case_exprlist(A) ::= case_exprlist(A) WHEN expr(Y) THEN expr(Z). {
   let pair = (Y, Z);
   A.push(pair);
@@ -1047,15 +1211,15 @@ case_exprlist(A) ::= case_exprlist(A) WHEN expr(Y) THEN expr(Z). {
 case_exprlist(A) ::= WHEN expr(Y) THEN expr(Z). {
   A = vec![(Y, Z)];
 }
-%type case_else {Option<Expr>}
+%type case_else "Option<Expr<'i>>"
 case_else(A) ::=  ELSE expr(X).         {A = Some(X);}
 case_else(A) ::=  .                     {A = None;}
-%type case_operand {Option<Expr>}
+%type case_operand "Option<Expr<'i>>"
 case_operand(A) ::= expr(X).            {A = Some(X); /*A-overwrites-X*/}
 case_operand(A) ::= .                   {A = None;}
 
-%type exprlist {Option<Vec<Expr>>}
-%type nexprlist {Vec<Expr>}
+%type exprlist "Option<Vec<Expr<'i>>>"
+%type nexprlist "Vec<Expr<'i>>"
 
 exprlist(A) ::= nexprlist(X).                {A = Some(X);}
 exprlist(A) ::= .                            {A = None;}
@@ -1067,7 +1231,7 @@ nexprlist(A) ::= expr(Y).
 %ifndef SQLITE_OMIT_SUBQUERY
 /* A paren_exprlist is an optional expression list contained inside
 ** of parenthesis */
-%type paren_exprlist {Option<Vec<Expr>>}
+%type paren_exprlist "Option<Vec<Expr<'i>>>"
 paren_exprlist(A) ::= .   {A = None;}
 paren_exprlist(A) ::= LP exprlist(X) RP.  {A = X;}
 %endif SQLITE_OMIT_SUBQUERY
@@ -1101,8 +1265,8 @@ uniqueflag(A) ::= .        {A = false;}
 // (busted) old databases, we need to continue parsing them when loading
 // historical schemas.
 //
-%type eidlist {Vec<IndexedColumn>}
-%type eidlist_opt {Option<Vec<IndexedColumn>>}
+%type eidlist "Vec<IndexedColumn<'i>>"
+%type eidlist_opt "Option<Vec<IndexedColumn<'i>>>"
 
 %include {
 } // end %include
@@ -1117,7 +1281,7 @@ eidlist(A) ::= nm(Y) collate(C) sortorder(Z). {
   A = vec![IndexedColumn{ col_name: Y, collation_name: C, order: Z }]; /*A-overwrites-Y*/
 }
 
-%type collate {Option<Name>}
+%type collate "Option<Name<'i>>"
 collate(C) ::= .              {C = None;}
 collate(C) ::= COLLATE ids(X).   {C = Some(Name::from_token(@X, X));}
 
@@ -1129,7 +1293,7 @@ cmd ::= DROP INDEX ifexists(E) fullname(X).   {self.ctx.stmt = Some(Stmt::DropIn
 ///////////////////////////// The VACUUM command /////////////////////////////
 //
 %if !SQLITE_OMIT_VACUUM && !SQLITE_OMIT_ATTACH
-%type vinto {Option<Expr>}
+%type vinto "Option<Expr<'i>>"
 cmd ::= VACUUM vinto(Y).                {self.ctx.stmt = Some(Stmt::Vacuum(None, Y));}
 cmd ::= VACUUM nm(X) vinto(Y).          {self.ctx.stmt = Some(Stmt::Vacuum(Some(X), Y));}
 vinto(A) ::= INTO expr(X).              {A = Some(X);}
@@ -1147,19 +1311,27 @@ cmd ::= PRAGMA fullname(X) EQ minus_num(Y).
 cmd ::= PRAGMA fullname(X) LP minus_num(Y) RP.
                                              {self.ctx.stmt = Some(Stmt::Pragma(X, Some(PragmaBody::Call(Y))));}
 
-%type nmnum {Expr}
+%type nmnum "Expr<'i>"
 nmnum(A) ::= plus_num(A).
-nmnum(A) ::= nm(X). {A = Expr::Name(X);}
-nmnum(A) ::= ON(X). {A = Expr::Literal(Literal::Keyword(from_token(@X, X)));}
-nmnum(A) ::= DELETE(X). {A = Expr::Literal(Literal::Keyword(from_token(@X, X)));}
-nmnum(A) ::= DEFAULT(X). {A = Expr::Literal(Literal::Keyword(from_token(@X, X)));}
+nmnum(A) ::= nm(X). {A = Expr::new(ExprKind::Name(X), yyspan);}
+nmnum(A) ::= ON(X). {A = Expr::new(ExprKind::Literal(Literal::Keyword(from_token(@X, X))), yyspan);}
+nmnum(A) ::= DELETE(X). {A = Expr::new(ExprKind::Literal(Literal::Keyword(from_token(@X, X))), yyspan);}
+nmnum(A) ::= DEFAULT(X). {A = Expr::new(ExprKind::Literal(Literal::Keyword(from_token(@X, X))), yyspan);}
 %endif SQLITE_OMIT_PRAGMA
 %token_class number INTEGER|FLOAT.
-%type plus_num {Expr}
-plus_num(A) ::= PLUS number(X).       {A = Expr::unary(UnaryOperator::Positive, Expr::Literal(Literal::Numeric(X.unwrap())));}
-plus_num(A) ::= number(X).            {A = Expr::Literal(Literal::Numeric(X.unwrap()));}
-%type minus_num {Expr}
-minus_num(A) ::= MINUS number(X).     {A = Expr::unary(UnaryOperator::Negative, Expr::Literal(Literal::Numeric(X.unwrap())));}
+%type plus_num "Expr<'i>"
+plus_num(A) ::= PLUS number(X).       {
+  let (xt, x) = (@X, X);
+  let x = Expr::new(ExprKind::Literal(Literal::Numeric(from_token(xt, x))), Span::from(&x));
+  A = Expr::new(ExprKind::unary(UnaryOperator::Positive, x), yyspan);
+}
+plus_num(A) ::= number(X).            {A = Expr::new(ExprKind::Literal(Literal::Numeric(from_token(@X, X))), yyspan);}
+%type minus_num "Expr<'i>"
+minus_num(A) ::= MINUS number(X).     {
+  let (xt, x) = (@X, X);
+  let x = Expr::new(ExprKind::Literal(Literal::Numeric(from_token(xt, x))), Span::from(&x));
+  A = Expr::new(ExprKind::unary(UnaryOperator::Negative, x), yyspan);
+}
 //////////////////////////// The CREATE TRIGGER command /////////////////////
 
 %ifndef SQLITE_OMIT_TRIGGER
@@ -1178,7 +1350,7 @@ trigger_time(A) ::= AFTER.  { A = Some(TriggerTime::After); }
 trigger_time(A) ::= INSTEAD OF.  { A = Some(TriggerTime::InsteadOf);}
 trigger_time(A) ::= .            { A = None; }
 
-%type trigger_event {TriggerEvent}
+%type trigger_event "TriggerEvent<'i>"
 trigger_event(A) ::= DELETE.   {A = TriggerEvent::Delete;}
 trigger_event(A) ::= INSERT.   {A = TriggerEvent::Insert;}
 trigger_event(A) ::= UPDATE.          {A = TriggerEvent::Update;}
@@ -1188,11 +1360,11 @@ trigger_event(A) ::= UPDATE OF idlist(X).{A = TriggerEvent::UpdateOf(X);}
 foreach_clause(A) ::= .             { A = false; }
 foreach_clause(A) ::= FOR EACH ROW. { A = true;  }
 
-%type when_clause {Option<Expr>}
+%type when_clause "Option<Expr<'i>>"
 when_clause(A) ::= .             { A = None; }
 when_clause(A) ::= WHEN expr(X). { A = Some(X); }
 
-%type trigger_cmd_list {Vec<TriggerCmd>}
+%type trigger_cmd_list "Vec<TriggerCmd<'i>>"
 trigger_cmd_list(A) ::= trigger_cmd_list(A) trigger_cmd(X) SEMI. {
   let tc = X;
   A.push(tc);
@@ -1205,7 +1377,7 @@ trigger_cmd_list(A) ::= trigger_cmd(X) SEMI. {
 // within a trigger.  The table to INSERT, UPDATE, or DELETE is always in
 // the same database as the table that the trigger fires on.
 //
-%type trnm {Name}
+%type trnm "Name<'i>"
 trnm(A) ::= nm(A).
 trnm(A) ::= nm DOT nm(X). {
   A = X;
@@ -1231,16 +1403,17 @@ tridxby ::= NOT INDEXED. {
 
 
 
-%type trigger_cmd {TriggerCmd}
+%type trigger_cmd "TriggerCmd<'i>"
 // UPDATE
 trigger_cmd(A) ::=
-   UPDATE orconf(R) trnm(X) tridxby SET setlist(Y) from(F) where_opt(Z).
-   {A = TriggerCmd::Update{ or_conflict: R, tbl_name: X, sets: Y, from: F, where_clause: Z };}
+   UPDATE orconf(R) trnm(X) tridxby SET setlist(Y) update_from(F) where_opt(Z). {
+  A = TriggerCmd::Update{ or_conflict: R, tbl_name: X, sets: Y, from: F, where_clause: Z };
+}
 
 // INSERT
 trigger_cmd(A) ::= insert_cmd(R) INTO
                       trnm(X) idlist_opt(F) select(S) upsert(U). {
-  let (upsert, returning) = U;
+  let (upsert, returning, _) = U;
    A = TriggerCmd::Insert{ or_conflict: R, tbl_name: X, col_names: F, select: S, upsert, returning };/*A-overwrites-R*/
 }
 // DELETE
@@ -1253,10 +1426,10 @@ trigger_cmd(A) ::= select(X).
 
 // The special RAISE expression that may occur in trigger programs
 expr(A) ::= RAISE LP IGNORE RP.  {
-  A = Expr::Raise(ResolveType::Ignore, None);
+  A = Expr::new(ExprKind::Raise(ResolveType::Ignore, None), yyspan);
 }
 expr(A) ::= RAISE LP raisetype(T) COMMA expr(Z) RP.  {
-  A = Expr::Raise(T, Some(Box::new(Z)));
+  A = Expr::new(ExprKind::Raise(T, Some(Box::new(Z))), yyspan);
 }
 %endif  !SQLITE_OMIT_TRIGGER
 
@@ -1282,7 +1455,7 @@ cmd ::= DETACH database_kw_opt expr(D). {
   self.ctx.stmt = Some(Stmt::Detach(D));
 }
 
-%type key_opt {Option<Expr>}
+%type key_opt "Option<Expr<'i>>"
 key_opt(A) ::= .                     { A = None; }
 key_opt(A) ::= KEY expr(X).          { A = Some(X); }
 
@@ -1313,11 +1486,17 @@ cmd ::= ALTER TABLE fullname(X)
   let cd = ColumnDefinition{ col_name, col_type, constraints: C };
   self.ctx.stmt = Some(Stmt::AlterTable(X, AlterTableBody::AddColumn(cd)));
 }
-cmd ::= ALTER TABLE fullname(X) RENAME kwcolumn_opt nm(Y) TO nm(Z). {
-  self.ctx.stmt = Some(Stmt::AlterTable(X, AlterTableBody::RenameColumn{ old: Y, new: Z }));
+cmd ::= ALTER TABLE fullname(X) RENAME(R) kwcolumn_opt nm(Y) TO nm(Z). {
+  let new = Z;
+  let span = Span::from(&R).to(new.1);
+  self.ctx.require("RENAME COLUMN", 3_025_000, span)?;
+  self.ctx.stmt = Some(Stmt::AlterTable(X, AlterTableBody::RenameColumn{ old: Y, new }));
 }
-cmd ::= ALTER TABLE fullname(X) DROP kwcolumn_opt nm(Y). {
-  self.ctx.stmt = Some(Stmt::AlterTable(X, AlterTableBody::DropColumn(Y)));
+cmd ::= ALTER TABLE fullname(X) DROP(D) kwcolumn_opt nm(Y). {
+  let name = Y;
+  let span = Span::from(&D).to(name.1);
+  self.ctx.require("DROP COLUMN", 3_035_000, span)?;
+  self.ctx.stmt = Some(Stmt::AlterTable(X, AlterTableBody::DropColumn(name)));
 }
 
 kwcolumn_opt ::= .
@@ -1334,7 +1513,7 @@ cmd ::= create_vtab(X) LP vtabarglist RP.  {
   }
   self.ctx.stmt = Some(stmt);
 }
-%type create_vtab {Stmt}
+%type create_vtab "Stmt<'i>"
 create_vtab(A) ::= createkw VIRTUAL TABLE ifnotexists(E)
                 fullname(X) USING nm(Z). {
     A = Stmt::CreateVirtualTable{ if_not_exists: E, tbl_name: X, module_name: Z, args: None };
@@ -1353,9 +1532,9 @@ anylist ::= anylist ANY.
 
 
 //////////////////////// COMMON TABLE EXPRESSIONS ////////////////////////////
-%type with {Option<With>}
-%type wqlist {Vec<CommonTableExpr>}
-%type wqitem {CommonTableExpr}
+%type with "Option<With<'i>>"
+%type wqlist "Vec<CommonTableExpr<'i>>"
+%type wqitem "CommonTableExpr<'i>"
 // %destructor wqitem {sqlite3CteDelete(pParse->db, $$);} // not reachable
 
 with(A) ::= . { A = None; }
@@ -1365,8 +1544,14 @@ with(A) ::= WITH RECURSIVE wqlist(W).    { A = Some(With{ recursive: true, ctes:
 
 %type wqas {Materialized}
 wqas(A)   ::= AS.                  {A = Materialized::Any;}
-wqas(A)   ::= AS MATERIALIZED.     {A = Materialized::Yes;}
-wqas(A)   ::= AS NOT MATERIALIZED. {A = Materialized::No;}
+wqas(A)   ::= AS MATERIALIZED.     {
+  self.ctx.require("MATERIALIZED", 3_035_000, yyspan)?;
+  A = Materialized::Yes;
+}
+wqas(A)   ::= AS NOT MATERIALIZED. {
+  self.ctx.require("NOT MATERIALIZED", 3_035_000, yyspan)?;
+  A = Materialized::No;
+}
 wqitem(A) ::= nm(X) eidlist_opt(Y) wqas(M) LP select(Z) RP. {
   A = CommonTableExpr{ tbl_name: X, columns: Y, materialized: M, select: Z }; /*A-overwrites-X*/
 }
@@ -1386,33 +1571,33 @@ wqlist(A) ::= wqlist(A) COMMA wqitem(X). {
 // tokens that may be output by the tokenizer except TK_SPACE and TK_ILLEGAL.
 //
 %ifndef SQLITE_OMIT_WINDOWFUNC
-%type windowdefn_list {Vec<WindowDef>}
+%type windowdefn_list "Vec<WindowDef<'i>>"
 windowdefn_list(A) ::= windowdefn(Z). { A = vec![Z]; }
 windowdefn_list(A) ::= windowdefn_list(A) COMMA windowdefn(Z). {
   let w = Z;
   A.push(w);
 }
 
-%type windowdefn {WindowDef}
+%type windowdefn "WindowDef<'i>"
 windowdefn(A) ::= nm(X) AS LP window(Y) RP. {
   A = WindowDef { name: X, window: Y};
 }
 
-%type window {Window}
+%type window "Window<'i>"
 
-%type frame_opt {Option<FrameClause>}
+%type frame_opt "Option<FrameClause<'i>>"
 
-%type filter_clause {Expr}
+%type filter_clause "Expr<'i>"
 
-%type over_clause {Over}
+%type over_clause "Over<'i>"
 
-%type filter_over {FunctionTail}
+%type filter_over "FunctionTail<'i>"
 
 %type range_or_rows {FrameMode}
 
-%type frame_bound {FrameBound}
-%type frame_bound_s {FrameBound}
-%type frame_bound_e {FrameBound}
+%type frame_bound "FrameBound<'i>"
+%type frame_bound_s "FrameBound<'i>"
+%type frame_bound_e "FrameBound<'i>"
 
 window(A) ::= PARTITION BY nexprlist(X) orderby_opt(Y) frame_opt(Z). {
   A = Window{ base: None,  partition_by: Some(X), order_by: Y, frame_clause: Z};
@@ -1468,8 +1653,11 @@ frame_exclude(A) ::= CURRENT ROW. { A = FrameExclude::CurrentRow; }
 frame_exclude(A) ::= GROUP.       { A = FrameExclude::Group; }
 frame_exclude(A) ::= TIES.        { A = FrameExclude::Ties; }
 
-%type window_clause {Vec<WindowDef>}
-window_clause(A) ::= WINDOW windowdefn_list(B). { A = B; }
+%type window_clause "Vec<WindowDef<'i>>"
+window_clause(A) ::= WINDOW windowdefn_list(B). {
+  self.ctx.require("window functions", 3_025_000, yyspan)?;
+  A = B;
+}
 
 filter_over(A) ::= filter_clause(F) over_clause(O). {
   A = FunctionTail{ filter_clause: Some(Box::new(F)), over_clause: Some(Box::new(O)) };
@@ -1482,11 +1670,47 @@ filter_over(A) ::= filter_clause(F). {
 }
 
 over_clause(A) ::= OVER LP window(Z) RP. {
+  self.ctx.require("window functions", 3_025_000, yyspan)?;
   A = Over::Window(Z);
 }
 over_clause(A) ::= OVER nm(Z). {
+  self.ctx.require("window functions", 3_025_000, yyspan)?;
   A = Over::Name(Z);
 }
 
-filter_clause(A) ::= FILTER LP WHERE expr(X) RP.  { A = X; }
+filter_clause(A) ::= FILTER LP WHERE expr(X) RP.  {
+  self.ctx.require("FILTER", 3_030_000, yyspan)?;
+  A = X;
+}
 %endif /* SQLITE_OMIT_WINDOWFUNC */
+
+//////////////////////////// Fragments ///////////////////////////////////////
+//
+// Not in SQLite grammar: an expression, a type, ... is parsed on its own
+// after one of the following tokens, which are never produced by the
+// tokenizer but only fed by Parser::parse_expr and friends.
+// They come after the tokens produced only in trivia mode, so that the
+// codes of the latter do not change.
+%token SPACE COMMENT.
+input ::= FRAGMENT_EXPR expr(X). {
+  self.ctx.fragment = Some(Fragment::Expr(X));
+}
+input ::= FRAGMENT_TYPE typetoken(X). {
+  let Some(ty) = X else {
+    return Err(ParserError::UnexpectedEof);
+  };
+  self.ctx.fragment = Some(Fragment::Type(ty));
+}
+input ::= FRAGMENT_COLUMN_DEF columnname(X) carglist(Y). {
+  let col = X;
+  let cd = ColumnDefinition{ col_name: col.0, col_type: col.1, constraints: Y };
+  let mut map = IndexMap::new();
+  ColumnDefinition::add_column(&mut map, cd)?;
+  self.ctx.fragment = map.pop().map(|(_, cd)| Fragment::ColumnDef(cd));
+}
+input ::= FRAGMENT_SORTED_COLUMNS sortlist(X). {
+  self.ctx.fragment = Some(Fragment::SortedColumns(X));
+}
+input ::= FRAGMENT_QUALIFIED_NAME fullname(X). {
+  self.ctx.fragment = Some(Fragment::QualifiedName(X));
+}
@@ -345,6 +345,7 @@ struct symbol {
                            ** union is the correct data type for this object */
   int bContent;            /* True if this symbol ever carries content - if
                            ** it is ever more than just syntax */
+  int nested;              /* True if the nesting of this NT is limited */
   /* The following fields are used by MULTITERMINALs only */
   int nsubsym;             /* Number of constituent symbols in the MULTI */
   struct symbol **subsym;  /* Array of constituent symbols */
@@ -2297,7 +2298,8 @@ enum e_state {
   WAITING_FOR_WILDCARD_ID,
   WAITING_FOR_CLASS_ID,
   WAITING_FOR_CLASS_TOKEN,
-  WAITING_FOR_TOKEN_NAME
+  WAITING_FOR_TOKEN_NAME,
+  WAITING_FOR_NESTED_ID
 };
 struct pstate {
   char *filename;       /* Name of the input file */
@@ -2618,6 +2620,8 @@ static void parseonetoken(struct pstate *psp)
           psp->state = WAITING_FOR_WILDCARD_ID;
         }else if( strcmp(x,"token_class")==0 ){
           psp->state = WAITING_FOR_CLASS_ID;
+        }else if( strcmp(x,"nested")==0 ){
+          psp->state = WAITING_FOR_NESTED_ID;
         }else{
           ErrorMsg(psp->filename,psp->tokenlineno,
             "Unknown declaration keyword: \"%%%s\".",x);
@@ -2794,6 +2798,17 @@ static void parseonetoken(struct pstate *psp)
         }
       }
       break;
+    case WAITING_FOR_NESTED_ID:
+      if( x[0]=='.' ){
+        psp->state = WAITING_FOR_DECL_OR_RULE;
+      }else if( !ISLOWER(x[0]) ){
+        ErrorMsg(psp->filename, psp->tokenlineno,
+          "%%nested argument \"%s\" should be a nonterminal", x);
+        psp->errorcnt++;
+      }else{
+        Symbol_new(x)->nested = 1;
+      }
+      break;
     case WAITING_FOR_CLASS_ID:
       if( !ISLOWER(x[0]) ){
         ErrorMsg(psp->filename, psp->tokenlineno,
@@ -4826,6 +4841,16 @@ void ReportTable(
   if( (i&3)!=0 ){ fprintf(out,"\n"); lineno++; }
   fprintf(out, "];\n"); lineno++;
 
+  /* Generate a table of the nonterminals whose nesting is limited (%nested)
+  */
+  fprintf(out, "#[rustfmt::skip]\n"); lineno++;
+  fprintf(out, "const YYNESTED: [bool; %d] = [\n", lemp->nsymbol); lineno++;
+  for(i=0; i<lemp->nsymbol; i++){
+    fprintf(out,"  /* %4d */ %s,\n",i, lemp->symbols[i]->nested ? "true" : "false");
+    lineno++;
+  }
+  fprintf(out, "];\n"); lineno++;
+
   tplt_xfer(lemp->name,in,out,&lineno);
 
   /* Generate a table containing a text string that describes every
@@ -172,6 +172,8 @@ pub struct yyStackEntry<'i> {
                             ** number for the token at this stack level */
     minor: YYMINORTYPE<'i>, /* The user-supplied minor token value.  This
                          ** is the value of the token  */
+    span: Span,            /* Location of the symbol in the input */
+    depth: usize,          /* Nesting of the YYNESTED symbols in this symbol */
 }
 
 /* The state of the parser is completely contained in an instance of
@@ -183,8 +185,11 @@ pub struct yyParser<'input> {
     yyhwm: usize, /* High-water mark of the stack */
     //#[cfg(not(feature = "YYNOERRORRECOVERY"))]
     yyerrcnt: i32, /* Shifts left before out of the error */
+    yymaxdepth: usize, /* Maximum nesting of the YYNESTED symbols */
 %%                               /* A place to hold %extra_context */
     yystack: Vec<yyStackEntry<'input>>, /* The parser's stack */
+    yystates: Vec<YYACTIONTYPE>, /* States overwritten by the reductions done for the current look-ahead, in reverse order */
+    yylow: usize, /* Stack entries below are not overwritten by these reductions */
 }
 
 use std::cmp::Ordering;
@@ -243,8 +248,8 @@ use log::{debug, log_enabled, Level::Debug};
 static TARGET: &str = "Parse";
 
 /* For tracing shifts, the names of all terminals and nonterminals
-** are required.  The following table supplies these names */
-#[cfg(any(feature = "YYCOVERAGE", not(feature = "NDEBUG")))]
+** are required.  The following table supplies these names
+** (also used by yySimulator::yy_reductions) */
 %%
 
 /* For tracing reduce actions, the names of all rules are required.
@@ -286,13 +291,25 @@ impl yyParser<'_> {
             #[cfg(feature = "YYTRACKMAXSTACKDEPTH")]
             yyhwm: 0,
             yystack: Vec::with_capacity(YYSTACKDEPTH),
+            yystates: Vec::new(),
+            yylow: 0,
             //#[cfg(not(feature = "YYNOERRORRECOVERY"))]
             yyerrcnt: -1,
+            yymaxdepth: usize::MAX,
 %%               /* Optional %extra_context store */
         };
         p.push(yyStackEntry::default());
         p
     }
+
+    /*
+    ** Limit the nesting of the YYNESTED symbols (defined by %nested):
+    ** the %stack_overflow code is executed instead of a reduction which
+    ** would exceed yymaxdepth.
+    */
+    pub fn yy_set_max_depth(&mut self, yymaxdepth: usize) {
+        self.yymaxdepth = yymaxdepth;
+    }
 }
 
 /*
@@ -390,8 +407,21 @@ fn ParseCoverage(/*FILE *out*/) -> i32 {
 */
 #[expect(non_snake_case)]
 fn yy_find_shift_action(
+    iLookAhead: YYCODETYPE, /* The look-ahead token */
+    stateno: YYACTIONTYPE,  /* Current state number */
+) -> YYACTIONTYPE {
+    yy_lookup_shift_action(iLookAhead, stateno, true)
+}
+
+/*
+** Same as yy_find_shift_action() but, when yyfallback is false, the
+** look-ahead token is never replaced by its fallback nor by the wildcard.
+*/
+#[expect(non_snake_case)]
+fn yy_lookup_shift_action(
     mut iLookAhead: YYCODETYPE, /* The look-ahead token */
     stateno: YYACTIONTYPE,      /* Current state number */
+    yyfallback: bool,           /* Whether fallback and wildcard apply */
 ) -> YYACTIONTYPE {
     if stateno > YY_MAX_SHIFT {
         return stateno;
@@ -409,7 +439,7 @@ fn yy_find_shift_action(
         assert!((iLookAhead as YYACTIONTYPE) < YYNTOKEN);
         i += iLookAhead as usize;
         if yy_lookahead[i] != iLookAhead {
-            if YYFALLBACK {
+            if YYFALLBACK && yyfallback {
                 let iFallback = yyFallback[iLookAhead as usize]; /* Fallback token */
                 if iFallback != 0 {
                     #[cfg(not(feature = "NDEBUG"))]
@@ -426,7 +456,7 @@ fn yy_find_shift_action(
                     continue;
                 }
             }
-            if YYWILDCARD > 0 {
+            if YYWILDCARD > 0 && yyfallback {
                 let j = i - iLookAhead as usize + YYWILDCARD as usize;
                 if yy_lookahead[j] == YYWILDCARD && iLookAhead > 0 {
                     #[cfg(not(feature = "NDEBUG"))]
@@ -478,9 +508,16 @@ fn yy_find_reduce_action(
     yy_action[i as usize]
 }
 
-        /******** Begin %stack_overflow code ******************************************
+/*
+** Return the error of a reduction exceeding yymaxdepth
+*/
+impl yyParser<'_> {
+    fn yy_stack_overflow(&self) -> ParseError {
+        /******** Begin %stack_overflow code ******************************************/
 %%
-        ******** End %stack_overflow code ********************************************/
+        /******** End %stack_overflow code ********************************************/
+    }
+}
 
 /*
 ** Print tracing information for a SHIFT action
@@ -533,7 +570,9 @@ impl<'input> yyParser<'input> {
         let yytos = yyStackEntry {
             stateno: yyNewState,
             major: yyMajor,
+            span: Span::from(&yyMinor),
             minor: YYMINORTYPE::yy0(yyMinor),
+            depth: 0,
         };
         self.push(yytos);
         self.yyTraceShift(yyNewState, "Shift");
@@ -554,6 +593,31 @@ static yyRuleInfoNRhs: [i8; YYNRULE] = [
 %%
 ];
 
+/*
+** Compute the location of the right-hand side of a rule from the locations
+** of its `-yysize` symbols on top of the stack.  Symbols which match no input
+** (empty rules) are skipped, unless there is nothing else.
+*/
+impl yyParser<'_> {
+    fn yy_rhs_span(&self, yysize: i8) -> Span {
+        let mut yyspan = Span::new(self[0].span.end, self[0].span.end);
+        let mut yyempty = true;
+        for i in yysize + 1..=0 {
+            let span = self[i].span;
+            if span.is_empty() {
+                continue;
+            }
+            if yyempty {
+                yyspan = span;
+                yyempty = false;
+            } else {
+                yyspan.end = span.end;
+            }
+        }
+        yyspan
+    }
+}
+
 /*
 ** Perform a reduce action and the shift that must immediately
 ** follow the reduce.
@@ -574,6 +638,22 @@ impl yyParser<'_> {
         let _ = yy_look_ahead;
         let _ = yy_lookahead_token;
 
+        let yygoto: YYCODETYPE = yyRuleInfoLhs[yyruleno as usize]; /* The next state */
+        let yysize: i8 = yyRuleInfoNRhs[yyruleno as usize];  /* Amount to pop the stack */
+
+        /* Nesting of the left-hand side, checked before any tree is built.
+        ** A rule with a single symbol on its right-hand side does not add a
+        ** level, as such rules may be skipped by the tables. */
+        let mut yydepth = (yysize + 1..=0).map(|i| self[i].depth).max().unwrap_or(0);
+        if YYNESTED[yygoto as usize] {
+            yydepth = if yysize == -1 { yydepth.max(1) } else { yydepth + 1 };
+            if yydepth > self.yymaxdepth {
+                return Err(self.yy_stack_overflow());
+            }
+        }
+
+        /* Location of the left-hand side, available to reduce actions */
+        let yyspan = self.yy_rhs_span(yysize);
         let yylhsminor: YYMINORTYPE<'_>;
         match yyruleno {
   /* Beginning here are the reduction cases.  A typical example
@@ -588,8 +668,6 @@ impl yyParser<'_> {
 %%
 /********** End reduce actions ************************************************/
         };
-        let yygoto: YYCODETYPE = yyRuleInfoLhs[yyruleno as usize]; /* The next state */
-        let yysize: i8 = yyRuleInfoNRhs[yyruleno as usize];  /* Amount to pop the stack */
         let yyact: YYACTIONTYPE = yy_find_reduce_action(self[yysize].stateno, yygoto); /* The next action */
 
         /* There are no SHIFTREDUCE actions on nonterminals because the table
@@ -604,12 +682,257 @@ impl yyParser<'_> {
             let yymsp = &mut self[0];
             yymsp.stateno = yyact;
             yymsp.major = yygoto;
+            yymsp.span = yyspan;
+            yymsp.depth = yydepth;
         }
         self.yyTraceShift(yyact, "... then shift");
         Ok(yyact)
     }
 }
 
+/*
+** Find the terminals which are accepted in the current state, possibly
+** after some reductions.  Terminals only accepted through their fallback
+** or the wildcard are skipped: the fallback or wildcard is reported instead.
+*/
+impl yyParser<'_> {
+    pub fn yy_expected_tokens(&self) -> Vec<YYCODETYPE> {
+        yy_expected(&self.yy_states())
+    }
+
+    /*
+    ** Same as yy_expected_tokens() but for %syntax_error code: the
+    ** reductions done for the erroneous look-ahead are ignored.
+    */
+    fn yy_expected_on_error(&self) -> Vec<YYCODETYPE> {
+        let yystates: Vec<YYACTIONTYPE> = (0..self.yylow.min(self.yyidx + 1))
+            .map(|i| self.yystack[i].stateno)
+            .chain(self.yystates.iter().rev().copied())
+            .collect();
+        yy_expected(&yystates)
+    }
+
+    /*
+    ** Return true if the terminals yymajors would be accepted, in that
+    ** order, from the current state.  The parser is left unchanged.
+    */
+    pub fn yy_accepts_all(&self, yymajors: &[YYCODETYPE]) -> bool {
+        /* Simulate on the top of the stack first: a simulation needing the
+        ** states below fails, and is done again on the whole stack */
+        let yyaccepts = |yylow: usize| {
+            let yystates = self.yy_states_from(yylow);
+            let mut yyoverlay = yyOverlay::new(&yystates);
+            yymajors
+                .iter()
+                .all(|&yymajor| yy_simulate(&mut yyoverlay, yymajor, true))
+        };
+        let yylow = (self.yyidx + 1).saturating_sub(YYSTACKDEPTH);
+        yyaccepts(yylow) || (yylow > 0 && yyaccepts(0))
+    }
+
+    fn yy_states(&self) -> Vec<YYACTIONTYPE> {
+        self.yy_states_from(0)
+    }
+
+    fn yy_states_from(&self, yylow: usize) -> Vec<YYACTIONTYPE> {
+        (yylow..=self.yyidx).map(|i| self.yystack[i].stateno).collect()
+    }
+}
+
+fn yy_expected(yystates: &[YYACTIONTYPE]) -> Vec<YYCODETYPE> {
+    (1..YYNTOKEN as YYCODETYPE)
+        .filter(|&yymajor| yy_simulate(&mut yyOverlay::new(yystates), yymajor, false))
+        .collect()
+}
+
+/*
+** State stack borrowed from a parser or a simulator, with the changes done
+** by a simulation kept aside: the borrowed states which are popped are only
+** counted out, and the states pushed are kept in an overlay.  So a
+** simulation costs the states it pops and pushes, not the whole stack.
+*/
+#[expect(non_camel_case_types)]
+struct yyOverlay<'a> {
+    yybase: &'a [YYACTIONTYPE],  /* Borrowed states */
+    yylen: usize,                /* Number of borrowed states still on the stack */
+    yypushed: Vec<YYACTIONTYPE>, /* States pushed above them */
+}
+
+impl<'a> yyOverlay<'a> {
+    fn new(yybase: &'a [YYACTIONTYPE]) -> Self {
+        Self {
+            yybase,
+            yylen: yybase.len(),
+            yypushed: Vec::new(),
+        }
+    }
+    fn len(&self) -> usize {
+        self.yylen + self.yypushed.len()
+    }
+    fn last(&self) -> Option<YYACTIONTYPE> {
+        self.yypushed
+            .last()
+            .or_else(|| self.yybase[..self.yylen].last())
+            .copied()
+    }
+    fn push(&mut self, stateno: YYACTIONTYPE) {
+        self.yypushed.push(stateno);
+    }
+    fn truncate(&mut self, len: usize) {
+        if len >= self.yylen {
+            self.yypushed.truncate(len - self.yylen);
+        } else {
+            self.yypushed.clear();
+            self.yylen = len;
+        }
+    }
+}
+
+/*
+** Simulate the parse of yymajor on a state stack: reduce as needed and
+** then shift yymajor.  Return false on syntax error.
+*/
+fn yy_simulate(yystates: &mut yyOverlay<'_>, yymajor: YYCODETYPE, yyfallback: bool) -> bool {
+    loop {
+        let Some(stateno) = yystates.last() else {
+            return false;
+        };
+        let mut yyact = yy_lookup_shift_action(yymajor, stateno, yyfallback);
+        if yyact < YY_MIN_REDUCE {
+            if yyact <= YY_MAX_SHIFTREDUCE {
+                if yyact > YY_MAX_SHIFT {
+                    yyact += YY_MIN_REDUCE - YY_MIN_SHIFTREDUCE;
+                }
+                yystates.push(yyact);
+                return true;
+            }
+            return yyact == YY_ACCEPT_ACTION;
+        }
+        let yyruleno = (yyact - YY_MIN_REDUCE) as usize;
+        let yysize = usize::from(yyRuleInfoNRhs[yyruleno].unsigned_abs());
+        if yysize >= yystates.len() {
+            return false;
+        }
+        yystates.truncate(yystates.len() - yysize);
+        let Some(stateno) = yystates.last() else {
+            return false;
+        };
+        yystates.push(yy_find_reduce_action(stateno, yyRuleInfoLhs[yyruleno]));
+    }
+}
+
+/*
+** Parser driven by the action tables only: no reduce action is executed,
+** so no value is built.  Only syntax errors are detected.
+*/
+#[derive(Clone)]
+#[expect(non_camel_case_types)]
+pub struct yySimulator {
+    yystates: Vec<YYACTIONTYPE>, /* State stack */
+}
+
+impl Default for yySimulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl yySimulator {
+    pub fn new() -> Self {
+        Self {
+            yystates: vec![0],
+        }
+    }
+
+    /*
+    ** Simulate the parse of yymajor.  Return false on syntax error and then
+    ** leave the simulator unchanged.
+    */
+    pub fn yy_parse(&mut self, yymajor: YYCODETYPE) -> bool {
+        let mut yyoverlay = yyOverlay::new(&self.yystates);
+        if !yy_simulate(&mut yyoverlay, yymajor, true) {
+            return false;
+        }
+        let yyOverlay { yylen, yypushed, .. } = yyoverlay;
+        self.yystates.truncate(yylen);
+        self.yystates.extend(yypushed);
+        true
+    }
+
+    pub fn yy_expected_tokens(&self) -> Vec<YYCODETYPE> {
+        yy_expected(&self.yystates)
+    }
+
+    /*
+    ** Whether yymajor is accepted as itself: not replaced by its fallback
+    ** nor by the wildcard.
+    */
+    pub fn yy_accepts_exactly(&self, yymajor: YYCODETYPE) -> bool {
+        yy_simulate(&mut yyOverlay::new(&self.yystates), yymajor, false)
+    }
+
+    /*
+    ** Simulate the parse of yymajors, in that order, and return the names of
+    ** the nonterminals reduced on the way, with the range of yymajors indexes
+    ** they cover (None for the symbols already on the stack).  Return None
+    ** on syntax error.  The simulator is left unchanged.
+    */
+    #[expect(clippy::type_complexity)]
+    pub fn yy_reductions(
+        &self,
+        yymajors: &[YYCODETYPE],
+    ) -> Option<Vec<(&'static str, std::ops::Range<Option<usize>>)>> {
+        let mut yystates = self.yystates.clone();
+        let mut yyranges = vec![None..None; yystates.len()];
+        let mut yyreduced = Vec::new();
+        for (i, &yymajor) in yymajors.iter().enumerate() {
+            loop {
+                let stateno = *yystates.last()?;
+                let mut yyact = yy_lookup_shift_action(yymajor, stateno, true);
+                if yyact < YY_MIN_REDUCE {
+                    if yyact > YY_MAX_SHIFTREDUCE {
+                        if yyact != YY_ACCEPT_ACTION {
+                            return None;
+                        }
+                    } else {
+                        if yyact > YY_MAX_SHIFT {
+                            yyact += YY_MIN_REDUCE - YY_MIN_SHIFTREDUCE;
+                        }
+                        yystates.push(yyact);
+                        yyranges.push(Some(i)..Some(i + 1));
+                    }
+                    break;
+                }
+                let yyruleno = (yyact - YY_MIN_REDUCE) as usize;
+                let yysize = usize::from(yyRuleInfoNRhs[yyruleno].unsigned_abs());
+                if yysize >= yystates.len() {
+                    return None;
+                }
+                let yylen = yystates.len() - yysize;
+                let yyrange = if yysize == 0 {
+                    Some(i)..Some(i)
+                } else {
+                    yyranges[yylen].start..yyranges[yyranges.len() - 1].end
+                };
+                yystates.truncate(yylen);
+                yyranges.truncate(yylen);
+                let yygoto = yyRuleInfoLhs[yyruleno];
+                yystates.push(yy_find_reduce_action(yystates[yylen - 1], yygoto));
+                yyranges.push(yyrange.clone());
+                yyreduced.push((yyTokenName[yygoto as usize], yyrange));
+            }
+        }
+        Some(yyreduced)
+    }
+
+    pub fn yy_accepts_all(&self, yymajors: &[YYCODETYPE]) -> bool {
+        let mut yyoverlay = yyOverlay::new(&self.yystates);
+        yymajors
+            .iter()
+            .all(|&yymajor| yy_simulate(&mut yyoverlay, yymajor, true))
+    }
+}
+
 /*
 ** The following code executes when the parse fails
 */
@@ -706,6 +1029,8 @@ impl<'input> yyParser<'input> {
             yyendofinput = yymajor == 0;
         }
 
+        self.yystates.clear();
+        self.yylow = self.yyidx + 1;
         let mut yyact: YYACTIONTYPE = self[0].stateno; /* The parser action. */
         #[cfg(not(feature = "NDEBUG"))]
         {
@@ -763,6 +1088,12 @@ impl<'input> yyParser<'input> {
                         break;
                     }
                 }
+                /* Keep the states which accept yymajor, in case of syntax error */
+                let yylow = self.shift(yyRuleInfoNRhs[yyruleno as usize] + 1);
+                while self.yylow > yylow {
+                    self.yylow -= 1;
+                    self.yystates.push(self.yystack[self.yylow].stateno);
+                }
                 yyact = self.yy_reduce(yyruleno, yymajor, &yyminor)?;
             } else if yyact <= YY_MAX_SHIFTREDUCE {
                 self.yy_shift(yyact, yymajor, yyminor);